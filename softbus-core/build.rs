@@ -9,8 +9,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     let proto_include = &["proto"];
     
+    // 输出到OUT_DIR，由src/rpc/protocol.rs通过include!引入
     prost_build::Config::new()
-        .out_dir("src/generated")
         .compile_protos(proto_files, proto_include)?;
     
     // 重新编译触发条件
@@ -2,7 +2,7 @@
 
 use async_trait::async_trait;
 use bytes::Bytes;
-use crate::{Error, Result, QosLevel};
+use crate::{DeviceId, Error, Result, QosLevel};
 
 /// 虚拟通道trait
 /// 
@@ -27,6 +27,13 @@ pub trait Channel: Send + Sync {
     /// 获取对端设备ID
     fn peer_device_id(&self) -> Option<String>;
 
+    /// 经过握手验证的对端设备，未验证时为`None`
+    ///
+    /// 与`peer_device_id`不同，这个ID由对端的身份公钥证明，不是对端自称的。
+    fn verified_peer(&self) -> Option<DeviceId> {
+        None
+    }
+
     /// 设置通道参数
     async fn set_options(&self, options: ChannelOptions) -> Result<()>;
 }
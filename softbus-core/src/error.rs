@@ -23,12 +23,18 @@ pub enum Error {
     #[error("超时")]
     Timeout,
 
+    #[error("已取消")]
+    Cancelled,
+
     #[error("认证失败: {0}")]
     Authentication(String),
 
     #[error("加密错误: {0}")]
     Encryption(String),
 
+    #[error("配对失败: {0}")]
+    Pairing(crate::security::pairing::PairingFailure),
+
     #[error("内部错误: {0}")]
     Internal(String),
 
@@ -1,17 +1,51 @@
 //! RPC客户端
 
-use std::sync::Arc;
-use bytes::Bytes;
-use serde::{Serialize, de::DeserializeOwned};
-use tokio::time::{timeout, Duration};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use dashmap::DashMap;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout, Duration};
+use uuid::Uuid;
 use crate::{Error, Result, Channel};
+use super::codec::{Codec, Decode, Encode};
+use super::context::{encode_deadline, AUTH_TOKEN_METADATA_KEY, DEADLINE_METADATA_KEY};
+use super::idempotency::IDEMPOTENCY_KEY_METADATA_KEY;
+use super::interceptor::{Interceptor, Invoker};
+use super::event::{route_event, EventSubscription, SubscriptionTable, DEFAULT_EVENT_BUFFER};
+use super::protocol::{FrameType, RpcRequest, RpcResponse};
+use super::retry::RetryPolicy;
+use super::stream::{
+    spawn_cleanup, ClientStreaming, Side, StreamReceiver, StreamSender, StreamState, StreamTable,
+    StreamWriter, DEFAULT_STREAM_WINDOW,
+};
+
+/// 等待响应的调用表：请求ID -> 响应发送端
+type PendingCalls = DashMap<String, oneshot::Sender<RpcResponse>>;
 
 /// RPC客户端
-/// 
-/// 用于发起远程过程调用
+///
+/// 用于发起远程过程调用。多个并发调用和流可共享同一通道，
+/// 后台读取任务按请求ID把响应和流帧分发给对应的调用方，把服务端推送的事件
+/// 分发给订阅者。
 pub struct RpcClient {
     channel: Arc<dyn Channel>,
     timeout: Duration,
+    retry: RetryPolicy,
+    /// 非幂等的`call`是否也按重试策略重试
+    retry_calls: bool,
+    /// 包裹普通调用的拦截器，按添加顺序由外到内
+    interceptors: Vec<Arc<dyn Interceptor>>,
+    codec: Codec,
+    auth_token: Option<String>,
+    stream_window: u32,
+    event_buffer: usize,
+    pending: Arc<PendingCalls>,
+    streams: Arc<StreamTable>,
+    subscriptions: Arc<SubscriptionTable>,
+    closed: Arc<AtomicBool>,
+    reader: OnceLock<JoinHandle<()>>,
 }
 
 impl RpcClient {
@@ -20,16 +54,96 @@ impl RpcClient {
         Self {
             channel,
             timeout: Duration::from_secs(30),
+            retry: RetryPolicy::default(),
+            retry_calls: false,
+            interceptors: Vec::new(),
+            codec: Codec::default(),
+            auth_token: None,
+            stream_window: DEFAULT_STREAM_WINDOW,
+            event_buffer: DEFAULT_EVENT_BUFFER,
+            pending: Arc::new(DashMap::new()),
+            streams: Arc::new(DashMap::new()),
+            subscriptions: Arc::new(DashMap::new()),
+            closed: Arc::new(AtomicBool::new(false)),
+            reader: OnceLock::new(),
         }
     }
 
     /// 设置超时时间
+    ///
+    /// 截止时间随请求发给服务端，到期后服务端也会取消处理器。
     pub fn with_timeout(mut self, timeout: Duration) -> Self {
         self.timeout = timeout;
         self
     }
 
+    /// 设置重试策略，默认为`RetryPolicy::default()`
+    ///
+    /// 策略用于`call_idempotent`，每次尝试单独计算超时。`call`默认不重试，见
+    /// `with_call_retry`。单向调用和流不重试。
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// 设置非幂等的`call`是否也按重试策略重试，默认不重试
+    ///
+    /// 重试时所有尝试携带同一个幂等键，由服务端在去重窗口内去重。
+    pub fn with_call_retry(mut self, enabled: bool) -> Self {
+        self.retry_calls = enabled;
+        self
+    }
+
+    /// 添加拦截器，先添加的拦截器在外层
+    ///
+    /// 拦截器包裹普通调用的每次尝试，单向调用和流不经过拦截器。
+    pub fn with_interceptor(mut self, interceptor: Arc<dyn Interceptor>) -> Self {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    /// 设置负载的编解码器，默认为bincode
+    ///
+    /// 编解码器编号随每个请求发送，服务端按同一格式应答。
+    pub fn with_codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// 设置认证令牌
+    ///
+    /// 令牌随每个请求发送，服务端用它验证本端设备的身份。
+    pub fn with_auth_token(mut self, token: impl Into<String>) -> Self {
+        self.auth_token = Some(token.into());
+        self
+    }
+
+    /// 设置每个流的接收窗口，即服务端无需等待即可连续发送的消息数
+    pub fn with_stream_window(mut self, window: u32) -> Self {
+        self.stream_window = window.max(1);
+        self
+    }
+
+    /// 设置每个订阅缓存的事件数
+    pub fn with_event_buffer(mut self, buffer: usize) -> Self {
+        self.event_buffer = buffer.max(1);
+        self
+    }
+
+    /// 当前等待响应的调用数量
+    pub fn in_flight(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// 当前未结束的流数量
+    pub fn open_streams(&self) -> usize {
+        self.streams.len()
+    }
+
     /// 调用远程方法
+    ///
+    /// 调用超时或返回的future在完成前被丢弃时，向服务端发送取消帧。默认不重试；
+    /// 通过`with_call_retry`启用后，所有尝试携带同一个幂等键，服务端只执行一次。
     pub async fn call<Req, Resp>(
         &self,
         service_name: &str,
@@ -37,29 +151,245 @@ impl RpcClient {
         request: Req,
     ) -> Result<Resp>
     where
-        Req: Serialize,
-        Resp: DeserializeOwned,
+        Req: Encode,
+        Resp: Decode,
+    {
+        let none = RetryPolicy::none();
+        let retry = if self.retry_calls { &self.retry } else { &none };
+        // 不重试时无需服务端去重
+        let idempotency_key = (retry.max_attempts() > 1).then(|| Uuid::new_v4().to_string());
+        self.call_with_retry(service_name, method_name, &request, retry, idempotency_key)
+            .await
+    }
+
+    /// 调用幂等方法
+    ///
+    /// 按重试策略自动重试，不携带幂等键，服务端每次尝试都会执行处理器。IDL中
+    /// 标记为`idempotent`的方法生成的代理使用此方法。
+    pub async fn call_idempotent<Req, Resp>(
+        &self,
+        service_name: &str,
+        method_name: &str,
+        request: Req,
+    ) -> Result<Resp>
+    where
+        Req: Encode,
+        Resp: Decode,
+    {
+        self.call_with_retry(service_name, method_name, &request, &self.retry, None)
+            .await
+    }
+
+    async fn call_with_retry<Req, Resp>(
+        &self,
+        service_name: &str,
+        method_name: &str,
+        request: &Req,
+        retry: &RetryPolicy,
+        idempotency_key: Option<String>,
+    ) -> Result<Resp>
+    where
+        Req: Encode,
+        Resp: Decode,
     {
         // 序列化请求
-        let request_bytes = bincode::serialize(&request)
-            .map_err(|e| Error::Serialization(e.to_string()))?;
+        let request_bytes = self.codec.encode(request)?;
+
+        let mut attempt = 1;
+        loop {
+            // 构造RPC请求消息，每次尝试使用新的请求ID
+            let mut rpc_request = self.build_request(service_name, method_name, request_bytes.clone());
+            rpc_request
+                .metadata
+                .insert(DEADLINE_METADATA_KEY.to_string(), encode_deadline(self.timeout));
+            if let Some(key) = &idempotency_key {
+                rpc_request
+                    .metadata
+                    .insert(IDEMPOTENCY_KEY_METADATA_KEY.to_string(), key.clone());
+            }
+
+            // 经过拦截器发送请求并等待响应
+            let result = Invoker::new(&self.interceptors, self)
+                .invoke(rpc_request)
+                .await
+                .and_then(RpcResponse::into_result);
+            match result {
+                // 解析响应
+                Ok(payload) => return self.codec.decode(&payload),
+                // 通道已关闭时重试没有意义
+                Err(e) if attempt < retry.max_attempts()
+                    && retry.is_retryable(&e)
+                    && !self.closed.load(Ordering::Acquire) =>
+                {
+                    let backoff = retry.backoff(attempt);
+                    tracing::debug!(
+                        "RPC call {}.{} failed on attempt {}, retrying in {:?}: {}",
+                        service_name, method_name, attempt, backoff, e
+                    );
+                    sleep(backoff).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// 调用单向方法：只发送请求，不等待响应
+    ///
+    /// 服务端处理失败时不会通知调用方，发送成功不代表对端已处理。
+    pub async fn notify<Req: Encode>(
+        &self,
+        service_name: &str,
+        method_name: &str,
+        request: Req,
+    ) -> Result<()> {
+        let request_bytes = self.codec.encode(&request)?;
+        let mut notification = RpcRequest::notification(service_name, method_name, request_bytes);
+        self.attach_metadata(&mut notification);
+        self.channel.send(notification.encode_bytes()).await
+    }
+
+    /// 订阅服务端推送的事件
+    ///
+    /// 订阅只在本端生效，服务端向所有连接推送事件。连接断开后订阅随之结束。
+    pub fn subscribe<E: Decode>(
+        &self,
+        service_name: &str,
+        event_name: &str,
+    ) -> EventSubscription<E> {
+        self.ensure_reader();
+
+        // 与普通调用相同：先登记再检查关闭标志
+        let (tx, rx) = mpsc::channel(self.event_buffer);
+        self.subscriptions
+            .entry((service_name.to_string(), event_name.to_string()))
+            .or_default()
+            .push(tx);
+        if self.closed.load(Ordering::Acquire) {
+            self.subscriptions.clear();
+        }
+        EventSubscription::new(rx)
+    }
+
+    /// 打开双向流
+    ///
+    /// `request`为方法的非流参数，随打开流的帧一起发送。流没有整体超时；
+    /// 服务端找不到方法等错误会在接收端的第一条消息中返回。
+    pub async fn open_stream<Req, In, Out>(
+        &self,
+        service_name: &str,
+        method_name: &str,
+        request: Req,
+    ) -> Result<(StreamSender<In>, StreamReceiver<Out>)>
+    where
+        Req: Encode,
+        In: Encode,
+        Out: Decode,
+    {
+        let request_bytes = self.codec.encode(&request)?;
+        let mut rpc_request = self.build_request(service_name, method_name, request_bytes);
+        rpc_request.frame_type = FrameType::StreamOpen as i32;
+        rpc_request.credit = self.stream_window;
+
+        self.ensure_reader();
 
-        // 构造RPC请求消息
-        let rpc_request = self.build_request(service_name, method_name, request_bytes)?;
+        // 与普通调用相同：先登记再检查关闭标志。服务端授予配额前发送端会等待
+        let (state, reader) = StreamState::register(
+            &self.streams,
+            &rpc_request,
+            Side::Client,
+            Arc::clone(&self.channel),
+            self.stream_window,
+            0,
+            self.stream_window,
+        );
+        let writer = StreamWriter::new(Arc::clone(&state));
+        if self.closed.load(Ordering::Acquire) {
+            state.abort(Error::Connection("RPC channel closed".to_string()));
+            state.remove();
+            return Err(Error::Connection("RPC channel closed".to_string()));
+        }
+
+        if let Err(e) = self.channel.send(rpc_request.encode_bytes()).await {
+            state.abort(Error::Connection("RPC channel closed".to_string()));
+            state.remove();
+            return Err(e);
+        }
+
+        Ok((StreamSender::new(writer), StreamReceiver::new(reader)))
+    }
+
+    /// 发起服务端流调用：发送一次请求，接收一组响应
+    pub async fn server_stream<Req, Resp>(
+        &self,
+        service_name: &str,
+        method_name: &str,
+        request: Req,
+    ) -> Result<StreamReceiver<Resp>>
+    where
+        Req: Encode,
+        Resp: Decode,
+    {
+        let (sender, receiver) = self
+            .open_stream::<Req, (), Resp>(service_name, method_name, request)
+            .await?;
+        sender.finish().await?;
+        Ok(receiver)
+    }
+
+    /// 发起客户端流调用：发送一组消息，接收单个响应
+    pub async fn client_stream<Req, Item, Resp>(
+        &self,
+        service_name: &str,
+        method_name: &str,
+        request: Req,
+    ) -> Result<ClientStreaming<Item, Resp>>
+    where
+        Req: Encode,
+        Item: Encode,
+        Resp: Decode,
+    {
+        let (sender, receiver) = self.open_stream(service_name, method_name, request).await?;
+        Ok(ClientStreaming::new(sender, receiver))
+    }
+
+    pub(crate) async fn send_request(&self, rpc_request: RpcRequest) -> Result<RpcResponse> {
+        self.ensure_reader();
 
-        // 发送请求
-        let response_bytes = timeout(self.timeout, async {
-            self.channel.send(rpc_request).await?;
-            self.channel.recv().await
+        // 先登记再检查关闭标志：读取任务先置位再清空调用表，两者不会错过
+        let (tx, rx) = oneshot::channel();
+        let mut guard = PendingGuard::register(
+            &self.pending,
+            &self.channel,
+            rpc_request.request_id.clone(),
+            tx,
+        );
+        if self.closed.load(Ordering::Acquire) {
+            return Err(Error::Connection("RPC channel closed".to_string()));
+        }
+
+        // 超时或调用被取消时，guard负责释放调用表中的槽位并通知服务端
+        timeout(self.timeout, async {
+            self.channel.send(rpc_request.encode_bytes()).await?;
+            guard.mark_sent();
+            rx.await
+                .map_err(|_| Error::Connection("RPC channel closed".to_string()))
         })
         .await
-        .map_err(|_| Error::Timeout)??;
-
-        // 解析响应
-        let response: Resp = bincode::deserialize(&response_bytes)
-            .map_err(|e| Error::Serialization(e.to_string()))?;
+        .map_err(|_| Error::Timeout)?
+    }
 
-        Ok(response)
+    /// 首次调用时启动后台读取任务
+    fn ensure_reader(&self) {
+        self.reader.get_or_init(|| {
+            tokio::spawn(read_responses(
+                Arc::clone(&self.channel),
+                Arc::clone(&self.pending),
+                Arc::clone(&self.streams),
+                Arc::clone(&self.subscriptions),
+                Arc::clone(&self.closed),
+            ))
+        });
     }
 
     fn build_request(
@@ -67,17 +397,175 @@ impl RpcClient {
         service_name: &str,
         method_name: &str,
         payload: Vec<u8>,
-    ) -> Result<Bytes> {
-        // TODO: 使用protobuf构造请求消息
-        // 这里暂时使用简单的格式
-        let mut request = Vec::new();
-        request.extend_from_slice(service_name.as_bytes());
-        request.push(0); // 分隔符
-        request.extend_from_slice(method_name.as_bytes());
-        request.push(0); // 分隔符
-        request.extend_from_slice(&payload);
-        
-        Ok(Bytes::from(request))
+    ) -> RpcRequest {
+        let mut request = RpcRequest {
+            request_id: Uuid::new_v4().to_string(),
+            service_name: service_name.to_string(),
+            method_name: method_name.to_string(),
+            payload,
+            metadata: HashMap::new(),
+            ..Default::default()
+        };
+        self.attach_metadata(&mut request);
+        request
+    }
+
+    /// 写入编解码器编号和认证令牌
+    fn attach_metadata(&self, request: &mut RpcRequest) {
+        self.codec.write_metadata(&mut request.metadata);
+        if let Some(token) = &self.auth_token {
+            request
+                .metadata
+                .insert(AUTH_TOKEN_METADATA_KEY.to_string(), token.clone());
+        }
+    }
+}
+
+impl Drop for RpcClient {
+    fn drop(&mut self) {
+        if let Some(reader) = self.reader.get() {
+            reader.abort();
+        }
+        // 读取任务已停止，未结束的流不会再收到任何帧
+        for entry in self.streams.iter() {
+            entry.value().abort(Error::Connection("RPC client dropped".to_string()));
+        }
+        self.streams.clear();
+        self.subscriptions.clear();
+    }
+}
+
+/// 后台读取任务：按请求ID把响应路由给等待中的调用方，流帧路由给对应的流，
+/// 事件路由给订阅者
+async fn read_responses(
+    channel: Arc<dyn Channel>,
+    pending: Arc<PendingCalls>,
+    streams: Arc<StreamTable>,
+    subscriptions: Arc<SubscriptionTable>,
+    closed: Arc<AtomicBool>,
+) {
+    loop {
+        let data = match channel.recv().await {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::debug!("RPC client reader stopped: {}", e);
+                break;
+            }
+        };
+
+        let response = match RpcResponse::decode_bytes(&data) {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::warn!("Dropping malformed RPC response: {}", e);
+                continue;
+            }
+        };
+
+        match response.frame_type() {
+            FrameType::Unary => {}
+            FrameType::Notification => {
+                route_event(&subscriptions, response);
+                continue;
+            }
+            _ => {
+                route_stream_frame(&streams, response).await;
+                continue;
+            }
+        }
+
+        match pending.remove(&response.request_id) {
+            Some((_, tx)) => {
+                let _ = tx.send(response);
+            }
+            None => {
+                // 调用已超时或被取消
+                tracing::debug!("Dropping stale RPC response: {}", response.request_id);
+            }
+        }
+    }
+
+    // 通道已断开，唤醒所有等待中的调用、流和订阅者
+    closed.store(true, Ordering::Release);
+    pending.clear();
+    for entry in streams.iter() {
+        entry.value().abort(Error::Connection("RPC channel closed".to_string()));
+    }
+    streams.clear();
+    subscriptions.clear();
+}
+
+/// 处理服务端发来的流帧
+async fn route_stream_frame(streams: &StreamTable, response: RpcResponse) {
+    let Some(state) = streams.get(&response.request_id).map(|entry| Arc::clone(entry.value())) else {
+        tracing::debug!("Dropping frame for unknown RPC stream: {}", response.request_id);
+        return;
+    };
+
+    match response.frame_type() {
+        FrameType::StreamData => {
+            if !state.on_data(response.payload) {
+                tracing::warn!("RPC stream {} exceeded its credit, cancelling", state.request_id());
+                let _ = state.send_cancel().await;
+            }
+        }
+        FrameType::StreamCredit => state.on_credit(response.credit),
+        FrameType::StreamEnd => {
+            // 服务端处理结束，整个流随之结束
+            state.close_send();
+            state.on_end(response.into_result().err());
+            state.remove();
+        }
+        FrameType::StreamCancel => {
+            state.abort(Error::Cancelled);
+            state.remove();
+        }
+        FrameType::Unary | FrameType::StreamOpen | FrameType::Notification | FrameType::Cancel => {
+            tracing::debug!("Ignoring unexpected RPC stream frame from server");
+        }
+    }
+}
+
+/// 调用表槽位守卫，离开作用域时移除对应的请求ID
+///
+/// 请求已发出但尚未收到响应时，向服务端发送取消帧。
+struct PendingGuard<'a> {
+    pending: &'a PendingCalls,
+    channel: &'a Arc<dyn Channel>,
+    request_id: String,
+    sent: bool,
+}
+
+impl<'a> PendingGuard<'a> {
+    fn register(
+        pending: &'a PendingCalls,
+        channel: &'a Arc<dyn Channel>,
+        request_id: String,
+        tx: oneshot::Sender<RpcResponse>,
+    ) -> Self {
+        pending.insert(request_id.clone(), tx);
+        Self {
+            pending,
+            channel,
+            request_id,
+            sent: false,
+        }
+    }
+
+    fn mark_sent(&mut self) {
+        self.sent = true;
+    }
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        // 读取任务取走槽位说明响应已到达或通道已断开，无需取消
+        if self.pending.remove(&self.request_id).is_some() && self.sent {
+            let channel = Arc::clone(self.channel);
+            let cancel = RpcRequest::cancel(std::mem::take(&mut self.request_id));
+            spawn_cleanup(async move {
+                let _ = channel.send(cancel.encode_bytes()).await;
+            });
+        }
     }
 }
 
@@ -0,0 +1,175 @@
+//! 负载编解码
+//!
+//! 请求参数、返回值、流消息和事件都按`Codec`编码。客户端把编解码器编号写入
+//! 请求元数据，服务端按调用方的格式解码请求并编码响应；没有编号的请求按
+//! bincode处理，与C#/C++运行时及旧版本客户端兼容。
+//!
+//! serde类型支持bincode和JSON；prost消息包装在`Proto`中，只支持protobuf。
+
+use std::collections::HashMap;
+use serde::{Serialize, de::DeserializeOwned};
+use crate::{Error, Result};
+
+/// 元数据中编解码器编号的键
+pub const CODEC_METADATA_KEY: &str = "softbus-codec";
+
+/// 负载编解码格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Codec {
+    /// bincode 1.x，默认格式
+    #[default]
+    Bincode,
+    /// serde_json
+    Json,
+    /// prost消息
+    Protobuf,
+}
+
+impl Codec {
+    /// 写入元数据的编号
+    pub fn id(self) -> &'static str {
+        match self {
+            Codec::Bincode => "bincode",
+            Codec::Json => "json",
+            Codec::Protobuf => "protobuf",
+        }
+    }
+
+    /// 按编号查找编解码器
+    pub fn from_id(id: &str) -> Result<Self> {
+        match id {
+            "bincode" => Ok(Codec::Bincode),
+            "json" => Ok(Codec::Json),
+            "protobuf" => Ok(Codec::Protobuf),
+            other => Err(Error::Serialization(format!("unsupported codec `{}`", other))),
+        }
+    }
+
+    /// 读取元数据中的编解码器，没有编号时为bincode
+    pub(crate) fn from_metadata(metadata: &HashMap<String, String>) -> Result<Self> {
+        metadata
+            .get(CODEC_METADATA_KEY)
+            .map_or(Ok(Codec::Bincode), |id| Codec::from_id(id))
+    }
+
+    /// 把编号写入元数据
+    pub(crate) fn write_metadata(self, metadata: &mut HashMap<String, String>) {
+        metadata.insert(CODEC_METADATA_KEY.to_string(), self.id().to_string());
+    }
+
+    /// 编码一个值
+    pub fn encode<T: Encode + ?Sized>(self, value: &T) -> Result<Vec<u8>> {
+        value.encode(self)
+    }
+
+    /// 解码一个值
+    pub fn decode<T: Decode>(self, bytes: &[u8]) -> Result<T> {
+        T::decode(self, bytes)
+    }
+}
+
+/// 可按`Codec`编码的负载
+pub trait Encode {
+    fn encode(&self, codec: Codec) -> Result<Vec<u8>>;
+}
+
+/// 可按`Codec`解码的负载
+pub trait Decode: Sized {
+    fn decode(codec: Codec, bytes: &[u8]) -> Result<Self>;
+}
+
+impl<T: Serialize + ?Sized> Encode for T {
+    fn encode(&self, codec: Codec) -> Result<Vec<u8>> {
+        match codec {
+            Codec::Bincode => bincode::serialize(self).map_err(|e| Error::Serialization(e.to_string())),
+            Codec::Json => serde_json::to_vec(self).map_err(|e| Error::Serialization(e.to_string())),
+            Codec::Protobuf => Err(not_a_message::<T>()),
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Decode for T {
+    fn decode(codec: Codec, bytes: &[u8]) -> Result<Self> {
+        match codec {
+            Codec::Bincode => bincode::deserialize(bytes).map_err(|e| Error::Serialization(e.to_string())),
+            Codec::Json => serde_json::from_slice(bytes).map_err(|e| Error::Serialization(e.to_string())),
+            Codec::Protobuf => Err(not_a_message::<T>()),
+        }
+    }
+}
+
+/// prost消息，只能按protobuf编解码
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Proto<T>(pub T);
+
+impl<T: prost::Message> Encode for Proto<T> {
+    fn encode(&self, codec: Codec) -> Result<Vec<u8>> {
+        match codec {
+            Codec::Protobuf => Ok(self.0.encode_to_vec()),
+            other => Err(message_only(other)),
+        }
+    }
+}
+
+impl<T: prost::Message + Default> Decode for Proto<T> {
+    fn decode(codec: Codec, bytes: &[u8]) -> Result<Self> {
+        match codec {
+            Codec::Protobuf => <T as prost::Message>::decode(bytes)
+                .map(Proto)
+                .map_err(|e| Error::Serialization(e.to_string())),
+            other => Err(message_only(other)),
+        }
+    }
+}
+
+fn not_a_message<T: ?Sized>() -> Error {
+    Error::Serialization(format!(
+        "`{}` is not a protobuf message, wrap prost messages in `Proto`",
+        std::any::type_name::<T>()
+    ))
+}
+
+fn message_only(codec: Codec) -> Error {
+    Error::Serialization(format!("prost messages cannot be encoded as {}", codec.id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::protocol::RpcRequest;
+
+    #[test]
+    fn test_serde_codecs_roundtrip() {
+        let value = ("front".to_string(), vec![1u8, 2, 3]);
+        for codec in [Codec::Bincode, Codec::Json] {
+            let bytes = codec.encode(&value).unwrap();
+            assert_eq!(codec.decode::<(String, Vec<u8>)>(&bytes).unwrap(), value);
+        }
+        assert_eq!(Codec::Json.encode(&41).unwrap(), b"41");
+        assert!(matches!(Codec::Protobuf.encode(&41), Err(Error::Serialization(_))));
+    }
+
+    #[test]
+    fn test_protobuf_codec_roundtrip() {
+        let message = Proto(RpcRequest {
+            service_name: "CameraService".to_string(),
+            ..Default::default()
+        });
+        let bytes = Codec::Protobuf.encode(&message).unwrap();
+        assert_eq!(Codec::Protobuf.decode::<Proto<RpcRequest>>(&bytes).unwrap(), message);
+        assert!(matches!(Codec::Json.encode(&message), Err(Error::Serialization(_))));
+    }
+
+    #[test]
+    fn test_codec_ids() {
+        for codec in [Codec::Bincode, Codec::Json, Codec::Protobuf] {
+            assert_eq!(Codec::from_id(codec.id()).unwrap(), codec);
+        }
+        assert!(Codec::from_id("msgpack").is_err());
+
+        let mut metadata = HashMap::new();
+        assert_eq!(Codec::from_metadata(&metadata).unwrap(), Codec::Bincode);
+        Codec::Json.write_metadata(&mut metadata);
+        assert_eq!(Codec::from_metadata(&metadata).unwrap(), Codec::Json);
+    }
+}
@@ -0,0 +1,244 @@
+//! 服务端调用上下文
+//!
+//! 服务端为每次调用构造`CallContext`，携带请求ID、请求元数据、调用方身份和
+//! 通道的QoS级别，供处理器按调用方授权；处理器按其中的编解码器解码请求、编码响应。
+//!
+//! 客户端把调用的绝对截止时间写入请求元数据，放弃等待时发送`CANCEL`帧。
+//! 截止时间到达或客户端取消时，取消令牌被触发，处理器可以提前结束耗时操作。
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use crate::{DeviceId, QosLevel};
+use super::codec::Codec;
+use super::protocol::RpcRequest;
+
+/// 元数据中截止时间的键，值为自UNIX纪元起的毫秒数
+pub const DEADLINE_METADATA_KEY: &str = "softbus-deadline-ms";
+
+/// 元数据中认证令牌的键，服务端验证后从交给处理器的元数据中移除
+pub const AUTH_TOKEN_METADATA_KEY: &str = "softbus-auth-token";
+
+/// 取消令牌，克隆体共享同一取消状态
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<TokenInner>,
+}
+
+#[derive(Debug, Default)]
+struct TokenInner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 触发取消，唤醒所有等待者
+    pub fn cancel(&self) {
+        if !self.inner.cancelled.swap(true, Ordering::AcqRel) {
+            self.inner.notify.notify_waiters();
+        }
+    }
+
+    /// 是否已取消
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Acquire)
+    }
+
+    /// 等待取消
+    pub async fn cancelled(&self) {
+        loop {
+            // 先登记再检查，避免错过检查之后发出的通知
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// 一次RPC调用的服务端上下文
+#[derive(Debug, Clone, Default)]
+pub struct CallContext {
+    request_id: String,
+    metadata: HashMap<String, String>,
+    peer: Option<DeviceId>,
+    qos_level: Option<QosLevel>,
+    codec: Codec,
+    deadline: Option<Instant>,
+    token: CancellationToken,
+}
+
+impl CallContext {
+    /// 创建上下文，不限时且没有调用方身份
+    pub fn new(request_id: impl Into<String>) -> Self {
+        Self {
+            request_id: request_id.into(),
+            ..Default::default()
+        }
+    }
+
+    /// 按请求构造上下文：请求ID、元数据以及元数据中的编解码器和截止时间
+    pub(crate) fn from_request(request: &RpcRequest) -> Self {
+        let mut metadata = request.metadata.clone();
+        metadata.remove(AUTH_TOKEN_METADATA_KEY);
+        Self {
+            request_id: request.request_id.clone(),
+            codec: Codec::from_metadata(&request.metadata).unwrap_or_default(),
+            deadline: decode_deadline(&request.metadata),
+            metadata,
+            ..Default::default()
+        }
+    }
+
+    /// 设置请求元数据
+    pub fn with_metadata(mut self, metadata: HashMap<String, String>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// 设置已认证的调用方设备
+    pub fn with_peer(mut self, peer: DeviceId) -> Self {
+        self.peer = Some(peer);
+        self
+    }
+
+    /// 设置请求所在通道的QoS级别
+    pub fn with_qos_level(mut self, qos_level: QosLevel) -> Self {
+        self.qos_level = Some(qos_level);
+        self
+    }
+
+    /// 设置调用方使用的编解码器
+    pub fn with_codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// 设置截止时间
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// 请求ID，单向调用为空
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    /// 请求元数据，不含认证令牌
+    pub fn metadata(&self) -> &HashMap<String, String> {
+        &self.metadata
+    }
+
+    /// 经握手验证或通过`AuthManager`令牌认证的调用方设备，未认证时为`None`
+    pub fn peer(&self) -> Option<&DeviceId> {
+        self.peer.as_ref()
+    }
+
+    /// 请求所在通道的QoS级别，不经通道处理的请求为`None`
+    pub fn qos_level(&self) -> Option<QosLevel> {
+        self.qos_level
+    }
+
+    /// 调用方使用的编解码器，请求和响应都按它编码
+    pub fn codec(&self) -> Codec {
+        self.codec
+    }
+
+    /// 调用的截止时间
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// 距截止时间的剩余时长，已超时为零
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// 调用的取消令牌，客户端取消或截止时间到达时触发
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    /// 调用是否已被取消
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+}
+
+/// 把相对超时编码为元数据中的绝对截止时间
+pub(crate) fn encode_deadline(timeout: Duration) -> String {
+    let deadline = SystemTime::now() + timeout;
+    let millis = deadline
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    millis.to_string()
+}
+
+/// 解析元数据中的截止时间并换算到本地单调时钟，缺失或格式错误时返回`None`
+fn decode_deadline(metadata: &HashMap<String, String>) -> Option<Instant> {
+    let millis: u64 = metadata.get(DEADLINE_METADATA_KEY)?.parse().ok()?;
+    let deadline = UNIX_EPOCH + Duration::from_millis(millis);
+    let remaining = deadline
+        .duration_since(SystemTime::now())
+        .unwrap_or_default();
+    Some(Instant::now() + remaining)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(key: &str, value: String) -> RpcRequest {
+        RpcRequest {
+            request_id: "req-1".to_string(),
+            metadata: HashMap::from([(key.to_string(), value)]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_deadline_roundtrip() {
+        let ctx = CallContext::from_request(&request(DEADLINE_METADATA_KEY, encode_deadline(Duration::from_secs(10))));
+        let remaining = ctx.remaining().unwrap();
+        assert!(remaining > Duration::from_secs(9) && remaining <= Duration::from_secs(10));
+        assert_eq!(ctx.request_id(), "req-1");
+
+        let expired = CallContext::from_request(&request(DEADLINE_METADATA_KEY, "1".to_string()));
+        assert_eq!(expired.remaining(), Some(Duration::ZERO));
+
+        let invalid = CallContext::from_request(&request(DEADLINE_METADATA_KEY, "soon".to_string()));
+        assert_eq!(invalid.deadline(), None);
+    }
+
+    #[test]
+    fn test_auth_token_is_hidden_from_handlers() {
+        let ctx = CallContext::from_request(&request(AUTH_TOKEN_METADATA_KEY, "secret".to_string()));
+        assert!(ctx.metadata().is_empty());
+        assert!(ctx.peer().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_wakes_waiters() {
+        let ctx = CallContext::default();
+        let token = ctx.cancellation().clone();
+        let waiter = tokio::spawn(async move { token.cancelled().await });
+
+        tokio::task::yield_now().await;
+        ctx.cancellation().cancel();
+        waiter.await.unwrap();
+        assert!(ctx.is_cancelled());
+        // 已取消的令牌立即返回
+        ctx.cancellation().cancelled().await;
+    }
+}
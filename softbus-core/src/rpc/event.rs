@@ -0,0 +1,108 @@
+//! 单向通知与事件订阅
+//!
+//! 通知帧不携带请求ID，也不会有响应。客户端用它调用单向方法，服务端用它
+//! 向所有连接的客户端推送事件；客户端按服务名和事件名把事件分发给订阅者。
+//! 事件按服务端的编解码器编码，编号写在通知帧的元数据中。
+
+use std::marker::PhantomData;
+use bytes::Bytes;
+use dashmap::DashMap;
+use tokio::sync::mpsc;
+use crate::Result;
+use super::codec::{Codec, Decode};
+use super::protocol::RpcResponse;
+
+/// 每个订阅默认缓存的事件数，订阅者处理不过来时丢弃新事件
+pub const DEFAULT_EVENT_BUFFER: usize = 64;
+
+/// 待解码的事件及其编解码器
+pub(crate) type EventPayload = (Codec, Bytes);
+
+/// 事件订阅表：(服务名, 事件名) -> 订阅者
+pub(crate) type SubscriptionTable = DashMap<(String, String), Vec<mpsc::Sender<EventPayload>>>;
+
+/// 把服务端推送的事件分发给订阅者，顺带清理已取消的订阅
+pub(crate) fn route_event(subscriptions: &SubscriptionTable, event: RpcResponse) {
+    let key = (event.service_name, event.event_name);
+    let Some(mut subscribers) = subscriptions.get_mut(&key) else {
+        tracing::debug!("Dropping event without subscribers: {}.{}", key.0, key.1);
+        return;
+    };
+    let codec = match Codec::from_metadata(&event.metadata) {
+        Ok(codec) => codec,
+        Err(e) => {
+            tracing::warn!("Dropping event {}.{}: {}", key.0, key.1, e);
+            return;
+        }
+    };
+
+    let payload = Bytes::from(event.payload);
+    subscribers.retain(|subscriber| match subscriber.try_send((codec, payload.clone())) {
+        Ok(()) => true,
+        Err(mpsc::error::TrySendError::Full(_)) => {
+            tracing::warn!("Event subscriber of {}.{} is lagging, dropping event", key.0, key.1);
+            true
+        }
+        Err(mpsc::error::TrySendError::Closed(_)) => false,
+    });
+
+    if subscribers.is_empty() {
+        drop(subscribers);
+        subscriptions.remove_if(&key, |_, subscribers| subscribers.is_empty());
+    }
+}
+
+/// 类型化的事件订阅，事件按通知帧标明的编解码器解码
+///
+/// 丢弃即取消订阅。
+pub struct EventSubscription<E> {
+    rx: mpsc::Receiver<EventPayload>,
+    _marker: PhantomData<fn() -> E>,
+}
+
+impl<E: Decode> EventSubscription<E> {
+    pub(crate) fn new(rx: mpsc::Receiver<EventPayload>) -> Self {
+        Self {
+            rx,
+            _marker: PhantomData,
+        }
+    }
+
+    /// 接收下一个事件，连接断开后返回`None`
+    pub async fn next(&mut self) -> Option<Result<E>> {
+        let (codec, payload) = self.rx.recv().await?;
+        Some(codec.decode(&payload))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_route_event_drops_cancelled_subscribers() {
+        let subscriptions = SubscriptionTable::new();
+        let key = ("CameraService".to_string(), "disconnected".to_string());
+        let (tx, rx) = mpsc::channel(1);
+        let (closed_tx, _) = mpsc::channel(1);
+        subscriptions.insert(key.clone(), vec![tx, closed_tx]);
+
+        let mut subscription = EventSubscription::<u32>::new(rx);
+        let event = |value: u32| {
+            let mut event =
+                RpcResponse::notification("CameraService", "disconnected", Codec::Json.encode(&value).unwrap());
+            Codec::Json.write_metadata(&mut event.metadata);
+            event
+        };
+        route_event(&subscriptions, event(7));
+        // 缓冲区已满，事件被丢弃但订阅保留
+        route_event(&subscriptions, event(8));
+
+        assert_eq!(subscriptions.get(&key).unwrap().len(), 1);
+        assert_eq!(subscription.next().await.unwrap().unwrap(), 7);
+
+        drop(subscription);
+        route_event(&subscriptions, event(9));
+        assert!(subscriptions.is_empty());
+    }
+}
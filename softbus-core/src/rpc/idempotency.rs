@@ -0,0 +1,255 @@
+//! 服务端按幂等键去重
+//!
+//! 客户端重试非幂等调用时，所有尝试携带同一个幂等键。服务端在去重窗口内记住
+//! 每个键的响应：重复的请求直接得到第一次执行的结果，第一次执行尚未结束时
+//! 等待它完成，而不是再执行一次处理器。
+//!
+//! 键只在同一调用方对同一方法的请求之间有效，一个调用方重用或重放的键不会
+//! 得到为其他调用方或其他方法记住的响应。记住的键数量有上限。
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use dashmap::DashMap;
+use tokio::sync::OnceCell;
+use tokio::time::Instant;
+use crate::DeviceId;
+use super::protocol::{RpcErrorCode, RpcResponse};
+
+/// 元数据中幂等键的键
+pub const IDEMPOTENCY_KEY_METADATA_KEY: &str = "softbus-idempotency-key";
+
+/// 默认的去重窗口
+pub(crate) const DEFAULT_IDEMPOTENCY_WINDOW: Duration = Duration::from_secs(60);
+
+/// 默认最多记住的键数量
+pub(crate) const DEFAULT_IDEMPOTENCY_CAPACITY: usize = 10_000;
+
+/// 幂等键及其作用域：调用方和方法
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct IdempotencyKey {
+    peer: DeviceId,
+    service_name: String,
+    method_name: String,
+    key: String,
+}
+
+impl IdempotencyKey {
+    pub(crate) fn new(peer: DeviceId, service_name: &str, method_name: &str, key: &str) -> Self {
+        Self {
+            peer,
+            service_name: service_name.to_string(),
+            method_name: method_name.to_string(),
+            key: key.to_string(),
+        }
+    }
+}
+
+struct Entry {
+    created: Instant,
+    response: Arc<OnceCell<RpcResponse>>,
+}
+
+/// 幂等键 -> 第一次执行的响应
+pub(crate) struct IdempotencyCache {
+    window: Duration,
+    capacity: usize,
+    entries: DashMap<IdempotencyKey, Entry>,
+}
+
+impl IdempotencyCache {
+    pub(crate) fn new(window: Duration, capacity: usize) -> Self {
+        Self {
+            window,
+            capacity: capacity.max(1),
+            entries: DashMap::new(),
+        }
+    }
+
+    pub(crate) fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// 记住的键数量
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 按幂等键执行调用，窗口内重复的键返回第一次执行的响应
+    ///
+    /// 被取消或超时的执行没有完成，不会被记住，之后的重试会重新执行。键数量达到
+    /// 上限时先淘汰最早的已完成键；全部键都在执行中时不去重，直接执行。
+    pub(crate) async fn run<F>(&self, key: IdempotencyKey, request_id: String, call: F) -> RpcResponse
+    where
+        F: Future<Output = RpcResponse>,
+    {
+        self.evict_expired();
+        if !self.entries.contains_key(&key) && !self.make_room() {
+            tracing::debug!("Idempotency cache is full, running request {} without deduplication", request_id);
+            let mut response = call.await;
+            response.request_id = request_id;
+            return response;
+        }
+        let cell = Arc::clone(
+            &self
+                .entries
+                .entry(key.clone())
+                .or_insert_with(|| Entry {
+                    created: Instant::now(),
+                    response: Arc::new(OnceCell::new()),
+                })
+                .response,
+        );
+
+        let mut response = cell.get_or_init(|| call).await.clone();
+        if matches!(response.error_code(), RpcErrorCode::Cancelled | RpcErrorCode::Timeout) {
+            self.entries
+                .remove_if(&key, |_, entry| Arc::ptr_eq(&entry.response, &cell));
+        }
+        response.request_id = request_id;
+        response
+    }
+
+    /// 清除窗口外已完成的键，仍在执行的键保留到执行结束
+    fn evict_expired(&self) {
+        let window = self.window;
+        self.entries.retain(|_, entry| {
+            entry.created.elapsed() < window || !entry.response.initialized()
+        });
+    }
+
+    /// 键数量达到上限时淘汰最早的已完成键，返回是否有空位
+    fn make_room(&self) -> bool {
+        while self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .filter(|entry| entry.response.initialized())
+                .min_by_key(|entry| entry.created)
+                .map(|entry| entry.key().clone());
+            match oldest {
+                Some(key) => {
+                    self.entries.remove(&key);
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use bytes::Bytes;
+    use crate::Error;
+
+    fn key(name: &str) -> IdempotencyKey {
+        IdempotencyKey::new(DeviceId::from_string("phone".to_string()), "CameraService", "capture", name)
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_keys_run_once() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60), DEFAULT_IDEMPOTENCY_CAPACITY);
+        let runs = AtomicU32::new(0);
+        let call = |payload: &'static [u8]| {
+            let runs = &runs;
+            async move {
+                runs.fetch_add(1, Ordering::SeqCst);
+                RpcResponse::ok(String::new(), Bytes::from_static(payload))
+            }
+        };
+
+        let first = cache.run(key("key-1"), "req-1".to_string(), call(b"photo")).await;
+        let retried = cache.run(key("key-1"), "req-2".to_string(), call(b"second photo")).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(first.request_id, "req-1");
+        assert_eq!(retried.request_id, "req-2");
+        assert_eq!(retried.payload, b"photo");
+
+        cache.run(key("key-2"), "req-3".to_string(), call(b"other")).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_calls_are_not_remembered() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60), DEFAULT_IDEMPOTENCY_CAPACITY);
+        let cancelled = cache.run(key("key-1"), "req-1".to_string(), async {
+                RpcResponse::from_error(String::new(), &Error::Cancelled)
+            })
+            .await;
+        assert_eq!(cancelled.error_code(), RpcErrorCode::Cancelled);
+        assert_eq!(cache.len(), 0);
+
+        let retried = cache.run(key("key-1"), "req-2".to_string(), async {
+                RpcResponse::ok(String::new(), Bytes::from_static(b"photo"))
+            })
+            .await;
+        assert_eq!(retried.payload, b"photo");
+    }
+
+    #[tokio::test]
+    async fn test_keys_expire_after_window() {
+        let cache = IdempotencyCache::new(Duration::from_millis(20), DEFAULT_IDEMPOTENCY_CAPACITY);
+        let ok = || async { RpcResponse::ok(String::new(), Bytes::new()) };
+
+        cache.run(key("key-1"), "req-1".to_string(), ok()).await;
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        cache.run(key("key-2"), "req-2".to_string(), ok()).await;
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_scoped_to_caller_and_method() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60), DEFAULT_IDEMPOTENCY_CAPACITY);
+        let respond = |payload: &'static [u8]| async move { RpcResponse::ok(String::new(), Bytes::from_static(payload)) };
+
+        cache.run(key("key-1"), "req-1".to_string(), respond(b"photo")).await;
+        let other_peer = IdempotencyKey::new(DeviceId::from_string("tablet".to_string()), "CameraService", "capture", "key-1");
+        let other_method = IdempotencyKey::new(DeviceId::from_string("phone".to_string()), "CameraService", "get_camera_info", "key-1");
+        assert_eq!(cache.run(other_peer, "req-2".to_string(), respond(b"tablet")).await.payload, b"tablet");
+        assert_eq!(cache.run(other_method, "req-3".to_string(), respond(b"info")).await.payload, b"info");
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_capacity_evicts_oldest_keys() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60), 2);
+        let respond = |payload: &'static [u8]| async move { RpcResponse::ok(String::new(), Bytes::from_static(payload)) };
+
+        for (name, payload) in [("key-1", b"one"), ("key-2", b"two"), ("key-3", b"six")] {
+            cache.run(key(name), String::new(), respond(payload)).await;
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert_eq!(cache.len(), 2);
+        // 最早的键已被淘汰，再次执行；较新的键仍然去重
+        assert_eq!(cache.run(key("key-1"), String::new(), respond(b"new")).await.payload, b"new");
+        assert_eq!(cache.run(key("key-3"), String::new(), respond(b"new")).await.payload, b"six");
+
+        // 所有键都在执行中时不去重
+        let cache = IdempotencyCache::new(Duration::from_secs(60), 1);
+        let (started_tx, started_rx) = tokio::sync::oneshot::channel();
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+        let pending = cache.run(key("key-1"), String::new(), async {
+            let _ = started_tx.send(());
+            let _ = release_rx.await;
+            RpcResponse::ok(String::new(), Bytes::new())
+        });
+        let other = async {
+            started_rx.await.unwrap();
+            let response = cache.run(key("key-2"), String::new(), respond(b"direct")).await;
+            assert_eq!(cache.len(), 1);
+            let _ = release_tx.send(());
+            response
+        };
+        let (_, response) = tokio::join!(pending, other);
+        assert_eq!(response.payload, b"direct");
+    }
+}
@@ -0,0 +1,45 @@
+//! 客户端拦截器
+//!
+//! 拦截器包裹普通调用的每次尝试，可以读取和修改请求元数据、不发送请求直接返回
+//! 错误，并在`Invoker::invoke`返回后观察耗时和结果。日志、令牌注入、指标和
+//! 追踪头都可以用拦截器实现，无需修改生成的代理。
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use crate::Result;
+use super::client::RpcClient;
+use super::protocol::{RpcRequest, RpcResponse};
+
+/// 客户端拦截器trait
+///
+/// 实现通常先修改`request`，再调用`next.invoke(request)`把请求交给下一个拦截器，
+/// 最后一个拦截器之后请求发往服务端。返回的响应可能携带服务端的错误，
+/// `Err`表示调用没有得到响应，例如超时或通道断开。
+#[async_trait]
+pub trait Interceptor: Send + Sync {
+    async fn intercept(&self, request: RpcRequest, next: Invoker<'_>) -> Result<RpcResponse>;
+}
+
+/// 拦截器链中剩余的部分
+pub struct Invoker<'a> {
+    interceptors: &'a [Arc<dyn Interceptor>],
+    client: &'a RpcClient,
+}
+
+impl<'a> Invoker<'a> {
+    pub(crate) fn new(interceptors: &'a [Arc<dyn Interceptor>], client: &'a RpcClient) -> Self {
+        Self { interceptors, client }
+    }
+
+    /// 把请求交给下一个拦截器，没有拦截器时发往服务端并等待响应
+    pub async fn invoke(self, request: RpcRequest) -> Result<RpcResponse> {
+        match self.interceptors.split_first() {
+            Some((first, rest)) => {
+                first
+                    .intercept(request, Invoker::new(rest, self.client))
+                    .await
+            }
+            None => self.client.send_request(request).await,
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! 服务端中间件
+//!
+//! 中间件包裹`MethodHandler::handle`，可以读取和修改请求元数据、不调用处理器
+//! 直接返回错误，并在`Next::run`返回后观察耗时和结果。中间件作用于普通调用和
+//! 单向调用，流式方法不经过中间件。
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use bytes::Bytes;
+use crate::Result;
+use super::context::CallContext;
+use super::protocol::RpcRequest;
+use super::server::RpcServer;
+
+/// 服务端中间件trait
+///
+/// 实现通常先检查或修改`request`，再调用`next.run(ctx, request)`交给下一个中间件，
+/// 最后一个中间件之后按服务名和方法名查找处理器。中间件修改的元数据会出现在
+/// 处理器的`CallContext::metadata`中。
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    async fn handle(&self, ctx: &CallContext, request: RpcRequest, next: Next<'_>) -> Result<Bytes>;
+}
+
+/// 中间件链中剩余的部分
+pub struct Next<'a> {
+    middleware: &'a [Arc<dyn Middleware>],
+    server: &'a RpcServer,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(middleware: &'a [Arc<dyn Middleware>], server: &'a RpcServer) -> Self {
+        Self { middleware, server }
+    }
+
+    /// 把请求交给下一个中间件，没有中间件时调用处理器
+    pub async fn run(self, ctx: &CallContext, request: RpcRequest) -> Result<Bytes> {
+        match self.middleware.split_first() {
+            Some((first, rest)) => {
+                first
+                    .handle(ctx, request, Next::new(rest, self.server))
+                    .await
+            }
+            None => self.server.invoke(ctx, request).await,
+        }
+    }
+}
@@ -2,6 +2,24 @@
 
 pub mod client;
 pub mod server;
+pub mod protocol;
+pub mod stream;
+pub mod event;
+pub mod context;
+pub mod codec;
+pub mod retry;
+pub mod interceptor;
+pub mod middleware;
+mod idempotency;
 
 pub use client::RpcClient;
-pub use server::RpcServer;
+pub use server::{RpcServer, StreamHandler};
+pub use protocol::{FrameType, RpcRequest, RpcResponse, RpcErrorCode};
+pub use stream::{ClientStreaming, StreamReader, StreamReceiver, StreamSender, StreamWriter};
+pub use event::EventSubscription;
+pub use codec::{Codec, Decode, Encode, Proto, CODEC_METADATA_KEY};
+pub use retry::RetryPolicy;
+pub use interceptor::{Interceptor, Invoker};
+pub use middleware::{Middleware, Next};
+pub use idempotency::IDEMPOTENCY_KEY_METADATA_KEY;
+pub use context::{CallContext, CancellationToken, AUTH_TOKEN_METADATA_KEY, DEADLINE_METADATA_KEY};
@@ -0,0 +1,241 @@
+//! RPC线路协议
+//!
+//! 请求和响应使用`proto/rpc.proto`中定义的Protobuf消息编码。
+
+use bytes::Bytes;
+use prost::Message;
+use crate::{Error, Result};
+
+/// prost生成的RPC消息类型
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/softbus.rpc.rs"));
+}
+
+pub use proto::{FrameType, RpcErrorCode, RpcRequest, RpcResponse};
+
+impl RpcRequest {
+    /// 构造调用单向方法的通知帧
+    pub fn notification(service_name: &str, method_name: &str, payload: Vec<u8>) -> Self {
+        Self {
+            service_name: service_name.to_string(),
+            method_name: method_name.to_string(),
+            payload,
+            frame_type: FrameType::Notification as i32,
+            ..Default::default()
+        }
+    }
+
+    /// 构造取消普通调用的帧
+    pub fn cancel(request_id: String) -> Self {
+        Self {
+            request_id,
+            frame_type: FrameType::Cancel as i32,
+            ..Default::default()
+        }
+    }
+
+    /// 构造客户端发出的流帧
+    pub fn stream_frame(request_id: String, frame_type: FrameType, payload: Vec<u8>, credit: u32) -> Self {
+        Self {
+            request_id,
+            payload,
+            frame_type: frame_type as i32,
+            credit,
+            ..Default::default()
+        }
+    }
+
+    /// 编码为线路格式
+    pub fn encode_bytes(&self) -> Bytes {
+        Bytes::from(self.encode_to_vec())
+    }
+
+    /// 从线路格式解码
+    pub fn decode_bytes(data: &[u8]) -> Result<Self> {
+        Self::decode(data).map_err(|e| Error::Serialization(e.to_string()))
+    }
+}
+
+impl RpcResponse {
+    /// 构造成功响应
+    pub fn ok(request_id: String, payload: Bytes) -> Self {
+        Self {
+            request_id,
+            success: true,
+            payload: payload.to_vec(),
+            error_message: String::new(),
+            metadata: Default::default(),
+            error_code: RpcErrorCode::Ok as i32,
+            frame_type: FrameType::Unary as i32,
+            credit: 0,
+            service_name: String::new(),
+            event_name: String::new(),
+        }
+    }
+
+    /// 根据错误构造失败响应
+    pub fn from_error(request_id: String, error: &Error) -> Self {
+        Self {
+            request_id,
+            success: false,
+            payload: Vec::new(),
+            error_message: error_message(error),
+            metadata: Default::default(),
+            error_code: RpcErrorCode::from(error) as i32,
+            frame_type: FrameType::Unary as i32,
+            credit: 0,
+            service_name: String::new(),
+            event_name: String::new(),
+        }
+    }
+
+    /// 构造服务端推送事件的通知帧
+    pub fn notification(service_name: &str, event_name: &str, payload: Vec<u8>) -> Self {
+        Self {
+            frame_type: FrameType::Notification as i32,
+            service_name: service_name.to_string(),
+            event_name: event_name.to_string(),
+            ..Self::ok(String::new(), Bytes::from(payload))
+        }
+    }
+
+    /// 构造服务端发出的流帧
+    pub fn stream_frame(request_id: String, frame_type: FrameType, payload: Vec<u8>, credit: u32) -> Self {
+        Self {
+            frame_type: frame_type as i32,
+            credit,
+            ..Self::ok(request_id, Bytes::from(payload))
+        }
+    }
+
+    /// 构造结束流的帧，`error`为调用失败的原因
+    pub fn stream_end(request_id: String, error: Option<&Error>) -> Self {
+        let response = match error {
+            Some(error) => Self::from_error(request_id, error),
+            None => Self::ok(request_id, Bytes::new()),
+        };
+        Self {
+            frame_type: FrameType::StreamEnd as i32,
+            ..response
+        }
+    }
+
+    /// 编码为线路格式
+    pub fn encode_bytes(&self) -> Bytes {
+        Bytes::from(self.encode_to_vec())
+    }
+
+    /// 从线路格式解码
+    pub fn decode_bytes(data: &[u8]) -> Result<Self> {
+        Self::decode(data).map_err(|e| Error::Serialization(e.to_string()))
+    }
+
+    /// 转换为调用结果，失败响应还原为对应的错误类型
+    pub fn into_result(self) -> Result<Bytes> {
+        if self.success {
+            return Ok(Bytes::from(self.payload));
+        }
+
+        let code = self.error_code();
+        let message = self.error_message;
+        Err(match code {
+            RpcErrorCode::Timeout => Error::Timeout,
+            RpcErrorCode::Cancelled => Error::Cancelled,
+            RpcErrorCode::Unauthenticated => Error::Authentication(message),
+            RpcErrorCode::ServiceNotFound => Error::ServiceNotFound(message),
+            RpcErrorCode::MethodNotFound => Error::MethodNotFound(message),
+            RpcErrorCode::SerializationError => Error::Serialization(message),
+            RpcErrorCode::NetworkError => Error::Network(message),
+            RpcErrorCode::Ok | RpcErrorCode::InternalError => Error::Internal(message),
+        })
+    }
+}
+
+impl From<&Error> for RpcErrorCode {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::Timeout => RpcErrorCode::Timeout,
+            Error::Cancelled => RpcErrorCode::Cancelled,
+            Error::Authentication(_) => RpcErrorCode::Unauthenticated,
+            Error::ServiceNotFound(_) => RpcErrorCode::ServiceNotFound,
+            Error::MethodNotFound(_) => RpcErrorCode::MethodNotFound,
+            Error::Serialization(_) => RpcErrorCode::SerializationError,
+            Error::Network(_) | Error::Connection(_) | Error::Io(_) => RpcErrorCode::NetworkError,
+            _ => RpcErrorCode::InternalError,
+        }
+    }
+}
+
+/// 提取错误信息
+///
+/// 能还原为同类错误的变体只传递内部信息，避免客户端重复添加前缀。
+fn error_message(error: &Error) -> String {
+    match error {
+        Error::ServiceNotFound(msg)
+        | Error::MethodNotFound(msg)
+        | Error::Serialization(msg)
+        | Error::Network(msg)
+        | Error::Authentication(msg) => msg.clone(),
+        Error::Timeout | Error::Cancelled => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_roundtrip() {
+        let request = RpcRequest {
+            request_id: "1".to_string(),
+            service_name: "CameraService".to_string(),
+            method_name: "open".to_string(),
+            payload: vec![1, 2, 3],
+            metadata: [("k".to_string(), "v".to_string())].into_iter().collect(),
+            ..Default::default()
+        };
+
+        let decoded = RpcRequest::decode_bytes(&request.encode_bytes()).unwrap();
+        assert_eq!(decoded, request);
+    }
+
+    #[test]
+    fn test_error_response_roundtrip() {
+        let error = Error::MethodNotFound("capture".to_string());
+        let response = RpcResponse::from_error("1".to_string(), &error);
+        assert!(!response.success);
+        assert_eq!(response.error_code(), RpcErrorCode::MethodNotFound);
+
+        let decoded = RpcResponse::decode_bytes(&response.encode_bytes()).unwrap();
+        match decoded.into_result() {
+            Err(Error::MethodNotFound(msg)) => assert_eq!(msg, "capture"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_stream_end_carries_error() {
+        let end = RpcResponse::stream_end("s".to_string(), Some(&Error::Cancelled));
+        let decoded = RpcResponse::decode_bytes(&end.encode_bytes()).unwrap();
+        assert_eq!(decoded.frame_type(), FrameType::StreamEnd);
+        assert!(matches!(decoded.into_result(), Err(Error::Cancelled)));
+
+        let end = RpcResponse::stream_end("s".to_string(), None);
+        assert!(end.success);
+        assert_eq!(end.frame_type(), FrameType::StreamEnd);
+    }
+
+    #[test]
+    fn test_notification_has_no_request_id() {
+        let event = RpcResponse::notification("CameraService", "disconnected", vec![7]);
+        let decoded = RpcResponse::decode_bytes(&event.encode_bytes()).unwrap();
+        assert_eq!(decoded.frame_type(), FrameType::Notification);
+        assert_eq!((decoded.service_name.as_str(), decoded.event_name.as_str()), ("CameraService", "disconnected"));
+        assert!(decoded.request_id.is_empty());
+
+        let request = RpcRequest::notification("RemoteControl", "key", vec![1]);
+        assert!(request.request_id.is_empty());
+        assert_eq!(request.frame_type(), FrameType::Notification);
+    }
+}
@@ -0,0 +1,170 @@
+//! 调用重试策略
+//!
+//! `RetryPolicy`决定失败的调用最多尝试几次、两次尝试之间等待多久以及哪些错误
+//! 值得重试。等待时间按指数退避增长，并加入随机抖动，避免多个客户端在链路
+//! 恢复时同时重试。
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use ring::rand::{SecureRandom, SystemRandom};
+use crate::Error;
+
+/// 判断错误能否重试
+type RetryablePredicate = Arc<dyn Fn(&Error) -> bool + Send + Sync>;
+
+/// 重试策略
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: f64,
+    retryable: RetryablePredicate,
+}
+
+impl RetryPolicy {
+    /// 不重试，每个调用只尝试一次
+    pub fn none() -> Self {
+        Self::default().with_max_attempts(1)
+    }
+
+    /// 设置最大尝试次数，包括第一次调用
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// 设置第一次重试前的等待时间和等待时间的上限
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// 设置每次重试后等待时间的增长倍数
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// 设置抖动比例，等待时间在`(1 ± jitter)`倍之间随机取值
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 设置哪些错误可以重试
+    ///
+    /// 默认只重试网络、连接、IO错误和超时；服务端处理器返回的业务错误不重试。
+    pub fn with_retryable<F>(mut self, retryable: F) -> Self
+    where
+        F: Fn(&Error) -> bool + Send + Sync + 'static,
+    {
+        self.retryable = Arc::new(retryable);
+        self
+    }
+
+    /// 最大尝试次数
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// 错误能否重试
+    pub fn is_retryable(&self, error: &Error) -> bool {
+        (self.retryable)(error)
+    }
+
+    /// 第`attempt`次尝试失败后、下一次尝试前的等待时间，`attempt`从1开始
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let base = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        let base = base.min(self.max_backoff.as_secs_f64());
+        let factor = 1.0 + self.jitter * (2.0 * random_unit() - 1.0);
+        Duration::from_secs_f64(base * factor)
+    }
+}
+
+impl Default for RetryPolicy {
+    /// 最多尝试3次，等待时间从100毫秒开始翻倍，不超过5秒，抖动20%
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: 0.2,
+            retryable: Arc::new(is_transient),
+        }
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("multiplier", &self.multiplier)
+            .field("jitter", &self.jitter)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 链路短暂中断可能导致的错误
+fn is_transient(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::Network(_) | Error::Connection(_) | Error::Io(_) | Error::Timeout
+    )
+}
+
+/// `[0, 1)`内的随机数，随机源不可用时取中间值，即不加抖动
+fn random_unit() -> f64 {
+    let mut bytes = [0u8; 4];
+    match SystemRandom::new().fill(&mut bytes) {
+        Ok(()) => f64::from(u32::from_le_bytes(bytes)) / (f64::from(u32::MAX) + 1.0),
+        Err(_) => 0.5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = RetryPolicy::default()
+            .with_backoff(Duration::from_millis(100), Duration::from_millis(350))
+            .with_jitter(0.0);
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_backoff_jitter_stays_in_range() {
+        let policy = RetryPolicy::default()
+            .with_backoff(Duration::from_millis(100), Duration::from_secs(1))
+            .with_jitter(0.5);
+        for _ in 0..100 {
+            let backoff = policy.backoff(1);
+            assert!(backoff >= Duration::from_millis(50) && backoff <= Duration::from_millis(150));
+        }
+    }
+
+    #[test]
+    fn test_retryable_errors() {
+        let policy = RetryPolicy::default();
+        assert!(policy.is_retryable(&Error::Timeout));
+        assert!(policy.is_retryable(&Error::Network("link lost".to_string())));
+        assert!(!policy.is_retryable(&Error::Internal("camera busy".to_string())));
+        assert_eq!(RetryPolicy::none().max_attempts(), 1);
+
+        let policy = policy.with_retryable(|e| matches!(e, Error::Internal(_)));
+        assert!(policy.is_retryable(&Error::Internal("camera busy".to_string())));
+        assert!(!policy.is_retryable(&Error::Timeout));
+    }
+}
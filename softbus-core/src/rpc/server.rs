@@ -1,23 +1,115 @@
 //! RPC服务端
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::collections::HashMap;
+use std::time::Duration;
 use bytes::Bytes;
 use async_trait::async_trait;
+use dashmap::DashMap;
 use parking_lot::RwLock;
-use crate::{Error, Result, Channel};
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+use crate::security::AuthManager;
+use crate::{Error, Result, Channel, DeviceId};
+use super::codec::{Codec, Encode};
+use super::context::{CallContext, CancellationToken, AUTH_TOKEN_METADATA_KEY};
+use super::middleware::{Middleware, Next};
+use super::idempotency::{
+    IdempotencyCache, IdempotencyKey, DEFAULT_IDEMPOTENCY_CAPACITY, DEFAULT_IDEMPOTENCY_WINDOW,
+    IDEMPOTENCY_KEY_METADATA_KEY,
+};
+use super::protocol::{FrameType, RpcRequest, RpcResponse};
+use super::stream::{Side, StreamReader, StreamState, StreamTable, StreamWriter, DEFAULT_STREAM_WINDOW};
 
 /// 方法处理器trait
+///
+/// `ctx`携带调用方身份、请求元数据、客户端的截止时间和取消令牌。请求按
+/// `ctx.codec()`编码，响应也应按它编码。超时或客户端取消时服务端不再等待
+/// 处理器，耗时操作应监听`ctx.cancellation()`及时停止。
 #[async_trait]
 pub trait MethodHandler: Send + Sync {
-    async fn handle(&self, request: Bytes) -> Result<Bytes>;
+    async fn handle(&self, ctx: &CallContext, request: Bytes) -> Result<Bytes>;
 }
 
+/// 流式方法处理器trait
+///
+/// `ctx`与普通调用一样携带调用方身份和打开流时的请求元数据，客户端取消流或
+/// 通道断开时触发`ctx.cancellation()`。`request`为打开流时携带的非流参数，
+/// `inbound`接收客户端发来的消息，`outbound`向客户端发送消息。参数和消息都按
+/// `inbound.codec()`编码。处理器返回后服务端结束流，返回的错误随结束帧传给客户端。
+#[async_trait]
+pub trait StreamHandler: Send + Sync {
+    async fn handle(
+        &self,
+        ctx: &CallContext,
+        request: Bytes,
+        inbound: StreamReader,
+        outbound: StreamWriter,
+    ) -> Result<()>;
+}
+
+/// RPC服务trait
+///
+/// 把一个服务的全部方法作为整体注册到`RpcServer`
+pub trait RpcService: Send + Sync {
+    /// 服务名称，与客户端调用时使用的服务名一致
+    fn service_name(&self) -> &str;
+
+    /// 服务提供的方法处理器：方法名 -> 处理器
+    fn method_handlers(&self) -> Vec<(String, Arc<dyn MethodHandler>)>;
+
+    /// 服务提供的流式方法处理器：方法名 -> 处理器
+    fn stream_handlers(&self) -> Vec<(String, Arc<dyn StreamHandler>)> {
+        Vec::new()
+    }
+}
+
+/// 一个服务注册的全部方法，同名方法只会出现在其中一张表里
+#[derive(Default)]
+struct ServiceMethods {
+    unary: HashMap<String, Arc<dyn MethodHandler>>,
+    streaming: HashMap<String, Arc<dyn StreamHandler>>,
+}
+
+impl ServiceMethods {
+    fn remove(&mut self, method_name: &str) {
+        self.unary.remove(method_name);
+        self.streaming.remove(method_name);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.unary.is_empty() && self.streaming.is_empty()
+    }
+}
+
+/// 服务名 -> 方法表
+type ServiceHandlers = HashMap<String, ServiceMethods>;
+
+/// 一个通道上正在处理的普通调用：请求ID -> 取消令牌
+type InFlightCalls = DashMap<String, CancellationToken>;
+
+/// 每个通道默认允许的最大并发请求数
+const DEFAULT_MAX_IN_FLIGHT: usize = 64;
+
 /// RPC服务端
 /// 
-/// 用于处理远程过程调用请求
+/// 用于处理远程过程调用请求。克隆开销很小，克隆体共享同一组处理器和连接。
+#[derive(Clone)]
 pub struct RpcServer {
-    handlers: Arc<RwLock<HashMap<String, Arc<dyn MethodHandler>>>>,
+    handlers: Arc<RwLock<ServiceHandlers>>,
+    /// 正在`serve`的通道，用于推送事件
+    peers: Arc<DashMap<u64, Arc<dyn Channel>>>,
+    next_peer: Arc<AtomicU64>,
+    /// 用于认证调用方，未设置时所有调用方都未认证
+    auth: Option<Arc<AuthManager>>,
+    /// 包裹方法处理器的中间件，按添加顺序由外到内
+    middleware: Arc<Vec<Arc<dyn Middleware>>>,
+    /// 推送事件使用的编解码器
+    codec: Codec,
+    /// 按调用方、方法和幂等键记住的响应，所有连接共享，客户端换连接重试时同样去重
+    idempotency: Arc<IdempotencyCache>,
+    max_in_flight: usize,
+    stream_window: u32,
 }
 
 impl RpcServer {
@@ -25,67 +117,561 @@ impl RpcServer {
     pub fn new() -> Self {
         Self {
             handlers: Arc::new(RwLock::new(HashMap::new())),
+            peers: Arc::new(DashMap::new()),
+            next_peer: Arc::new(AtomicU64::new(0)),
+            auth: None,
+            middleware: Arc::new(Vec::new()),
+            codec: Codec::default(),
+            idempotency: Arc::new(IdempotencyCache::new(DEFAULT_IDEMPOTENCY_WINDOW, DEFAULT_IDEMPOTENCY_CAPACITY)),
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            stream_window: DEFAULT_STREAM_WINDOW,
         }
     }
 
+    /// 设置每个通道的最大并发请求数
+    ///
+    /// 超出上限的请求排队等待，直到有请求处理完成；`serve`不因此暂停读取，排队
+    /// 和执行中的请求都能被取消。流不占用该配额，但每个通道同时打开的流数量也
+    /// 以此为上限，超出时拒绝新流。
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.clamp(1, u32::MAX as usize);
+        self
+    }
+
+    /// 设置每个流的接收窗口，即客户端无需等待即可连续发送的消息数
+    pub fn with_stream_window(mut self, window: u32) -> Self {
+        self.stream_window = window.max(1);
+        self
+    }
+
+    /// 设置推送事件使用的编解码器，默认为bincode
+    ///
+    /// 请求总是按调用方使用的编解码器应答，不受该设置影响。
+    pub fn with_codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// 设置幂等键的去重窗口，默认为60秒
+    ///
+    /// 窗口内同一调用方对同一方法携带相同幂等键的请求只执行一次，重复的请求
+    /// 得到第一次执行的响应。调用方身份未经认证或握手验证时不去重。窗口应长于
+    /// 客户端重试的总时长。
+    pub fn with_idempotency_window(mut self, window: Duration) -> Self {
+        self.idempotency = Arc::new(IdempotencyCache::new(window, self.idempotency.capacity()));
+        self
+    }
+
+    /// 设置最多记住的幂等键数量，默认为10000
+    ///
+    /// 达到上限时淘汰最早的已完成键。
+    pub fn with_idempotency_capacity(mut self, capacity: usize) -> Self {
+        self.idempotency = Arc::new(IdempotencyCache::new(self.idempotency.window(), capacity));
+        self
+    }
+
+    /// 添加中间件，先添加的中间件在外层
+    ///
+    /// 中间件包裹普通调用和单向调用的处理器，流式方法不经过中间件。
+    pub fn with_middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        Arc::make_mut(&mut self.middleware).push(middleware);
+        self
+    }
+
+    /// 设置认证管理器
+    ///
+    /// `serve`用请求元数据中的令牌验证通道对端的设备ID，验证通过后作为
+    /// `CallContext::peer`交给处理器。验证失败的请求照常分发，由处理器决定是否拒绝。
+    pub fn with_auth_manager(mut self, auth: Arc<AuthManager>) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
     /// 注册方法处理器
-    pub fn register_method(&self, method_name: &str, handler: Arc<dyn MethodHandler>) {
-        self.handlers.write().insert(method_name.to_string(), handler);
+    pub fn register_method(
+        &self,
+        service_name: &str,
+        method_name: &str,
+        handler: Arc<dyn MethodHandler>,
+    ) {
+        let mut handlers = self.handlers.write();
+        let methods = handlers.entry(service_name.to_string()).or_default();
+        methods.remove(method_name);
+        methods.unary.insert(method_name.to_string(), handler);
+    }
+
+    /// 注册流式方法处理器
+    pub fn register_stream_method(
+        &self,
+        service_name: &str,
+        method_name: &str,
+        handler: Arc<dyn StreamHandler>,
+    ) {
+        let mut handlers = self.handlers.write();
+        let methods = handlers.entry(service_name.to_string()).or_default();
+        methods.remove(method_name);
+        methods.streaming.insert(method_name.to_string(), handler);
     }
 
     /// 注销方法处理器
-    pub fn unregister_method(&self, method_name: &str) {
-        self.handlers.write().remove(method_name);
+    pub fn unregister_method(&self, service_name: &str, method_name: &str) {
+        let mut handlers = self.handlers.write();
+        if let Some(methods) = handlers.get_mut(service_name) {
+            methods.remove(method_name);
+            if methods.is_empty() {
+                handlers.remove(service_name);
+            }
+        }
+    }
+
+    /// 注册整个服务，替换同名服务已有的全部方法
+    pub fn register_service<S: RpcService + ?Sized>(&self, service: &S) {
+        let methods = ServiceMethods {
+            unary: service.method_handlers().into_iter().collect(),
+            streaming: service.stream_handlers().into_iter().collect(),
+        };
+        self.handlers
+            .write()
+            .insert(service.service_name().to_string(), methods);
+    }
+
+    /// 注销整个服务
+    pub fn unregister_service(&self, service_name: &str) {
+        self.handlers.write().remove(service_name);
+    }
+
+    /// 列出已注册的服务名称
+    pub fn services(&self) -> Vec<String> {
+        self.handlers.read().keys().cloned().collect()
+    }
+
+    /// 当前正在`serve`的连接数量
+    pub fn peers(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// 向所有连接推送事件，返回成功发送的连接数
+    ///
+    /// 事件不需要确认，发送失败的连接只记录日志。
+    pub async fn publish<E: Encode + ?Sized>(
+        &self,
+        service_name: &str,
+        event_name: &str,
+        event: &E,
+    ) -> Result<usize> {
+        let payload = self.codec.encode(event)?;
+        let mut notification = RpcResponse::notification(service_name, event_name, payload);
+        self.codec.write_metadata(&mut notification.metadata);
+        let frame = notification.encode_bytes();
+
+        let channels: Vec<Arc<dyn Channel>> =
+            self.peers.iter().map(|entry| Arc::clone(entry.value())).collect();
+        let mut delivered = 0;
+        for channel in channels {
+            match channel.send(frame.clone()).await {
+                Ok(()) => delivered += 1,
+                Err(e) => tracing::debug!("Failed to publish {}.{}: {}", service_name, event_name, e),
+            }
+        }
+        Ok(delivered)
     }
 
     /// 处理RPC请求
+    ///
+    /// 处理器返回的错误会编码为失败的`RpcResponse`，不会向上传播。请求携带的
+    /// 截止时间同样生效，但没有通道可供认证，调用方总是未认证。单向方法同样
+    /// 返回响应，由调用方决定是否丢弃；流式调用和取消帧只能通过`serve`处理。
     pub async fn handle_request(&self, request: Bytes) -> Result<Bytes> {
-        // 解析请求
-        let (method_name, payload) = self.parse_request(&request)?;
+        let response = match self.parse_request(&request) {
+            Ok(rpc_request) => {
+                let ctx = CallContext::from_request(&rpc_request);
+                self.respond(&ctx, rpc_request).await
+            }
+            // 无法解析的请求没有请求ID，仍然回复错误让对端知道原因
+            Err(e) => RpcResponse::from_error(String::new(), &e),
+        };
+
+        Ok(response.encode_bytes())
+    }
 
-        // 查找处理器
+    async fn respond(&self, ctx: &CallContext, request: RpcRequest) -> RpcResponse {
+        let request_id = request.request_id.clone();
+        let result = self.dispatch(ctx, request).await;
+        if let Err(e) = &result {
+            tracing::debug!("RPC request {} failed: {}", request_id, e);
+        }
+        to_response(request_id, result)
+    }
+
+    async fn dispatch(&self, ctx: &CallContext, request: RpcRequest) -> Result<Bytes> {
+        if !matches!(request.frame_type(), FrameType::Unary | FrameType::Notification) {
+            return Err(Error::Internal("RPC stream frames must be handled by serve".to_string()));
+        }
+        // 无法按调用方的格式应答时直接拒绝
+        Codec::from_metadata(&request.metadata)?;
+
+        Next::new(&self.middleware, self).run(ctx, request).await
+    }
+
+    /// 中间件链的末端：查找并调用处理器
+    pub(crate) async fn invoke(&self, ctx: &CallContext, request: RpcRequest) -> Result<Bytes> {
+        // 先按服务名、再按方法名查找处理器
         let handler = {
             let handlers = self.handlers.read();
-            handlers
-                .get(&method_name)
+            find_service(&handlers, &request)?
+                .unary
+                .get(&request.method_name)
                 .map(Arc::clone)
-                .ok_or_else(|| Error::MethodNotFound(method_name.clone()))?
+                .ok_or_else(|| method_not_found(&request))?
+        };
+
+        // 处理器看到中间件修改后的元数据
+        let mut metadata = request.metadata;
+        metadata.remove(AUTH_TOKEN_METADATA_KEY);
+        let ctx = ctx.clone().with_metadata(metadata);
+
+        // 调用处理器，携带幂等键的请求在去重窗口内只执行一次。去重在中间件之后，
+        // 重复的请求同样要经过中间件的检查。无法确认调用方时不去重，否则重放的键
+        // 可能得到其他调用方的响应
+        let payload = Bytes::from(request.payload);
+        match (ctx.peer(), ctx.metadata().get(IDEMPOTENCY_KEY_METADATA_KEY)) {
+            (Some(peer), Some(key)) => {
+                let key = IdempotencyKey::new(peer.clone(), &request.service_name, &request.method_name, key);
+                let call = async {
+                    to_response(String::new(), run_handler(handler.as_ref(), &ctx, payload).await)
+                };
+                self.idempotency
+                    .run(key, request.request_id, call)
+                    .await
+                    .into_result()
+            }
+            _ => run_handler(handler.as_ref(), &ctx, payload).await,
+        }
+    }
+
+    /// 处理单向方法调用，结果不回复给客户端
+    async fn handle_notification(&self, ctx: &CallContext, request: RpcRequest) {
+        let method = format!("{}.{}", request.service_name, request.method_name);
+        if let Err(e) = self.dispatch(ctx, request).await {
+            tracing::debug!("RPC notification {} failed: {}", method, e);
+        }
+    }
+
+    /// 为通道上收到的请求构造调用上下文
+    fn call_context(&self, channel: &dyn Channel, request: &RpcRequest) -> CallContext {
+        let ctx = CallContext::from_request(request).with_qos_level(channel.qos_level());
+        match self.caller(channel, request) {
+            Some(peer) => ctx.with_peer(peer),
+            None => ctx,
+        }
+    }
+
+    /// 调用方的设备ID
+    ///
+    /// 握手验证过的对端优先，它出示的令牌也必须有效，签名令牌必须签发给该对端。
+    /// 未经握手的通道只能用凭证验证对端自称的ID。
+    fn caller(&self, channel: &dyn Channel, request: &RpcRequest) -> Option<DeviceId> {
+        let verified = channel.verified_peer();
+        let (Some(auth), Some(token)) = (self.auth.as_ref(), request.metadata.get(AUTH_TOKEN_METADATA_KEY)) else {
+            return verified;
+        };
+        let result = match verified {
+            Some(peer) => auth.authenticate_verified(&peer, token).map(|()| peer),
+            None => {
+                let peer = DeviceId::from_string(channel.peer_device_id()?);
+                auth.authenticate(&peer, token).map(|()| peer)
+            }
         };
+        match result {
+            Ok(peer) => Some(peer),
+            Err(e) => {
+                tracing::debug!("RPC request {} is not authenticated: {}", request.request_id, e);
+                None
+            }
+        }
+    }
 
-        // 调用处理器
-        handler.handle(payload).await
-    }
-
-    fn parse_request(&self, request: &Bytes) -> Result<(String, Bytes)> {
-        // TODO: 使用protobuf解析请求
-        // 这里暂时使用简单的格式
-        let data = request.as_ref();
-        
-        // 查找第一个分隔符（服务名结束）
-        let first_null = data.iter().position(|&b| b == 0)
-            .ok_or_else(|| Error::Serialization("Invalid request format".to_string()))?;
-        
-        // 查找第二个分隔符（方法名结束）
-        let second_null = data[first_null + 1..].iter().position(|&b| b == 0)
-            .ok_or_else(|| Error::Serialization("Invalid request format".to_string()))?;
-        
-        let method_name = String::from_utf8_lossy(&data[first_null + 1..first_null + 1 + second_null]).to_string();
-        let payload = Bytes::copy_from_slice(&data[first_null + second_null + 2..]);
-        
-        Ok((method_name, payload))
+    fn find_stream_handler(&self, request: &RpcRequest) -> Result<Arc<dyn StreamHandler>> {
+        let handlers = self.handlers.read();
+        find_service(&handlers, request)?
+            .streaming
+            .get(&request.method_name)
+            .map(Arc::clone)
+            .ok_or_else(|| method_not_found(request))
+    }
+
+    fn parse_request(&self, request: &Bytes) -> Result<RpcRequest> {
+        RpcRequest::decode_bytes(request)
     }
 
     /// 启动服务端，监听指定通道
+    ///
+    /// 每个请求和流在独立任务中处理，响应按完成顺序写回通道，由请求ID关联。
+    /// 客户端取消的请求不再回复。单向方法按到达顺序逐个处理，占用请求配额但
+    /// 不回复。服务期间该通道会收到`publish`推送的事件。
+    /// 仅在通道收发失败时返回，返回前取消未结束的流和普通调用，并等待已接收的
+    /// 请求处理完毕。
     pub async fn serve(&self, channel: Arc<dyn Channel>) -> Result<()> {
-        loop {
+        let max_in_flight = self.max_in_flight as u32;
+        let permits = Arc::new(Semaphore::new(self.max_in_flight));
+        let stream_permits = Arc::new(Semaphore::new(self.max_in_flight));
+        let streams: Arc<StreamTable> = Arc::new(StreamTable::new());
+        let in_flight: Arc<InFlightCalls> = Arc::new(InFlightCalls::new());
+
+        let peer = self.next_peer.fetch_add(1, Ordering::Relaxed);
+        self.peers.insert(peer, Arc::clone(&channel));
+
+        // 单向方法在同一任务中依次执行，保证输入事件等通知不乱序
+        let (notifications, mut queued) = mpsc::unbounded_channel();
+        let server = self.clone();
+        let notification_permits = Arc::clone(&permits);
+        let notification_task = tokio::spawn(async move {
+            while let Some((ctx, request)) = queued.recv().await {
+                let Ok(_permit) = acquire_permit(&notification_permits).await else {
+                    return;
+                };
+                server.handle_notification(&ctx, request).await;
+            }
+        });
+
+        let result = loop {
             // 接收请求
-            let request = channel.recv().await?;
+            let data = match channel.recv().await {
+                Ok(data) => data,
+                Err(e) => break e,
+            };
+
+            let request = match self.parse_request(&data) {
+                Ok(request) => request,
+                Err(e) => {
+                    let response = RpcResponse::from_error(String::new(), &e);
+                    if let Err(e) = channel.send(response.encode_bytes()).await {
+                        tracing::warn!("Failed to send RPC response: {}", e);
+                    }
+                    continue;
+                }
+            };
+
+            // 普通调用和单向调用在处理任务中获取配额，读取不会因并发上限暂停，
+            // 保证取消帧和流帧总能被及时处理
+            match request.frame_type() {
+                FrameType::Unary => {
+                    let server = self.clone();
+                    let channel = Arc::clone(&channel);
+                    let in_flight = Arc::clone(&in_flight);
+                    let permits = Arc::clone(&permits);
+                    let ctx = self.call_context(channel.as_ref(), &request);
+                    let request_id = request.request_id.clone();
+                    // 先登记再启动任务，排队中的调用也能被取消
+                    in_flight.insert(request_id.clone(), ctx.cancellation().clone());
+                    tokio::spawn(async move {
+                        let _permit = tokio::select! {
+                            biased;
+                            _ = ctx.cancellation().cancelled() => {
+                                tracing::debug!("RPC request {} cancelled before it started", request_id);
+                                return;
+                            }
+                            permit = acquire_permit(&permits) => match permit {
+                                Ok(permit) => permit,
+                                Err(_) => return,
+                            },
+                        };
+
+                        // 处理请求
+                        let response = server.respond(&ctx, request).await;
 
-            // 处理请求
-            let response = self.handle_request(request).await?;
+                        // 取消帧已移除登记，客户端不再等待响应
+                        if in_flight.remove(&request_id).is_none() {
+                            tracing::debug!("RPC request {} cancelled by client", request_id);
+                            return;
+                        }
 
-            // 发送响应
-            channel.send(response).await?;
+                        // 发送响应
+                        if let Err(e) = channel.send(response.encode_bytes()).await {
+                            tracing::warn!("Failed to send RPC response: {}", e);
+                        }
+                    });
+                }
+                FrameType::Notification => {
+                    let ctx = self.call_context(channel.as_ref(), &request);
+                    let _ = notifications.send((ctx, request));
+                }
+                FrameType::Cancel => {
+                    if let Some((_, token)) = in_flight.remove(&request.request_id) {
+                        token.cancel();
+                    }
+                }
+                // 流可能长期存在，不占用请求配额，否则流之间会因读不到后续帧而互相等待
+                FrameType::StreamOpen => self.start_stream(request, &channel, &streams, &stream_permits),
+                _ => route_stream_frame(&streams, request).await,
+            }
+        };
+
+        // 通道已断开，未结束的流无法继续
+        self.peers.remove(&peer);
+        for entry in streams.iter() {
+            entry.value().abort_task();
+            entry.value().abort(Error::Connection("RPC channel closed".to_string()));
+        }
+        streams.clear();
+
+        // 在途的普通调用不再有人等待响应，没有截止时间的处理器也必须结束
+        for entry in in_flight.iter() {
+            entry.value().cancel();
+        }
+        in_flight.clear();
+
+        // 等待排队的单向调用和所有在途请求结束
+        drop(notifications);
+        let _ = notification_task.await;
+        let _ = permits.acquire_many(max_in_flight).await;
+        let _ = stream_permits.acquire_many(max_in_flight).await;
+        Err(result)
+    }
+
+    /// 打开流并在独立任务中运行处理器
+    fn start_stream(
+        &self,
+        request: RpcRequest,
+        channel: &Arc<dyn Channel>,
+        streams: &Arc<StreamTable>,
+        stream_permits: &Arc<Semaphore>,
+    ) {
+        if streams.contains_key(&request.request_id) {
+            tracing::warn!("Ignoring duplicate RPC stream: {}", request.request_id);
+            return;
+        }
+
+        let opened = Codec::from_metadata(&request.metadata)
+            .and_then(|_| self.find_stream_handler(&request))
+            .and_then(|handler| {
+                let permit = Arc::clone(stream_permits)
+                    .try_acquire_owned()
+                    .map_err(|_| Error::Internal("too many concurrent RPC streams".to_string()))?;
+                Ok((handler, permit))
+            });
+        let (handler, permit) = match opened {
+            Ok(opened) => opened,
+            Err(e) => {
+                // 流没有建立，直接以错误结束
+                tracing::debug!("RPC stream {} rejected: {}", request.request_id, e);
+                let end = RpcResponse::stream_end(request.request_id, Some(&e));
+                let channel = Arc::clone(channel);
+                tokio::spawn(async move {
+                    if let Err(e) = channel.send(end.encode_bytes()).await {
+                        tracing::warn!("Failed to send RPC response: {}", e);
+                    }
+                });
+                return;
+            }
+        };
+
+        // 先登记再启动任务，保证后续帧能找到该流
+        let ctx = self.call_context(channel.as_ref(), &request);
+        let (state, inbound) = StreamState::register(
+            streams,
+            &request,
+            Side::Server,
+            Arc::clone(channel),
+            self.stream_window,
+            request.credit,
+            0,
+        );
+        let outbound = StreamWriter::new(Arc::clone(&state));
+        let window = self.stream_window;
+        let task_state = Arc::clone(&state);
+        let payload = Bytes::from(request.payload);
+        let cancellation = ctx.cancellation().clone();
+
+        let task = tokio::spawn(async move {
+            let _permit = permit;
+            let state = task_state;
+
+            let result = match state.grant(window).await {
+                Ok(()) => handler.handle(&ctx, payload, inbound, outbound).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = &result {
+                tracing::debug!("RPC stream {} failed: {}", state.request_id(), e);
+            }
+            if let Err(e) = state.send_end(result.as_ref().err()).await {
+                tracing::warn!("Failed to end RPC stream {}: {}", state.request_id(), e);
+            }
+            state.remove();
+        });
+        state.set_task(task.abort_handle(), cancellation);
+    }
+}
+
+async fn acquire_permit(permits: &Arc<Semaphore>) -> Result<OwnedSemaphorePermit> {
+    Arc::clone(permits)
+        .acquire_owned()
+        .await
+        .map_err(|_| Error::Internal("Semaphore closed".to_string()))
+}
+
+fn find_service<'a>(handlers: &'a ServiceHandlers, request: &RpcRequest) -> Result<&'a ServiceMethods> {
+    handlers
+        .get(&request.service_name)
+        .ok_or_else(|| Error::ServiceNotFound(request.service_name.clone()))
+}
+
+fn method_not_found(request: &RpcRequest) -> Error {
+    Error::MethodNotFound(format!("{}.{}", request.service_name, request.method_name))
+}
+
+fn to_response(request_id: String, result: Result<Bytes>) -> RpcResponse {
+    match result {
+        Ok(payload) => RpcResponse::ok(request_id, payload),
+        Err(e) => RpcResponse::from_error(request_id, &e),
+    }
+}
+
+/// 运行处理器，截止时间到达或调用被取消时触发取消令牌并放弃等待
+async fn run_handler(handler: &dyn MethodHandler, ctx: &CallContext, payload: Bytes) -> Result<Bytes> {
+    let token = ctx.cancellation();
+    let expired = async {
+        match ctx.deadline() {
+            Some(deadline) => tokio::time::sleep_until(deadline).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        biased;
+        _ = token.cancelled() => Err(Error::Cancelled),
+        _ = expired => {
+            token.cancel();
+            Err(Error::Timeout)
+        }
+        result = handler.handle(ctx, payload) => result,
+    }
+}
+
+/// 处理客户端发来的流帧
+async fn route_stream_frame(streams: &StreamTable, request: RpcRequest) {
+    let Some(state) = streams.get(&request.request_id).map(|entry| Arc::clone(entry.value())) else {
+        tracing::debug!("Dropping frame for unknown RPC stream: {}", request.request_id);
+        return;
+    };
+
+    match request.frame_type() {
+        FrameType::StreamData => {
+            if !state.on_data(request.payload) {
+                tracing::warn!("RPC stream {} exceeded its credit, cancelling", state.request_id());
+                state.abort_task();
+                let _ = state.send_cancel().await;
+            }
+        }
+        FrameType::StreamCredit => state.on_credit(request.credit),
+        FrameType::StreamEnd => state.on_end(None),
+        FrameType::StreamCancel => {
+            state.abort_task();
+            state.abort(Error::Cancelled);
+            state.remove();
+        }
+        FrameType::Unary | FrameType::StreamOpen | FrameType::Notification | FrameType::Cancel => {
+            tracing::debug!("Ignoring unexpected RPC stream frame from client");
         }
     }
 }
@@ -99,10 +685,139 @@ impl Default for RpcServer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rpc::protocol::RpcErrorCode;
 
     #[test]
     fn test_rpc_server_creation() {
         let server = RpcServer::new();
         assert_eq!(server.handlers.read().len(), 0);
     }
+
+    struct EchoHandler;
+
+    #[async_trait]
+    impl MethodHandler for EchoHandler {
+        async fn handle(&self, _ctx: &CallContext, request: Bytes) -> Result<Bytes> {
+            Ok(request)
+        }
+    }
+
+    struct FailingHandler;
+
+    #[async_trait]
+    impl MethodHandler for FailingHandler {
+        async fn handle(&self, _ctx: &CallContext, _request: Bytes) -> Result<Bytes> {
+            Err(Error::Internal("camera busy".to_string()))
+        }
+    }
+
+    /// 直到被取消才返回的处理器
+    struct StuckHandler;
+
+    #[async_trait]
+    impl MethodHandler for StuckHandler {
+        async fn handle(&self, _ctx: &CallContext, _request: Bytes) -> Result<Bytes> {
+            std::future::pending().await
+        }
+    }
+
+    fn request(method_name: &str, payload: &[u8]) -> Bytes {
+        service_request("TestService", method_name, payload)
+    }
+
+    fn service_request(service_name: &str, method_name: &str, payload: &[u8]) -> Bytes {
+        RpcRequest {
+            request_id: "req-1".to_string(),
+            service_name: service_name.to_string(),
+            method_name: method_name.to_string(),
+            payload: payload.to_vec(),
+            metadata: Default::default(),
+            ..Default::default()
+        }
+        .encode_bytes()
+    }
+
+    #[tokio::test]
+    async fn test_handle_request_success() {
+        let server = RpcServer::new();
+        server.register_method("TestService", "echo", Arc::new(EchoHandler));
+
+        let response = server.handle_request(request("echo", b"hi")).await.unwrap();
+        let response = RpcResponse::decode_bytes(&response).unwrap();
+        assert!(response.success);
+        assert_eq!(response.request_id, "req-1");
+        assert_eq!(response.payload, b"hi");
+    }
+
+    #[tokio::test]
+    async fn test_handle_request_errors_become_responses() {
+        let server = RpcServer::new();
+        server.register_method("TestService", "fail", Arc::new(FailingHandler));
+
+        let response = server.handle_request(request("fail", b"")).await.unwrap();
+        let response = RpcResponse::decode_bytes(&response).unwrap();
+        assert!(!response.success);
+        assert_eq!(response.error_code(), RpcErrorCode::InternalError);
+
+        let response = server.handle_request(request("missing", b"")).await.unwrap();
+        let response = RpcResponse::decode_bytes(&response).unwrap();
+        assert_eq!(response.error_code(), RpcErrorCode::MethodNotFound);
+
+        let response = server.handle_request(Bytes::from_static(b"\xff\xff")).await.unwrap();
+        let response = RpcResponse::decode_bytes(&response).unwrap();
+        assert_eq!(response.error_code(), RpcErrorCode::SerializationError);
+    }
+
+    #[tokio::test]
+    async fn test_deadline_stops_handler() {
+        let server = RpcServer::new();
+        server.register_method("TestService", "stuck", Arc::new(StuckHandler));
+
+        let mut request = RpcRequest::decode_bytes(&request("stuck", b"")).unwrap();
+        request.metadata.insert(
+            crate::rpc::DEADLINE_METADATA_KEY.to_string(),
+            crate::rpc::context::encode_deadline(std::time::Duration::from_millis(20)),
+        );
+        let response = server.handle_request(request.encode_bytes()).await.unwrap();
+        let response = RpcResponse::decode_bytes(&response).unwrap();
+        assert_eq!(response.error_code(), RpcErrorCode::Timeout);
+    }
+
+    struct CameraService;
+
+    impl RpcService for CameraService {
+        fn service_name(&self) -> &str {
+            "CameraService"
+        }
+
+        fn method_handlers(&self) -> Vec<(String, Arc<dyn MethodHandler>)> {
+            vec![("close".to_string(), Arc::new(FailingHandler) as Arc<dyn MethodHandler>)]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_is_scoped_by_service() {
+        let server = RpcServer::new();
+        server.register_method("FileService", "close", Arc::new(EchoHandler));
+        server.register_service(&CameraService);
+
+        // 同名方法分属不同服务，互不覆盖
+        let response = server.handle_request(service_request("FileService", "close", b"ok")).await.unwrap();
+        assert!(RpcResponse::decode_bytes(&response).unwrap().success);
+
+        let response = server.handle_request(service_request("CameraService", "close", b"")).await.unwrap();
+        let response = RpcResponse::decode_bytes(&response).unwrap();
+        assert_eq!(response.error_code(), RpcErrorCode::InternalError);
+
+        let response = server.handle_request(service_request("CameraService", "open", b"")).await.unwrap();
+        let response = RpcResponse::decode_bytes(&response).unwrap();
+        assert_eq!(response.error_code(), RpcErrorCode::MethodNotFound);
+
+        let response = server.handle_request(service_request("AudioService", "close", b"")).await.unwrap();
+        let response = RpcResponse::decode_bytes(&response).unwrap();
+        assert_eq!(response.error_code(), RpcErrorCode::ServiceNotFound);
+
+        server.unregister_service("CameraService");
+        assert_eq!(server.services(), vec!["FileService".to_string()]);
+    }
 }
@@ -0,0 +1,418 @@
+//! 流式调用
+//!
+//! 一个流的所有帧共用打开流时的请求ID，与普通调用复用同一条`Channel`。
+//! 流的每个方向各自做流控：接收方在打开流时以及消费消息后通过
+//! `STREAM_CREDIT`帧授予对端发送配额，发送方用尽配额后等待，慢消费者
+//! 不会拖垮共享通道上的其他调用。流消息按打开流时约定的`Codec`编码。
+
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Weak};
+use bytes::Bytes;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::AbortHandle;
+use crate::{Error, Result, Channel};
+use super::codec::{Codec, Decode, Encode};
+use super::context::CancellationToken;
+use super::protocol::{FrameType, RpcRequest, RpcResponse};
+
+/// 每个流默认的接收窗口（消息条数）
+pub const DEFAULT_STREAM_WINDOW: u32 = 32;
+
+/// 流所在的一端，决定帧的编码方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Side {
+    /// 客户端发出`RpcRequest`帧
+    Client,
+    /// 服务端发出`RpcResponse`帧
+    Server,
+}
+
+/// 进行中的流：请求ID -> 流状态
+pub(crate) type StreamTable = DashMap<String, Arc<StreamState>>;
+
+/// 单个流在本端的状态
+pub(crate) struct StreamState {
+    request_id: String,
+    side: Side,
+    /// 流消息的编解码器
+    codec: Codec,
+    channel: Arc<dyn Channel>,
+    table: Weak<StreamTable>,
+    window: u32,
+    /// 对端授予的发送配额
+    send_credits: Semaphore,
+    /// 本端发送方向已结束
+    send_closed: AtomicBool,
+    /// 已授予对端、尚未被使用的接收配额
+    recv_credits: AtomicU32,
+    /// 交给`StreamReader`的消息队列，对端结束后置空
+    inbound: Mutex<Option<mpsc::UnboundedSender<Result<Bytes>>>>,
+    /// 服务端处理任务及其调用上下文的取消令牌，对端取消时中止
+    task: Mutex<Option<(AbortHandle, CancellationToken)>>,
+}
+
+impl StreamState {
+    /// 按打开流的请求创建流状态并登记到`table`
+    ///
+    /// 流消息使用请求元数据中的编解码器。`send_credits`为对端已授予的发送配额，
+    /// `recv_credits`为本端已授予对端的配额。
+    pub(crate) fn register(
+        table: &Arc<StreamTable>,
+        open: &RpcRequest,
+        side: Side,
+        channel: Arc<dyn Channel>,
+        window: u32,
+        send_credits: u32,
+        recv_credits: u32,
+    ) -> (Arc<Self>, StreamReader) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let request_id = open.request_id.clone();
+        let state = Arc::new(Self {
+            request_id: request_id.clone(),
+            side,
+            codec: Codec::from_metadata(&open.metadata).unwrap_or_default(),
+            channel,
+            table: Arc::downgrade(table),
+            window: window.max(1),
+            send_credits: Semaphore::new(0),
+            send_closed: AtomicBool::new(false),
+            recv_credits: AtomicU32::new(recv_credits),
+            inbound: Mutex::new(Some(tx)),
+            task: Mutex::new(None),
+        });
+        state.on_credit(send_credits);
+        table.insert(request_id, Arc::clone(&state));
+
+        let reader = StreamReader {
+            state: Arc::clone(&state),
+            rx,
+            consumed: 0,
+        };
+        (state, reader)
+    }
+
+    pub(crate) fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    pub(crate) fn set_task(&self, task: AbortHandle, cancellation: CancellationToken) {
+        *self.task.lock() = Some((task, cancellation));
+    }
+
+    fn frame(&self, frame_type: FrameType, payload: Vec<u8>, credit: u32) -> Bytes {
+        let request_id = self.request_id.clone();
+        match self.side {
+            Side::Client => RpcRequest::stream_frame(request_id, frame_type, payload, credit).encode_bytes(),
+            Side::Server => RpcResponse::stream_frame(request_id, frame_type, payload, credit).encode_bytes(),
+        }
+    }
+
+    async fn send_data(&self, payload: Bytes) -> Result<()> {
+        if self.send_closed.load(Ordering::Acquire) {
+            return Err(stream_closed());
+        }
+        self.send_credits
+            .acquire()
+            .await
+            .map_err(|_| stream_closed())?
+            .forget();
+        self.channel
+            .send(self.frame(FrameType::StreamData, payload.to_vec(), 0))
+            .await
+    }
+
+    /// 结束本端发送方向，服务端发出的结束帧携带调用结果
+    pub(crate) async fn send_end(&self, error: Option<&Error>) -> Result<()> {
+        if self.send_closed.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+        self.send_credits.close();
+        let frame = match self.side {
+            Side::Client => self.frame(FrameType::StreamEnd, Vec::new(), 0),
+            Side::Server => RpcResponse::stream_end(self.request_id.clone(), error).encode_bytes(),
+        };
+        self.channel.send(frame).await
+    }
+
+    /// 取消流并通知对端
+    pub(crate) async fn send_cancel(&self) -> Result<()> {
+        self.abort(Error::Cancelled);
+        self.remove();
+        self.channel
+            .send(self.frame(FrameType::StreamCancel, Vec::new(), 0))
+            .await
+    }
+
+    /// 授予对端更多发送配额
+    pub(crate) async fn grant(&self, credit: u32) -> Result<()> {
+        self.recv_credits.fetch_add(credit, Ordering::AcqRel);
+        self.channel
+            .send(self.frame(FrameType::StreamCredit, Vec::new(), credit))
+            .await
+    }
+
+    /// 收到对端的消息，对端超出配额时返回`false`
+    pub(crate) fn on_data(&self, payload: Vec<u8>) -> bool {
+        let within_credit = self
+            .recv_credits
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |credit| credit.checked_sub(1))
+            .is_ok();
+        if !within_credit {
+            return false;
+        }
+        if let Some(tx) = self.inbound.lock().as_ref() {
+            let _ = tx.send(Ok(Bytes::from(payload)));
+        }
+        true
+    }
+
+    /// 收到对端授予的发送配额
+    pub(crate) fn on_credit(&self, credit: u32) {
+        let room = Semaphore::MAX_PERMITS - self.send_credits.available_permits();
+        self.send_credits.add_permits((credit as usize).min(room));
+    }
+
+    /// 对端结束了发送方向，`error`为对端报告的失败
+    pub(crate) fn on_end(&self, error: Option<Error>) {
+        if let Some(tx) = self.inbound.lock().take() {
+            if let Some(error) = error {
+                let _ = tx.send(Err(error));
+            }
+        }
+    }
+
+    /// 关闭本端发送方向，不通知对端
+    pub(crate) fn close_send(&self) {
+        self.send_closed.store(true, Ordering::Release);
+        self.send_credits.close();
+    }
+
+    /// 终止流的两个方向，等待中的收发以`error`结束
+    pub(crate) fn abort(&self, error: Error) {
+        self.close_send();
+        self.on_end(Some(error));
+    }
+
+    /// 触发服务端处理器的取消令牌并中止处理任务
+    pub(crate) fn abort_task(&self) {
+        if let Some((task, cancellation)) = self.task.lock().take() {
+            cancellation.cancel();
+            task.abort();
+        }
+    }
+
+    /// 从流表中移除
+    pub(crate) fn remove(&self) {
+        if let Some(table) = self.table.upgrade() {
+            table.remove(&self.request_id);
+        }
+    }
+
+    fn inbound_open(&self) -> bool {
+        self.inbound.lock().is_some()
+    }
+}
+
+fn stream_closed() -> Error {
+    Error::Connection("RPC stream closed".to_string())
+}
+
+/// 在当前运行时上执行清理任务，运行时已关闭时直接放弃
+pub(crate) fn spawn_cleanup<F>(cleanup: F)
+where
+    F: std::future::Future<Output = ()> + Send + 'static,
+{
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        handle.spawn(cleanup);
+    }
+}
+
+/// 流的发送端，传输未解码的消息
+///
+/// 客户端的发送端被丢弃时自动结束发送方向。服务端处理器返回后由`RpcServer`
+/// 结束流，无需手动调用`finish`。
+pub struct StreamWriter {
+    state: Arc<StreamState>,
+}
+
+impl StreamWriter {
+    pub(crate) fn new(state: Arc<StreamState>) -> Self {
+        Self { state }
+    }
+
+    /// 发送一条消息，对端配额用尽时等待
+    pub async fn send(&self, payload: Bytes) -> Result<()> {
+        self.state.send_data(payload).await
+    }
+
+    /// 流消息的编解码器
+    pub fn codec(&self) -> Codec {
+        self.state.codec
+    }
+
+    /// 结束发送方向
+    pub async fn finish(self) -> Result<()> {
+        self.state.send_end(None).await
+    }
+}
+
+impl Drop for StreamWriter {
+    fn drop(&mut self) {
+        if self.state.side == Side::Client && !self.state.send_closed.load(Ordering::Acquire) {
+            let state = Arc::clone(&self.state);
+            spawn_cleanup(async move {
+                let _ = state.send_end(None).await;
+            });
+        }
+    }
+}
+
+/// 流的接收端，传输未解码的消息
+///
+/// 客户端在流结束前丢弃接收端会取消整个流。
+pub struct StreamReader {
+    state: Arc<StreamState>,
+    rx: mpsc::UnboundedReceiver<Result<Bytes>>,
+    /// 已消费但尚未归还给对端的配额
+    consumed: u32,
+}
+
+impl StreamReader {
+    /// 接收下一条消息，对端正常结束后返回`None`
+    pub async fn next(&mut self) -> Option<Result<Bytes>> {
+        let item = self.rx.recv().await?;
+        if item.is_ok() {
+            // 消费过半窗口后批量归还配额，减少控制帧数量
+            self.consumed += 1;
+            if self.consumed >= (self.state.window / 2).max(1) {
+                let credit = std::mem::take(&mut self.consumed);
+                if let Err(e) = self.state.grant(credit).await {
+                    tracing::debug!("Failed to grant stream credit: {}", e);
+                }
+            }
+        }
+        Some(item)
+    }
+
+    /// 取消流，对端停止收发
+    pub async fn cancel(self) -> Result<()> {
+        self.state.send_cancel().await
+    }
+
+    /// 流消息的编解码器，服务端也用它解码打开流时携带的参数
+    pub fn codec(&self) -> Codec {
+        self.state.codec
+    }
+}
+
+impl Drop for StreamReader {
+    fn drop(&mut self) {
+        if self.state.side == Side::Client && self.state.inbound_open() {
+            let state = Arc::clone(&self.state);
+            spawn_cleanup(async move {
+                let _ = state.send_cancel().await;
+            });
+        }
+    }
+}
+
+/// 类型化的流发送端，消息按流的编解码器编码
+pub struct StreamSender<T> {
+    writer: StreamWriter,
+    _marker: PhantomData<fn(T)>,
+}
+
+impl<T: Encode> StreamSender<T> {
+    pub fn new(writer: StreamWriter) -> Self {
+        Self {
+            writer,
+            _marker: PhantomData,
+        }
+    }
+
+    /// 发送一条消息，对端配额用尽时等待
+    pub async fn send(&self, item: T) -> Result<()> {
+        let payload = self.writer.codec().encode(&item)?;
+        self.writer.send(Bytes::from(payload)).await
+    }
+
+    /// 结束发送方向
+    pub async fn finish(self) -> Result<()> {
+        self.writer.finish().await
+    }
+
+    /// 取出未类型化的发送端
+    pub fn into_inner(self) -> StreamWriter {
+        self.writer
+    }
+}
+
+/// 类型化的流接收端，消息按流的编解码器解码
+pub struct StreamReceiver<T> {
+    reader: StreamReader,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Decode> StreamReceiver<T> {
+    pub fn new(reader: StreamReader) -> Self {
+        Self {
+            reader,
+            _marker: PhantomData,
+        }
+    }
+
+    /// 接收下一条消息，对端正常结束后返回`None`
+    pub async fn next(&mut self) -> Option<Result<T>> {
+        let codec = self.reader.codec();
+        let item = self.reader.next().await?;
+        Some(item.and_then(|payload| codec.decode(&payload)))
+    }
+
+    /// 取消流，对端停止收发
+    pub async fn cancel(self) -> Result<()> {
+        self.reader.cancel().await
+    }
+
+    /// 取出未类型化的接收端
+    pub fn into_inner(self) -> StreamReader {
+        self.reader
+    }
+}
+
+/// 客户端流调用：发送一组消息后等待单个响应
+pub struct ClientStreaming<Req, Resp> {
+    sender: StreamSender<Req>,
+    receiver: StreamReceiver<Resp>,
+}
+
+impl<Req: Encode, Resp: Decode> ClientStreaming<Req, Resp> {
+    pub fn new(sender: StreamSender<Req>, receiver: StreamReceiver<Resp>) -> Self {
+        Self { sender, receiver }
+    }
+
+    /// 发送一条消息
+    ///
+    /// 服务端提前结束时发送失败，调用`finish`可取得服务端返回的错误。
+    pub async fn send(&self, item: Req) -> Result<()> {
+        self.sender.send(item).await
+    }
+
+    /// 结束发送并等待响应
+    pub async fn finish(self) -> Result<Resp> {
+        let Self { sender, mut receiver } = self;
+        sender.finish().await?;
+
+        let response = match receiver.next().await {
+            Some(response) => response?,
+            None => return Err(Error::Internal("stream ended without a response".to_string())),
+        };
+        match receiver.next().await {
+            None => Ok(response),
+            Some(Err(e)) => Err(e),
+            Some(Ok(_)) => Err(Error::Internal("stream returned more than one response".to_string())),
+        }
+    }
+}
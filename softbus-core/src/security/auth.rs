@@ -1,36 +1,127 @@
 //! 身份认证
 
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
 use crate::{Error, Result, DeviceId};
+use super::handshake::DeviceIdentity;
+use super::token::{Revocation, RevocationKey, TokenClaims, TOKEN_PREFIX};
+use super::trust_store::{MemoryTrustStore, TrustStore};
+
+/// 验证令牌签发时间时容忍的时钟偏差（秒）
+const CLOCK_SKEW: i64 = 60;
 
 /// 认证凭证
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Credential {
     pub device_id: DeviceId,
     pub token: String,
     pub expires_at: i64,
 }
 
+/// 设备的信任级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrustLevel {
+    /// 拒绝该设备
+    Blocked,
+    /// 已知但不受信任，例如被暂停信任的已配对设备
+    Known,
+    /// 受信任
+    Trusted,
+}
+
+/// 设备的信任记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustRecord {
+    pub device_id: DeviceId,
+    /// 对端的Ed25519身份公钥，未经配对直接信任的设备为`None`
+    pub public_key: Option<Vec<u8>>,
+    pub trust_level: TrustLevel,
+    /// 配对或首次信任的时间（Unix秒）
+    pub paired_at: i64,
+    /// 最近一次握手成功的时间（Unix秒）
+    pub last_seen: Option<i64>,
+}
+
 /// 认证管理器
 /// 
-/// 负责设备身份认证和授权
+/// 负责设备身份认证和授权。信任记录、凭证和吊销记录写入`TrustStore`，修改
+/// 方法在写入成功后才更新内存中的副本。设置了本设备身份时可以签发令牌，
+/// 只接受本设备和`with_token_issuer`指定的签发者签发的令牌。
 pub struct AuthManager {
     credentials: Arc<DashMap<DeviceId, Credential>>,
-    trusted_devices: Arc<DashMap<DeviceId, bool>>,
+    trust_records: Arc<DashMap<DeviceId, TrustRecord>>,
+    /// 按签发者和令牌ID索引，吊销只对同一签发者的令牌生效
+    revocations: Arc<DashMap<RevocationKey, Revocation>>,
+    identity: Option<Arc<DeviceIdentity>>,
+    /// 本设备之外接受的令牌签发者及其公钥
+    issuers: HashMap<DeviceId, Vec<u8>>,
+    store: Arc<dyn TrustStore>,
 }
 
 impl AuthManager {
-    /// 创建新的认证管理器
+    /// 创建新的认证管理器，信任记录和凭证只保存在内存中
     pub fn new() -> Self {
         Self {
             credentials: Arc::new(DashMap::new()),
-            trusted_devices: Arc::new(DashMap::new()),
+            trust_records: Arc::new(DashMap::new()),
+            revocations: Arc::new(DashMap::new()),
+            identity: None,
+            issuers: HashMap::new(),
+            store: Arc::new(MemoryTrustStore::new()),
+        }
+    }
+
+    /// 创建使用`store`的认证管理器，并载入其中的信任记录、凭证和吊销记录
+    pub fn with_store(store: Arc<dyn TrustStore>) -> Result<Self> {
+        let credentials = DashMap::new();
+        for credential in store.credentials()? {
+            credentials.insert(credential.device_id.clone(), credential);
+        }
+        let trust_records = DashMap::new();
+        for record in store.records()? {
+            trust_records.insert(record.device_id.clone(), record);
+        }
+        let revocations = DashMap::new();
+        for revocation in store.revocations()? {
+            revocations.insert(revocation.key(), revocation);
         }
+        Ok(Self {
+            credentials: Arc::new(credentials),
+            trust_records: Arc::new(trust_records),
+            revocations: Arc::new(revocations),
+            identity: None,
+            issuers: HashMap::new(),
+            store,
+        })
     }
 
-    /// 验证设备
+    /// 设置本设备的身份，用于签发、刷新和吊销令牌
+    pub fn with_identity(mut self, identity: Arc<DeviceIdentity>) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    /// 接受`issuer`用`public_key`签名的令牌和吊销记录
+    ///
+    /// 信任记录只表示信任设备本身，受信任的设备不能因此为其他设备签发令牌。
+    pub fn with_token_issuer(mut self, issuer: DeviceId, public_key: Vec<u8>) -> Self {
+        self.issuers.insert(issuer, public_key);
+        self
+    }
+
+    /// 用添加的凭证验证设备
+    ///
+    /// `device_id`可能是对端自称的ID，签名令牌不能用于这种验证，需要用
+    /// `authenticate_verified`验证。
     pub fn authenticate(&self, device_id: &DeviceId, token: &str) -> Result<()> {
+        if token.starts_with(TOKEN_PREFIX) {
+            return Err(Error::Authentication("Signed tokens require a verified peer".to_string()));
+        }
+
         if let Some(cred) = self.credentials.get(device_id) {
             if cred.token == token {
                 // 检查是否过期
@@ -46,29 +137,92 @@ impl AuthManager {
         Err(Error::Authentication("Invalid credentials".to_string()))
     }
 
+    /// 验证握手验证过的对端`peer`出示的令牌
+    ///
+    /// 签名令牌按`verify_token`验证并且必须签发给`peer`，其他令牌与添加的凭证
+    /// 比较。
+    pub fn authenticate_verified(&self, peer: &DeviceId, token: &str) -> Result<()> {
+        if !token.starts_with(TOKEN_PREFIX) {
+            return self.authenticate(peer, token);
+        }
+        let claims = self.verify_token(token)?;
+        if claims.device_id != *peer {
+            return Err(Error::Authentication("Token was issued to another device".to_string()));
+        }
+        Ok(())
+    }
+
     /// 添加凭证
-    pub fn add_credential(&self, credential: Credential) {
+    pub fn add_credential(&self, credential: Credential) -> Result<()> {
+        self.store.put_credential(&credential)?;
         self.credentials.insert(credential.device_id.clone(), credential);
+        Ok(())
     }
 
     /// 移除凭证
-    pub fn remove_credential(&self, device_id: &DeviceId) {
+    pub fn remove_credential(&self, device_id: &DeviceId) -> Result<()> {
+        self.store.remove_credential(device_id)?;
         self.credentials.remove(device_id);
+        Ok(())
     }
 
     /// 添加信任设备
-    pub fn trust_device(&self, device_id: DeviceId) {
-        self.trusted_devices.insert(device_id, true);
+    pub fn trust_device(&self, device_id: DeviceId) -> Result<()> {
+        self.set_trust_level(device_id, TrustLevel::Trusted)
+    }
+
+    /// 设置设备的信任级别，没有记录的设备新建一条不含公钥的记录
+    pub fn set_trust_level(&self, device_id: DeviceId, trust_level: TrustLevel) -> Result<()> {
+        let record = match self.trust_record(&device_id) {
+            Some(record) => TrustRecord { trust_level, ..record },
+            None => TrustRecord {
+                device_id,
+                public_key: None,
+                trust_level,
+                paired_at: chrono::Utc::now().timestamp(),
+                last_seen: None,
+            },
+        };
+        self.add_trust_record(record)
+    }
+
+    /// 移除信任设备及其信任记录
+    pub fn untrust_device(&self, device_id: &DeviceId) -> Result<()> {
+        self.store.remove_record(device_id)?;
+        self.trust_records.remove(device_id);
+        Ok(())
+    }
+
+    /// 写入信任记录，例如配对的结果
+    pub fn add_trust_record(&self, record: TrustRecord) -> Result<()> {
+        self.store.put_record(&record)?;
+        self.trust_records.insert(record.device_id.clone(), record);
+        Ok(())
     }
 
-    /// 移除信任设备
-    pub fn untrust_device(&self, device_id: &DeviceId) {
-        self.trusted_devices.remove(device_id);
+    /// 设备的信任记录，未知的设备为`None`
+    pub fn trust_record(&self, device_id: &DeviceId) -> Option<TrustRecord> {
+        self.trust_records.get(device_id).map(|record| record.clone())
+    }
+
+    /// 记录与设备的一次成功握手，未知的设备忽略
+    pub fn record_seen(&self, device_id: &DeviceId) -> Result<()> {
+        match self.trust_record(device_id) {
+            Some(record) => self.add_trust_record(TrustRecord {
+                last_seen: Some(chrono::Utc::now().timestamp()),
+                ..record
+            }),
+            None => Ok(()),
+        }
     }
 
     /// 检查设备是否受信任
+    ///
+    /// 应传入经过验证的设备ID，例如`HandshakeOutcome::peer`，而不是对端自称的ID。
     pub fn is_trusted(&self, device_id: &DeviceId) -> bool {
-        self.trusted_devices.contains_key(device_id)
+        self.trust_records
+            .get(device_id)
+            .is_some_and(|record| record.trust_level == TrustLevel::Trusted)
     }
 
     /// 生成新的token
@@ -76,6 +230,156 @@ impl AuthManager {
         use uuid::Uuid;
         Uuid::new_v4().to_string()
     }
+
+    /// 为设备签发有效期为`ttl`的签名令牌，过期时间超出范围时返回错误
+    pub fn issue_token(&self, device_id: &DeviceId, scopes: &[&str], ttl: Duration) -> Result<String> {
+        let identity = self.signing_identity()?;
+        let now = chrono::Utc::now().timestamp();
+        let expires_at = i64::try_from(ttl.as_secs())
+            .ok()
+            .and_then(|ttl| now.checked_add(ttl))
+            .ok_or_else(|| Error::Authentication("Token lifetime is too long".to_string()))?;
+        TokenClaims {
+            token_id: self.generate_token(),
+            issuer: identity.device_id().clone(),
+            device_id: device_id.clone(),
+            scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+            issued_at: now,
+            expires_at,
+        }
+        .sign(identity)
+    }
+
+    /// 离线验证签名令牌
+    ///
+    /// 签发者必须是本设备或`with_token_issuer`指定的签发者。令牌未过期且未被
+    /// 吊销时返回其中的声明。
+    pub fn verify_token(&self, token: &str) -> Result<TokenClaims> {
+        let claims = TokenClaims::verify(token, |issuer| self.issuer_key(issuer))?;
+        let now = chrono::Utc::now().timestamp();
+        if claims.issued_at > now + CLOCK_SKEW {
+            return Err(Error::Authentication("Token issued in the future".to_string()));
+        }
+        if claims.expires_at <= now {
+            return Err(Error::Authentication("Token expired".to_string()));
+        }
+        if self.revocations.contains_key(&(claims.issuer.clone(), claims.token_id.clone())) {
+            return Err(Error::Authentication("Token revoked".to_string()));
+        }
+        Ok(claims)
+    }
+
+    /// 在过期前刷新本设备签发的令牌，新令牌的权限范围不变、有效期为`ttl`
+    ///
+    /// 旧令牌在过期前仍然有效，需要立即失效时用`revoke_token`吊销。
+    pub fn refresh_token(&self, token: &str, ttl: Duration) -> Result<String> {
+        let claims = self.verify_token(token)?;
+        if claims.issuer != *self.signing_identity()?.device_id() {
+            return Err(Error::Authentication("Token was issued by another device".to_string()));
+        }
+        let scopes: Vec<&str> = claims.scopes.iter().map(String::as_str).collect();
+        self.issue_token(&claims.device_id, &scopes, ttl)
+    }
+
+    /// 吊销本设备签发的令牌
+    ///
+    /// 返回的吊销记录可以用`RevocationService::publish`推送给对端。
+    pub fn revoke_token(&self, token: &str) -> Result<Revocation> {
+        let identity = self.signing_identity()?;
+        let claims = TokenClaims::verify(token, |issuer| match issuer == identity.device_id() {
+            true => Ok(identity.public_key().to_vec()),
+            false => Err(Error::Authentication("Token was issued by another device".to_string())),
+        })?;
+        let revocation = Revocation::new(identity, &claims);
+        self.store.put_revocation(&revocation)?;
+        self.revocations.insert(revocation.key(), revocation.clone());
+        Ok(revocation)
+    }
+
+    /// 应用对端传播的吊销记录，返回是否是新的吊销
+    ///
+    /// 吊销记录必须由其中的签发者签名，并且只对该签发者签发的令牌生效。
+    pub fn apply_revocation(&self, revocation: Revocation) -> Result<bool> {
+        if self.revocations.contains_key(&revocation.key()) {
+            return Ok(false);
+        }
+        revocation.verify(&self.issuer_key(&revocation.issuer)?)?;
+        if revocation.expires_at <= chrono::Utc::now().timestamp() {
+            return Ok(false);
+        }
+        self.store.put_revocation(&revocation)?;
+        self.revocations.insert(revocation.key(), revocation);
+        Ok(true)
+    }
+
+    /// 当前的吊销列表
+    pub fn revocations(&self) -> Vec<Revocation> {
+        self.revocations.iter().map(|entry| entry.value().clone()).collect()
+    }
+
+    /// 清除过期的凭证和吊销记录，返回清除的数量
+    pub fn purge_expired(&self) -> Result<usize> {
+        let now = chrono::Utc::now().timestamp();
+        let credentials: Vec<DeviceId> = self
+            .credentials
+            .iter()
+            .filter(|entry| entry.expires_at <= now)
+            .map(|entry| entry.key().clone())
+            .collect();
+        let revocations: Vec<RevocationKey> = self
+            .revocations
+            .iter()
+            .filter(|entry| entry.expires_at <= now)
+            .map(|entry| entry.key().clone())
+            .collect();
+
+        for device_id in &credentials {
+            self.remove_credential(device_id)?;
+        }
+        for key in &revocations {
+            self.store.remove_revocation(&key.0, &key.1)?;
+            self.revocations.remove(key);
+        }
+        Ok(credentials.len() + revocations.len())
+    }
+
+    /// 启动后台任务，每隔`interval`调用一次`purge_expired`
+    ///
+    /// 任务只持有弱引用，认证管理器释放后任务结束。
+    pub fn spawn_sweeper(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let manager = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let Some(manager) = manager.upgrade() else {
+                    return;
+                };
+                match manager.purge_expired() {
+                    Ok(0) => {}
+                    Ok(purged) => tracing::debug!("Purged {} expired credentials and revocations", purged),
+                    Err(e) => tracing::warn!("Failed to purge expired credentials: {}", e),
+                }
+            }
+        })
+    }
+
+    /// 签发者的公钥：本设备，或`with_token_issuer`指定的签发者
+    fn issuer_key(&self, issuer: &DeviceId) -> Result<Vec<u8>> {
+        if let Some(identity) = self.identity.as_ref().filter(|identity| identity.device_id() == issuer) {
+            return Ok(identity.public_key().to_vec());
+        }
+        self.issuers
+            .get(issuer)
+            .cloned()
+            .ok_or_else(|| Error::Authentication(format!("Unknown token issuer {}", issuer)))
+    }
+
+    fn signing_identity(&self) -> Result<&DeviceIdentity> {
+        self.identity
+            .as_deref()
+            .ok_or_else(|| Error::Authentication("No identity to sign tokens".to_string()))
+    }
 }
 
 impl Default for AuthManager {
@@ -87,20 +391,233 @@ impl Default for AuthManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::security::trust_store::FileTrustStore;
 
     #[test]
     fn test_auth_manager() {
         let manager = AuthManager::new();
         let device_id = DeviceId::new();
         
-        manager.trust_device(device_id.clone());
+        manager.trust_device(device_id.clone()).unwrap();
         assert!(manager.is_trusted(&device_id));
     }
 
+    #[test]
+    fn test_trust_survives_restart() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("trust.json");
+        let open = || AuthManager::with_store(Arc::new(FileTrustStore::open_encrypted(&path, &[9; 32]).unwrap())).unwrap();
+        let (phone, tablet, stranger) = (DeviceId::new(), DeviceId::new(), DeviceId::new());
+
+        let manager = open();
+        manager
+            .add_trust_record(TrustRecord {
+                device_id: phone.clone(),
+                public_key: Some(vec![1; 32]),
+                trust_level: TrustLevel::Trusted,
+                paired_at: 1_700_000_000,
+                last_seen: None,
+            })
+            .unwrap();
+        manager.trust_device(tablet.clone()).unwrap();
+        manager.set_trust_level(stranger.clone(), TrustLevel::Blocked).unwrap();
+        manager.record_seen(&phone).unwrap();
+        manager
+            .add_credential(Credential {
+                device_id: phone.clone(),
+                token: "secret".to_string(),
+                expires_at: chrono::Utc::now().timestamp() + 3600,
+            })
+            .unwrap();
+        manager.set_trust_level(tablet.clone(), TrustLevel::Known).unwrap();
+        drop(manager);
+
+        let manager = open();
+        assert!(manager.is_trusted(&phone));
+        assert!(!manager.is_trusted(&tablet));
+        assert!(!manager.is_trusted(&stranger));
+        let record = manager.trust_record(&phone).unwrap();
+        assert_eq!(record.public_key, Some(vec![1; 32]));
+        assert_eq!(record.paired_at, 1_700_000_000);
+        assert!(record.last_seen.is_some());
+        assert_eq!(manager.trust_record(&stranger).unwrap().trust_level, TrustLevel::Blocked);
+        assert!(manager.authenticate(&phone, "secret").is_ok());
+
+        manager.untrust_device(&phone).unwrap();
+        manager.remove_credential(&phone).unwrap();
+        drop(manager);
+        let manager = open();
+        assert!(manager.trust_record(&phone).is_none());
+        assert!(manager.authenticate(&phone, "secret").is_err());
+    }
+
     #[test]
     fn test_generate_token() {
         let manager = AuthManager::new();
         let token = manager.generate_token();
         assert!(!token.is_empty());
     }
+
+    /// 签发者和接受其令牌的另一个节点
+    fn issuer_and_verifier() -> (AuthManager, AuthManager) {
+        let identity = Arc::new(DeviceIdentity::generate().unwrap());
+        let verifier =
+            AuthManager::new().with_token_issuer(identity.device_id().clone(), identity.public_key().to_vec());
+        (AuthManager::new().with_identity(identity), verifier)
+    }
+
+    #[test]
+    fn test_signed_tokens() {
+        let (issuer, verifier) = issuer_and_verifier();
+        let phone = DeviceId::new();
+        let token = issuer.issue_token(&phone, &["camera.capture"], Duration::from_secs(600)).unwrap();
+
+        // 接受签发者的节点离线验证
+        let claims = verifier.verify_token(&token).unwrap();
+        assert_eq!(claims.device_id, phone);
+        assert!(claims.has_scope("camera.capture"));
+        assert!(issuer.authenticate_verified(&phone, &token).is_ok());
+        assert!(verifier.authenticate_verified(&phone, &token).is_ok());
+        assert!(verifier.authenticate_verified(&DeviceId::new(), &token).is_err());
+
+        // 对端自称的ID不能用签名令牌验证
+        assert!(verifier.authenticate(&phone, &token).is_err());
+
+        // 不认识签发者、令牌过期
+        assert!(AuthManager::new().verify_token(&token).is_err());
+        let expired = issuer.issue_token(&phone, &[], Duration::ZERO).unwrap();
+        assert!(matches!(verifier.verify_token(&expired), Err(Error::Authentication(msg)) if msg.contains("expired")));
+
+        // 没有身份的节点不能签发
+        assert!(verifier.issue_token(&phone, &[], Duration::from_secs(600)).is_err());
+
+        // 过期时间溢出时拒绝签发，而不是得到已经过期的令牌
+        for ttl in [Duration::MAX, Duration::from_secs(i64::MAX as u64)] {
+            assert!(matches!(issuer.issue_token(&phone, &[], ttl), Err(Error::Authentication(_))));
+        }
+    }
+
+    #[test]
+    fn test_refresh_token() {
+        let (issuer, verifier) = issuer_and_verifier();
+        let phone = DeviceId::new();
+        let token = issuer.issue_token(&phone, &["camera.capture"], Duration::from_secs(30)).unwrap();
+
+        let refreshed = issuer.refresh_token(&token, Duration::from_secs(3600)).unwrap();
+        let (old, new) = (verifier.verify_token(&token).unwrap(), verifier.verify_token(&refreshed).unwrap());
+        assert_ne!(old.token_id, new.token_id);
+        assert_eq!((&old.device_id, &old.scopes), (&new.device_id, &new.scopes));
+        assert!(old.expires_within(Duration::from_secs(60)));
+        assert!(!new.expires_within(Duration::from_secs(60)));
+
+        // 只有签发者能刷新，过期的令牌不能刷新
+        assert!(verifier.refresh_token(&token, Duration::from_secs(3600)).is_err());
+        let expired = issuer.issue_token(&phone, &[], Duration::ZERO).unwrap();
+        assert!(issuer.refresh_token(&expired, Duration::from_secs(3600)).is_err());
+    }
+
+    #[test]
+    fn test_revocation() {
+        let (issuer, verifier) = issuer_and_verifier();
+        let token = issuer.issue_token(&DeviceId::new(), &[], Duration::from_secs(600)).unwrap();
+        let revocation = issuer.revoke_token(&token).unwrap();
+        assert!(matches!(issuer.verify_token(&token), Err(Error::Authentication(msg)) if msg.contains("revoked")));
+        assert!(issuer.refresh_token(&token, Duration::from_secs(600)).is_err());
+        assert!(verifier.revoke_token(&token).is_err());
+
+        // 对端应用签发者的吊销，重复应用不产生变化
+        assert!(verifier.verify_token(&token).is_ok());
+        assert!(verifier.apply_revocation(revocation.clone()).unwrap());
+        assert!(!verifier.apply_revocation(revocation.clone()).unwrap());
+        assert!(verifier.verify_token(&token).is_err());
+        assert_eq!(verifier.revocations(), vec![revocation]);
+
+        // 未知签发者的吊销被拒绝
+        let stranger = AuthManager::new().with_identity(Arc::new(DeviceIdentity::generate().unwrap()));
+        let other = stranger.issue_token(&DeviceId::new(), &[], Duration::from_secs(600)).unwrap();
+        let forged = stranger.revoke_token(&other).unwrap();
+        assert!(verifier.apply_revocation(forged).is_err());
+    }
+
+    #[test]
+    fn test_revocations_only_apply_to_their_issuer() {
+        let (issuer, verifier) = issuer_and_verifier();
+        let token = issuer.issue_token(&DeviceId::new(), &[], Duration::from_secs(600)).unwrap();
+        let claims = verifier.verify_token(&token).unwrap();
+
+        // 另一个签发者用自己的身份签名，声称吊销该令牌ID
+        let mallory = DeviceIdentity::generate().unwrap();
+        let verifier = verifier.with_token_issuer(mallory.device_id().clone(), mallory.public_key().to_vec());
+        let forged = Revocation::new(
+            &mallory,
+            &TokenClaims {
+                issuer: mallory.device_id().clone(),
+                ..claims.clone()
+            },
+        );
+        assert_eq!(forged.token_id, claims.token_id);
+        assert!(verifier.apply_revocation(forged).unwrap());
+        assert!(verifier.verify_token(&token).is_ok());
+
+        // 签发者自己的吊销仍然生效
+        assert!(verifier.apply_revocation(issuer.revoke_token(&token).unwrap()).unwrap());
+        assert!(verifier.verify_token(&token).is_err());
+        assert_eq!(verifier.revocations().len(), 2);
+    }
+
+    #[test]
+    fn test_trusted_devices_do_not_issue_tokens() {
+        let mallory = AuthManager::new().with_identity(Arc::new(DeviceIdentity::generate().unwrap()));
+        let identity = mallory.signing_identity().unwrap();
+        let verifier = AuthManager::new().with_identity(Arc::new(DeviceIdentity::generate().unwrap()));
+        verifier
+            .add_trust_record(TrustRecord {
+                device_id: identity.device_id().clone(),
+                public_key: Some(identity.public_key().to_vec()),
+                trust_level: TrustLevel::Trusted,
+                paired_at: 0,
+                last_seen: None,
+            })
+            .unwrap();
+
+        // 受信任的设备为自己或其他设备签发的令牌都不被接受
+        let phone = DeviceId::new();
+        for device_id in [identity.device_id(), &phone] {
+            let token = mallory.issue_token(device_id, &["camera.capture"], Duration::from_secs(600)).unwrap();
+            assert!(verifier.authenticate_verified(device_id, &token).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sweeper_purges_expired_credentials() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = Arc::new(FileTrustStore::open(dir.path().join("trust.json")).unwrap());
+        let identity = Arc::new(DeviceIdentity::generate().unwrap());
+        let manager = Arc::new(AuthManager::with_store(store.clone()).unwrap().with_identity(identity));
+        let (stale, fresh) = (DeviceId::new(), DeviceId::new());
+        let now = chrono::Utc::now().timestamp();
+        for (device_id, expires_at) in [(&stale, now - 1), (&fresh, now + 3600)] {
+            manager
+                .add_credential(Credential {
+                    device_id: device_id.clone(),
+                    token: "secret".to_string(),
+                    expires_at,
+                })
+                .unwrap();
+        }
+        let expired = manager.issue_token(&stale, &[], Duration::ZERO).unwrap();
+        manager.revoke_token(&expired).unwrap();
+
+        let sweeper = manager.spawn_sweeper(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(manager.authenticate(&fresh, "secret").is_ok());
+        assert!(manager.authenticate(&stale, "secret").is_err());
+        assert!(manager.revocations().is_empty());
+        assert_eq!(store.credentials().unwrap().len(), 1);
+        assert!(store.revocations().unwrap().is_empty());
+
+        // 认证管理器释放后任务结束
+        drop(manager);
+        tokio::time::timeout(Duration::from_secs(1), sweeper).await.unwrap().unwrap();
+    }
 }
@@ -1,6 +1,5 @@
 //! 加密模块
 
-use bytes::Bytes;
 use ring::aead::{Aad, BoundKey, Nonce, NonceSequence, OpeningKey, SealingKey, UnboundKey, AES_256_GCM};
 use ring::error::Unspecified;
 use ring::rand::{SecureRandom, SystemRandom};
@@ -40,7 +39,6 @@ impl CryptoManager {
         let unbound_key = UnboundKey::new(&AES_256_GCM, key)
             .map_err(|_| Error::Encryption("Invalid key".to_string()))?;
         
-        let nonce = Nonce::assume_unique_for_key(nonce_bytes);
         let mut sealing_key = SealingKey::new(unbound_key, CounterNonce::new(nonce_bytes));
 
         let mut in_out = plaintext.to_vec();
@@ -85,19 +83,31 @@ impl Default for CryptoManager {
 }
 
 /// 简单的计数器nonce序列
+///
+/// 从初始nonce开始，每次把末尾8字节作为大端计数器加一，计数器用尽时报错
 struct CounterNonce {
     nonce: [u8; NONCE_LEN],
+    exhausted: bool,
 }
 
 impl CounterNonce {
     fn new(nonce: [u8; NONCE_LEN]) -> Self {
-        Self { nonce }
+        Self { nonce, exhausted: false }
     }
 }
 
 impl NonceSequence for CounterNonce {
     fn advance(&mut self) -> std::result::Result<Nonce, Unspecified> {
-        Ok(Nonce::assume_unique_for_key(self.nonce))
+        if self.exhausted {
+            return Err(Unspecified);
+        }
+        let current = Nonce::assume_unique_for_key(self.nonce);
+        let counter = u64::from_be_bytes(self.nonce[NONCE_LEN - 8..].try_into().unwrap());
+        match counter.checked_add(1) {
+            Some(next) => self.nonce[NONCE_LEN - 8..].copy_from_slice(&next.to_be_bytes()),
+            None => self.exhausted = true,
+        }
+        Ok(current)
     }
 }
 
@@ -116,4 +126,19 @@ mod tests {
         
         assert_eq!(plaintext, decrypted.as_slice());
     }
+
+    #[test]
+    fn test_counter_nonce_advances() {
+        let mut nonces = CounterNonce::new([0xff; NONCE_LEN]);
+        assert!(nonces.advance().is_ok());
+        assert!(nonces.advance().is_err());
+
+        let mut start = [0u8; NONCE_LEN];
+        start[NONCE_LEN - 1] = 1;
+        let mut nonces = CounterNonce::new(start);
+        let first = nonces.advance().unwrap();
+        let second = nonces.advance().unwrap();
+        assert_eq!(first.as_ref()[NONCE_LEN - 1], 1);
+        assert_eq!(second.as_ref()[NONCE_LEN - 1], 2);
+    }
 }
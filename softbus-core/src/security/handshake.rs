@@ -0,0 +1,451 @@
+//! 设备间的认证密钥交换
+//!
+//! 双方用长期Ed25519身份密钥互相认证，用临时X25519密钥协商会话密钥。设备ID
+//! 由身份公钥派生，验证签名即证明对端拥有这个设备ID，对端自称的ID不被采信。
+//!
+//! 握手共三条消息：
+//! 1. 发起方 -> 响应方：协议版本和临时公钥
+//! 2. 响应方 -> 发起方：临时公钥和身份证明
+//! 3. 发起方 -> 响应方：身份证明
+//!
+//! 身份证明包含身份公钥、对双方临时公钥的签名，以及用协商出的密钥对身份公钥
+//! 计算的MAC，把身份绑定到本次握手的密钥上。
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use bytes::Bytes;
+use ring::agreement::{self, EphemeralPrivateKey, X25519};
+use ring::hkdf::{self, HKDF_SHA256};
+use ring::hmac;
+use ring::rand::SystemRandom;
+use ring::signature::{self, Ed25519KeyPair, KeyPair, ED25519};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use crate::{Channel, DeviceId, Error, Result};
+use super::auth::AuthManager;
+use super::session::{EncryptedChannel, KeyLen, SessionRole};
+
+/// 握手协议版本
+const PROTOCOL_VERSION: u8 = 1;
+
+/// 默认的握手超时
+const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// X25519和Ed25519公钥长度
+const PUBLIC_KEY_LEN: usize = 32;
+
+/// 设备的长期身份
+///
+/// 持有Ed25519密钥对，设备ID由公钥派生。`pkcs8`可以持久化，之后用`from_pkcs8`
+/// 恢复同一个身份。
+pub struct DeviceIdentity {
+    key_pair: Ed25519KeyPair,
+    pkcs8: Vec<u8>,
+    device_id: DeviceId,
+}
+
+impl DeviceIdentity {
+    /// 生成新的身份
+    pub fn generate() -> Result<Self> {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map_err(|_| Error::Encryption("Failed to generate identity key".to_string()))?;
+        Self::from_pkcs8(pkcs8.as_ref())
+    }
+
+    /// 从PKCS#8编码的密钥恢复身份
+    pub fn from_pkcs8(pkcs8: &[u8]) -> Result<Self> {
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8)
+            .map_err(|e| Error::Encryption(format!("Invalid identity key: {}", e)))?;
+        let device_id = DeviceId::from_public_key(key_pair.public_key().as_ref());
+        Ok(Self {
+            key_pair,
+            pkcs8: pkcs8.to_vec(),
+            device_id,
+        })
+    }
+
+    /// PKCS#8编码的密钥，用于持久化
+    pub fn pkcs8(&self) -> &[u8] {
+        &self.pkcs8
+    }
+
+    /// Ed25519公钥
+    pub fn public_key(&self) -> &[u8] {
+        self.key_pair.public_key().as_ref()
+    }
+
+    /// 由公钥派生的设备ID
+    pub fn device_id(&self) -> &DeviceId {
+        &self.device_id
+    }
+
+    /// 用身份密钥签名
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        self.key_pair.sign(message).as_ref().to_vec()
+    }
+}
+
+/// 握手结果
+pub struct HandshakeOutcome {
+    peer: DeviceId,
+    peer_public_key: Vec<u8>,
+    session_key: [u8; 32],
+    role: SessionRole,
+}
+
+impl HandshakeOutcome {
+    /// 由对端身份公钥验证的设备ID
+    pub fn peer(&self) -> &DeviceId {
+        &self.peer
+    }
+
+    /// 对端的Ed25519身份公钥
+    pub fn peer_public_key(&self) -> &[u8] {
+        &self.peer_public_key
+    }
+
+    /// 双方协商出的会话密钥
+    pub fn session_key(&self) -> &[u8; 32] {
+        &self.session_key
+    }
+
+    /// 本端在会话中的角色
+    pub fn role(&self) -> SessionRole {
+        self.role
+    }
+
+    /// 用会话密钥加密`inner`，通道的`verified_peer`报告握手验证的对端
+    pub fn into_channel(self, inner: Arc<dyn Channel>) -> Result<EncryptedChannel> {
+        Ok(EncryptedChannel::new(inner, &self.session_key, self.role)?.with_verified_peer(self.peer))
+    }
+}
+
+/// 第一条消息
+#[derive(Serialize, Deserialize)]
+struct Hello {
+    version: u8,
+    ephemeral: Vec<u8>,
+}
+
+/// 第二条消息
+#[derive(Serialize, Deserialize)]
+struct Reply {
+    ephemeral: Vec<u8>,
+    proof: Proof,
+}
+
+/// 一方的身份证明，也是第三条消息
+#[derive(Serialize, Deserialize)]
+struct Proof {
+    identity: Vec<u8>,
+    signature: Vec<u8>,
+    mac: Vec<u8>,
+}
+
+/// 认证密钥交换
+///
+/// 在明文通道上完成握手，之后用`HandshakeOutcome::into_channel`加密通道。
+/// 握手失败时调用方应关闭通道。
+#[derive(Clone)]
+pub struct Handshake {
+    identity: Arc<DeviceIdentity>,
+    auth: Option<Arc<AuthManager>>,
+    timeout: Duration,
+}
+
+impl Handshake {
+    /// 用本设备的身份创建握手
+    pub fn new(identity: Arc<DeviceIdentity>) -> Self {
+        Self {
+            identity,
+            auth: None,
+            timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+        }
+    }
+
+    /// 只接受`AuthManager`信任的对端，按验证后的设备ID检查
+    pub fn with_auth_manager(mut self, auth: Arc<AuthManager>) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// 设置握手超时
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 作为发起方握手
+    ///
+    /// 对端未受信任时不发送本端的身份证明。
+    pub async fn initiate(&self, channel: &dyn Channel) -> Result<HandshakeOutcome> {
+        self.bounded(async {
+            let (private, ephemeral) = ephemeral_key()?;
+            send(channel, &Hello {
+                version: PROTOCOL_VERSION,
+                ephemeral: ephemeral.clone(),
+            })
+            .await?;
+
+            let reply: Reply = recv(channel).await?;
+            let keys = Keys::agree(private, &reply.ephemeral, [&ephemeral, &reply.ephemeral])?;
+            let peer = keys.verify(SessionRole::Responder, &reply.proof)?;
+            self.check_trusted(&peer)?;
+            send(channel, &keys.prove(SessionRole::Initiator, &self.identity)).await?;
+            Ok(keys.finish(peer, reply.proof.identity, SessionRole::Initiator))
+        })
+        .await
+    }
+
+    /// 作为响应方握手
+    pub async fn respond(&self, channel: &dyn Channel) -> Result<HandshakeOutcome> {
+        self.bounded(async {
+            let hello: Hello = recv(channel).await?;
+            if hello.version != PROTOCOL_VERSION {
+                return Err(Error::Authentication(format!(
+                    "Unsupported handshake version {}",
+                    hello.version
+                )));
+            }
+
+            let (private, ephemeral) = ephemeral_key()?;
+            let keys = Keys::agree(private, &hello.ephemeral, [&hello.ephemeral, &ephemeral])?;
+            send(channel, &Reply {
+                ephemeral,
+                proof: keys.prove(SessionRole::Responder, &self.identity),
+            })
+            .await?;
+
+            let proof: Proof = recv(channel).await?;
+            let peer = keys.verify(SessionRole::Initiator, &proof)?;
+            self.check_trusted(&peer)?;
+            Ok(keys.finish(peer, proof.identity, SessionRole::Responder))
+        })
+        .await
+    }
+
+    async fn bounded<F>(&self, handshake: F) -> Result<HandshakeOutcome>
+    where
+        F: Future<Output = Result<HandshakeOutcome>>,
+    {
+        tokio::time::timeout(self.timeout, handshake)
+            .await
+            .map_err(|_| Error::Timeout)?
+    }
+
+    /// 按验证后的设备ID检查信任，并更新受信任设备的最近握手时间
+    fn check_trusted(&self, peer: &DeviceId) -> Result<()> {
+        let Some(auth) = &self.auth else {
+            return Ok(());
+        };
+        if !auth.is_trusted(peer) {
+            return Err(Error::Authentication(format!("Device {} is not trusted", peer)));
+        }
+        if let Err(e) = auth.record_seen(peer) {
+            tracing::warn!("Failed to record handshake with {}: {}", peer, e);
+        }
+        Ok(())
+    }
+}
+
+/// 由共享密钥派生的握手密钥
+struct Keys {
+    /// 发起方和响应方的临时公钥，签名的内容
+    transcript: Vec<u8>,
+    mac: hmac::Key,
+    session: [u8; 32],
+}
+
+impl Keys {
+    /// 与对端临时公钥协商，`ephemerals`按发起方、响应方的顺序
+    fn agree(private: EphemeralPrivateKey, peer: &[u8], ephemerals: [&[u8]; 2]) -> Result<Self> {
+        if ephemerals.iter().any(|key| key.len() != PUBLIC_KEY_LEN) {
+            return Err(Error::Authentication("Invalid ephemeral key".to_string()));
+        }
+        let transcript = ephemerals.concat();
+        let salt = hkdf::Salt::new(HKDF_SHA256, &transcript);
+        let prk = agreement::agree_ephemeral(
+            private,
+            &agreement::UnparsedPublicKey::new(&X25519, peer),
+            |shared| salt.extract(shared),
+        )
+        .map_err(|_| Error::Authentication("Key agreement failed".to_string()))?;
+
+        Ok(Self {
+            mac: hmac::Key::new(hmac::HMAC_SHA256, &expand(&prk, b"softbus handshake mac")?),
+            session: expand(&prk, b"softbus handshake session")?,
+            transcript,
+        })
+    }
+
+    /// 以`role`的身份证明本端
+    fn prove(&self, role: SessionRole, identity: &DeviceIdentity) -> Proof {
+        Proof {
+            identity: identity.public_key().to_vec(),
+            signature: identity.sign(&[proof_label(role), &self.transcript].concat()),
+            mac: hmac::sign(&self.mac, &[proof_label(role), identity.public_key()].concat())
+                .as_ref()
+                .to_vec(),
+        }
+    }
+
+    /// 验证对端以`role`给出的身份证明，返回由公钥派生的设备ID
+    fn verify(&self, role: SessionRole, proof: &Proof) -> Result<DeviceId> {
+        if proof.identity.len() != PUBLIC_KEY_LEN {
+            return Err(Error::Authentication("Invalid identity key".to_string()));
+        }
+        signature::UnparsedPublicKey::new(&ED25519, &proof.identity)
+            .verify(&[proof_label(role), &self.transcript].concat(), &proof.signature)
+            .map_err(|_| Error::Authentication("Invalid handshake signature".to_string()))?;
+        hmac::verify(&self.mac, &[proof_label(role), &proof.identity].concat(), &proof.mac)
+            .map_err(|_| Error::Authentication("Handshake key confirmation failed".to_string()))?;
+        Ok(DeviceId::from_public_key(&proof.identity))
+    }
+
+    fn finish(self, peer: DeviceId, peer_public_key: Vec<u8>, role: SessionRole) -> HandshakeOutcome {
+        HandshakeOutcome {
+            peer,
+            peer_public_key,
+            session_key: self.session,
+            role,
+        }
+    }
+}
+
+/// 身份证明的签名和MAC标签，区分双方的证明
+fn proof_label(role: SessionRole) -> &'static [u8] {
+    match role {
+        SessionRole::Initiator => b"softbus handshake initiator",
+        SessionRole::Responder => b"softbus handshake responder",
+    }
+}
+
+pub(super) fn expand(prk: &hkdf::Prk, label: &[u8]) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    prk.expand(&[label], KeyLen(key.len()))
+        .and_then(|okm| okm.fill(&mut key))
+        .map_err(|_| Error::Encryption("Key derivation failed".to_string()))?;
+    Ok(key)
+}
+
+pub(super) fn ephemeral_key() -> Result<(EphemeralPrivateKey, Vec<u8>)> {
+    let private = EphemeralPrivateKey::generate(&X25519, &SystemRandom::new())
+        .map_err(|_| Error::Encryption("Failed to generate ephemeral key".to_string()))?;
+    let public = private
+        .compute_public_key()
+        .map_err(|_| Error::Encryption("Failed to compute ephemeral key".to_string()))?;
+    Ok((private, public.as_ref().to_vec()))
+}
+
+async fn send<T: Serialize>(channel: &dyn Channel, message: &T) -> Result<()> {
+    let data = bincode::serialize(message).map_err(|e| Error::Serialization(e.to_string()))?;
+    channel.send(Bytes::from(data)).await
+}
+
+async fn recv<T: DeserializeOwned>(channel: &dyn Channel) -> Result<T> {
+    let data = channel.recv().await?;
+    bincode::deserialize(&data)
+        .map_err(|e| Error::Authentication(format!("Malformed handshake message: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::testing::pipe;
+
+    fn identity() -> Arc<DeviceIdentity> {
+        Arc::new(DeviceIdentity::generate().unwrap())
+    }
+
+    #[test]
+    fn test_identity_roundtrip() {
+        let identity = identity();
+        let restored = DeviceIdentity::from_pkcs8(identity.pkcs8()).unwrap();
+        assert_eq!(restored.public_key(), identity.public_key());
+        assert_eq!(restored.device_id(), identity.device_id());
+        assert_eq!(identity.device_id(), &DeviceId::from_public_key(identity.public_key()));
+        assert_eq!(identity.device_id().as_str().len(), 32);
+        assert!(DeviceIdentity::from_pkcs8(b"not a key").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handshake_agrees_on_session_key() {
+        let (phone, camera) = (identity(), identity());
+        let (a, b) = pipe();
+        let (initiator, responder) = (Handshake::new(Arc::clone(&phone)), Handshake::new(Arc::clone(&camera)));
+        let (initiator, responder) = tokio::join!(initiator.initiate(&*a), responder.respond(&*b));
+        let (initiator, responder) = (initiator.unwrap(), responder.unwrap());
+
+        assert_eq!(initiator.peer(), camera.device_id());
+        assert_eq!(initiator.peer_public_key(), camera.public_key());
+        assert_eq!(responder.peer(), phone.device_id());
+        assert_eq!(initiator.session_key(), responder.session_key());
+        assert_eq!(initiator.role(), SessionRole::Initiator);
+        assert_eq!(responder.role(), SessionRole::Responder);
+
+        let initiator = initiator.into_channel(a).unwrap();
+        let responder = responder.into_channel(b).unwrap();
+        assert_eq!(initiator.verified_peer().as_ref(), Some(camera.device_id()));
+        assert_eq!(responder.peer_device_id(), Some(phone.device_id().to_string()));
+        initiator.send(Bytes::from_static(b"open camera")).await.unwrap();
+        assert_eq!(responder.recv().await.unwrap(), "open camera");
+    }
+
+    #[tokio::test]
+    async fn test_untrusted_peers_are_rejected() {
+        let (phone, camera) = (identity(), identity());
+        let auth = Arc::new(AuthManager::new());
+        let initiator = Handshake::new(Arc::clone(&phone)).with_auth_manager(Arc::clone(&auth));
+        let responder = Handshake::new(Arc::clone(&camera))
+            .with_auth_manager(Arc::clone(&auth))
+            .with_timeout(Duration::from_millis(50));
+
+        // 发起方不信任相机，不发送自己的身份证明
+        let (a, b) = pipe();
+        let (result, pending) = tokio::join!(initiator.initiate(&*a), responder.respond(&*b));
+        assert!(matches!(result, Err(Error::Authentication(msg)) if msg.contains("not trusted")));
+        assert!(matches!(pending, Err(Error::Timeout)));
+
+        // 只信任相机时，相机拒绝手机
+        auth.trust_device(camera.device_id().clone()).unwrap();
+        let (a, b) = pipe();
+        let (result, rejected) = tokio::join!(initiator.initiate(&*a), responder.respond(&*b));
+        assert!(result.is_ok());
+        assert!(matches!(rejected, Err(Error::Authentication(_))));
+
+        auth.trust_device(phone.device_id().clone()).unwrap();
+        let (a, b) = pipe();
+        let (result, accepted) = tokio::join!(initiator.initiate(&*a), responder.respond(&*b));
+        assert_eq!(result.unwrap().peer(), camera.device_id());
+        assert_eq!(accepted.unwrap().peer(), phone.device_id());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_replies_are_rejected() {
+        let victim = identity();
+        let tamperings: [fn(&mut Reply, &DeviceIdentity); 3] = [
+            // 冒充另一台设备的公钥
+            |reply, victim| reply.proof.identity = victim.public_key().to_vec(),
+            // 中间人替换临时公钥
+            |reply, _| reply.ephemeral = ephemeral_key().unwrap().1,
+            // 签名正确但MAC不是用本次握手的密钥算的
+            |reply, _| reply.proof.mac[0] ^= 1,
+        ];
+
+        for tamper in tamperings {
+            let (a, raw_a) = pipe();
+            let (b, raw_b) = pipe();
+            let phone = Handshake::new(identity());
+            let camera = Handshake::new(identity()).with_timeout(Duration::from_millis(50));
+            let relay = async {
+                raw_b.send(raw_a.recv().await.unwrap()).await.unwrap();
+                let mut reply: Reply = bincode::deserialize(&raw_b.recv().await.unwrap()).unwrap();
+                tamper(&mut reply, &victim);
+                raw_a.send(Bytes::from(bincode::serialize(&reply).unwrap())).await.unwrap();
+            };
+            let (result, _, _) = tokio::join!(phone.initiate(&*a), camera.respond(&*b), relay);
+            assert!(matches!(result, Err(Error::Authentication(_))));
+        }
+    }
+}
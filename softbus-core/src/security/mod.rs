@@ -2,6 +2,19 @@
 
 pub mod auth;
 pub mod crypto;
+pub mod handshake;
+pub mod pairing;
+pub mod session;
+pub mod token;
+pub mod trust_store;
 
-pub use auth::AuthManager;
+#[cfg(test)]
+mod testing;
+
+pub use auth::{AuthManager, TrustLevel, TrustRecord};
 pub use crypto::CryptoManager;
+pub use handshake::{DeviceIdentity, Handshake, HandshakeOutcome};
+pub use pairing::{Pairing, PairingCode, PairingFailure, PairingPrompt};
+pub use session::{EncryptedChannel, SessionRole};
+pub use token::{Revocation, RevocationService, TokenClaims};
+pub use trust_store::{FileTrustStore, MemoryTrustStore, TrustStore};
@@ -0,0 +1,719 @@
+//! 首次配对
+//!
+//! 一台设备显示PIN或二维码，另一台设备输入或扫描。双方用配对码做口令认证的
+//! 密钥交换CPace：由配对码和本次尝试的随机数在ristretto255群上派生生成元，
+//! 双方各自发送生成元的随机倍数，只有知道配对码的一方能算出相同的共享密钥。
+//! 发送的份额是均匀分布的群元素，在任何猜测的配对码下都同样合法，窃听者无法
+//! 离线验证猜测；主动攻击者每次尝试只能验证一个猜测，尝试次数由显示配对码的
+//! 一方限制。
+//!
+//! 密钥确认通过后双方对握手记录签名，证明持有各自的身份密钥，并在`AuthManager`
+//! 中记录对方的信任记录。之后的`Handshake`即可按验证后的设备ID检查信任。
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use bytes::Bytes;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use ring::digest::{self, SHA256, SHA512};
+use ring::hkdf::{self, HKDF_SHA256};
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{self, ED25519};
+use serde::{Deserialize, Serialize};
+use crate::{Channel, DeviceId, Error, Result};
+use super::auth::{AuthManager, TrustLevel, TrustRecord};
+use super::handshake::{expand, DeviceIdentity};
+use super::session::SessionRole;
+
+/// 配对协议版本
+const PROTOCOL_VERSION: u8 = 2;
+
+/// PIN的位数
+const PIN_DIGITS: usize = 6;
+
+/// 二维码中随机配对码的字节数
+const QR_SECRET_LEN: usize = 16;
+
+/// 二维码内容的前缀
+const QR_PREFIX: &str = "softbus-pair:1:";
+
+/// 每次尝试的随机数长度
+const NONCE_LEN: usize = 16;
+
+/// Ed25519公钥和ristretto255群元素的编码长度
+const PUBLIC_KEY_LEN: usize = 32;
+
+/// 默认允许的尝试次数
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// 默认的配对超时，包括用户输入配对码的时间
+const DEFAULT_PAIRING_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 配对失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PairingFailure {
+    /// 配对码错误，还可以再试`attempts_left`次
+    WrongCode { attempts_left: u32 },
+    /// 尝试次数用尽，需要生成新的配对码
+    AttemptsExhausted,
+    /// 用户或对端取消了配对
+    Cancelled,
+    /// 对端不是二维码中的设备
+    UnexpectedPeer(DeviceId),
+}
+
+impl std::fmt::Display for PairingFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PairingFailure::WrongCode { attempts_left } => {
+                write!(f, "wrong pairing code, {} attempts left", attempts_left)
+            }
+            PairingFailure::AttemptsExhausted => write!(f, "pairing attempts exhausted"),
+            PairingFailure::Cancelled => write!(f, "pairing cancelled"),
+            PairingFailure::UnexpectedPeer(peer) => write!(f, "unexpected peer {}", peer),
+        }
+    }
+}
+
+/// 配对码：PIN或二维码中的随机串
+#[derive(Clone, PartialEq, Eq)]
+pub struct PairingCode {
+    secret: String,
+    peer: Option<DeviceId>,
+}
+
+impl PairingCode {
+    /// 生成随机的6位PIN
+    pub fn random_pin() -> Result<Self> {
+        // 拒绝采样，保证每个PIN概率相同
+        const LIMIT: u32 = u32::MAX - u32::MAX % 1_000_000;
+        loop {
+            let value = u32::from_be_bytes(random_bytes()?);
+            if value < LIMIT {
+                return Ok(Self {
+                    secret: format!("{:06}", value % 1_000_000),
+                    peer: None,
+                });
+            }
+        }
+    }
+
+    /// 生成用于二维码的随机配对码，熵远高于PIN
+    pub fn random_secret() -> Result<Self> {
+        let bytes: [u8; QR_SECRET_LEN] = random_bytes()?;
+        Ok(Self {
+            secret: bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+            peer: None,
+        })
+    }
+
+    /// 解析用户输入的PIN，忽略空白
+    pub fn from_pin(pin: &str) -> Result<Self> {
+        let secret: String = pin.chars().filter(|c| !c.is_whitespace()).collect();
+        if secret.len() != PIN_DIGITS || !secret.chars().all(|c| c.is_ascii_digit()) {
+            return Err(Error::Authentication(format!("PIN must be {} digits", PIN_DIGITS)));
+        }
+        Ok(Self { secret, peer: None })
+    }
+
+    /// 解析扫描到的二维码内容，之后的配对只接受二维码中的设备
+    pub fn from_qr_payload(payload: &str) -> Result<Self> {
+        let invalid = || Error::Authentication("Invalid pairing QR code".to_string());
+        let (device, secret) = payload
+            .strip_prefix(QR_PREFIX)
+            .and_then(|rest| rest.split_once(':'))
+            .ok_or_else(invalid)?;
+        if device.is_empty() || secret.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            secret: secret.to_string(),
+            peer: Some(DeviceId::from_string(device.to_string())),
+        })
+    }
+
+    /// 显示给用户的配对码
+    pub fn as_str(&self) -> &str {
+        &self.secret
+    }
+
+    /// 二维码内容，包含显示配对码的设备ID
+    pub fn to_qr_payload(&self, device: &DeviceId) -> String {
+        format!("{}{}:{}", QR_PREFIX, device, self.secret)
+    }
+
+    /// 二维码中的设备，输入PIN时为`None`
+    pub fn expected_peer(&self) -> Option<&DeviceId> {
+        self.peer.as_ref()
+    }
+}
+
+impl std::fmt::Debug for PairingCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PairingCode")
+            .field("peer", &self.peer)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 配对界面
+///
+/// 显示配对码的一方调用`show_code`，输入配对码的一方调用`request_code`，
+/// 配对码错误时会再次调用。
+#[async_trait]
+pub trait PairingPrompt: Send + Sync {
+    /// 显示本设备的配对码，`qr_payload`为对应的二维码内容
+    async fn show_code(&self, code: &PairingCode, qr_payload: &str);
+
+    /// 请求用户输入PIN或扫描二维码，返回`None`表示用户取消
+    ///
+    /// 第一次请求时`attempts_left`为`None`，配对码错误后为剩余的尝试次数。
+    async fn request_code(&self, attempts_left: Option<u32>) -> Option<PairingCode>;
+}
+
+/// 配对消息
+#[derive(Serialize, Deserialize)]
+enum PairingMessage {
+    /// 输入方开始一次尝试
+    Start { version: u8, nonce: Vec<u8>, identity: Vec<u8>, key: Vec<u8> },
+    /// 显示方的密钥和证明
+    Reply { identity: Vec<u8>, key: Vec<u8>, signature: Vec<u8>, mac: Vec<u8> },
+    /// 输入方的证明
+    Confirm { signature: Vec<u8>, mac: Vec<u8> },
+    /// 输入方发现密钥确认失败
+    Mismatch,
+    /// 显示方接受配对
+    Accepted,
+    /// 显示方判定本次尝试失败
+    Failed { attempts_left: u32 },
+    /// 取消配对
+    Cancel,
+}
+
+/// 一次尝试中本端的CPace状态
+struct Attempt {
+    generator: RistrettoPoint,
+    scalar: Scalar,
+}
+
+impl Attempt {
+    /// 由配对码和本次尝试的随机数派生生成元，并选取本端的随机标量
+    fn new(code: &PairingCode, nonce: &[u8]) -> Result<Self> {
+        let mut context = digest::Context::new(&SHA512);
+        for field in [&b"softbus pairing cpace ristretto255"[..], code.secret.as_bytes(), nonce] {
+            context.update(&(field.len() as u32).to_be_bytes());
+            context.update(field);
+        }
+        let mut uniform = [0u8; 64];
+        uniform.copy_from_slice(context.finish().as_ref());
+        Ok(Self {
+            generator: RistrettoPoint::from_uniform_bytes(&uniform),
+            scalar: Scalar::from_bytes_mod_order_wide(&random_bytes()?),
+        })
+    }
+
+    /// 发送给对端的份额
+    fn share(&self) -> Vec<u8> {
+        (self.scalar * self.generator).compress().to_bytes().to_vec()
+    }
+
+    /// 用对端的份额协商确认密钥
+    ///
+    /// 配对码不同时生成元不同，双方得到不同的确认密钥。对端份额不是合法的群
+    /// 元素或共享点为单位元时失败，诚实的对端不会发送这样的份额。
+    fn confirm_key(self, peer_share: &[u8], transcript: &digest::Digest) -> Result<hmac::Key> {
+        let malformed = || Error::Authentication("Invalid pairing key share".to_string());
+        let peer = CompressedRistretto::from_slice(peer_share)
+            .ok()
+            .and_then(|point| point.decompress())
+            .ok_or_else(malformed)?;
+        let shared = self.scalar * peer;
+        if shared.is_identity() {
+            return Err(malformed());
+        }
+
+        let prk = hkdf::Salt::new(HKDF_SHA256, transcript.as_ref()).extract(shared.compress().as_bytes());
+        Ok(hmac::Key::new(hmac::HMAC_SHA256, &expand(&prk, b"softbus pairing confirm")?))
+    }
+}
+
+/// 设备配对
+///
+/// 显示配对码的一方调用`respond`，输入配对码的一方调用`initiate`。成功后双方
+/// 的`AuthManager`都记录了对方的信任记录。
+#[derive(Clone)]
+pub struct Pairing {
+    identity: Arc<DeviceIdentity>,
+    auth: Arc<AuthManager>,
+    prompt: Arc<dyn PairingPrompt>,
+    max_attempts: u32,
+    timeout: Duration,
+}
+
+impl Pairing {
+    /// 用本设备的身份、信任记录和配对界面创建配对
+    pub fn new(identity: Arc<DeviceIdentity>, auth: Arc<AuthManager>, prompt: Arc<dyn PairingPrompt>) -> Self {
+        Self {
+            identity,
+            auth,
+            prompt,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            timeout: DEFAULT_PAIRING_TIMEOUT,
+        }
+    }
+
+    /// 设置显示方允许的尝试次数，用尽后配对码作废
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// 设置配对超时
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 输入对端显示的配对码并配对，配对码错误时重新请求输入
+    pub async fn initiate(&self, channel: &dyn Channel) -> Result<TrustRecord> {
+        self.bounded(async {
+            let mut attempts_left = None;
+            loop {
+                let Some(code) = self.prompt.request_code(attempts_left).await else {
+                    send(channel, &PairingMessage::Cancel).await?;
+                    return Err(Error::Pairing(PairingFailure::Cancelled));
+                };
+                match self.initiate_once(channel, &code).await {
+                    Err(Error::Pairing(PairingFailure::WrongCode { attempts_left: left })) => {
+                        attempts_left = Some(left);
+                    }
+                    result => return result,
+                }
+            }
+        })
+        .await
+    }
+
+    /// 显示配对码并等待对端配对
+    pub async fn respond(&self, channel: &dyn Channel, code: PairingCode) -> Result<TrustRecord> {
+        self.prompt
+            .show_code(&code, &code.to_qr_payload(self.identity.device_id()))
+            .await;
+        self.bounded(async {
+            let mut attempts_left = self.max_attempts;
+            loop {
+                if let Some(record) = self.respond_once(channel, &code).await? {
+                    return Ok(record);
+                }
+                attempts_left -= 1;
+                send(channel, &PairingMessage::Failed { attempts_left }).await?;
+                if attempts_left == 0 {
+                    return Err(Error::Pairing(PairingFailure::AttemptsExhausted));
+                }
+            }
+        })
+        .await
+    }
+
+    async fn initiate_once(&self, channel: &dyn Channel, code: &PairingCode) -> Result<TrustRecord> {
+        let nonce: [u8; NONCE_LEN] = random_bytes()?;
+        let attempt = Attempt::new(code, &nonce)?;
+        let key = attempt.share();
+        send(channel, &PairingMessage::Start {
+            version: PROTOCOL_VERSION,
+            nonce: nonce.to_vec(),
+            identity: self.identity.public_key().to_vec(),
+            key: key.clone(),
+        })
+        .await?;
+
+        let (peer_identity, peer_key, signature, mac) = match recv(channel).await? {
+            PairingMessage::Reply { identity, key, signature, mac } => (identity, key, signature, mac),
+            other => return Err(verdict(other)),
+        };
+        let transcript = transcript(&nonce, self.identity.public_key(), &key, &peer_identity, &peer_key)?;
+        let confirm = attempt
+            .confirm_key(&peer_key, &transcript)
+            .ok()
+            .filter(|confirm| verify_mac(confirm, SessionRole::Responder, &transcript, &mac));
+        let Some(confirm) = confirm else {
+            send(channel, &PairingMessage::Mismatch).await?;
+            return Err(verdict(recv(channel).await?));
+        };
+
+        let peer = DeviceId::from_public_key(&peer_identity);
+        let checked = verify_signature(&peer_identity, SessionRole::Responder, &transcript, &signature)
+            .and_then(|()| match code.expected_peer() {
+                Some(expected) if *expected != peer => Err(Error::Pairing(PairingFailure::UnexpectedPeer(peer.clone()))),
+                _ => Ok(()),
+            });
+        if let Err(e) = checked {
+            send(channel, &PairingMessage::Cancel).await?;
+            return Err(e);
+        }
+
+        let (signature, mac) = prove(&confirm, &self.identity, SessionRole::Initiator, &transcript);
+        send(channel, &PairingMessage::Confirm { signature, mac }).await?;
+        match recv(channel).await? {
+            PairingMessage::Accepted => self.record(peer, peer_identity),
+            other => Err(verdict(other)),
+        }
+    }
+
+    /// 处理一次尝试，配对码错误时返回`None`
+    async fn respond_once(&self, channel: &dyn Channel, code: &PairingCode) -> Result<Option<TrustRecord>> {
+        let (nonce, peer_identity, peer_key) = match recv(channel).await? {
+            PairingMessage::Start { version, nonce, identity, key } if version == PROTOCOL_VERSION => {
+                (nonce, identity, key)
+            }
+            PairingMessage::Start { version, .. } => {
+                return Err(Error::Authentication(format!("Unsupported pairing version {}", version)));
+            }
+            other => return Err(verdict(other)),
+        };
+        let attempt = Attempt::new(code, &nonce)?;
+        let key = attempt.share();
+        let transcript = transcript(&nonce, &peer_identity, &peer_key, self.identity.public_key(), &key)?;
+        let Ok(confirm) = attempt.confirm_key(&peer_key, &transcript) else {
+            return Ok(None);
+        };
+
+        let (signature, mac) = prove(&confirm, &self.identity, SessionRole::Responder, &transcript);
+        send(channel, &PairingMessage::Reply {
+            identity: self.identity.public_key().to_vec(),
+            key,
+            signature,
+            mac,
+        })
+        .await?;
+
+        let (signature, mac) = match recv(channel).await? {
+            PairingMessage::Confirm { signature, mac } => (signature, mac),
+            PairingMessage::Mismatch => return Ok(None),
+            other => return Err(verdict(other)),
+        };
+        if !verify_mac(&confirm, SessionRole::Initiator, &transcript, &mac) {
+            return Ok(None);
+        }
+        if let Err(e) = verify_signature(&peer_identity, SessionRole::Initiator, &transcript, &signature) {
+            send(channel, &PairingMessage::Cancel).await?;
+            return Err(e);
+        }
+        send(channel, &PairingMessage::Accepted).await?;
+        self.record(DeviceId::from_public_key(&peer_identity), peer_identity).map(Some)
+    }
+
+    fn record(&self, device_id: DeviceId, public_key: Vec<u8>) -> Result<TrustRecord> {
+        let now = chrono::Utc::now().timestamp();
+        let record = TrustRecord {
+            device_id,
+            public_key: Some(public_key),
+            trust_level: TrustLevel::Trusted,
+            paired_at: now,
+            last_seen: Some(now),
+        };
+        self.auth.add_trust_record(record.clone())?;
+        tracing::info!("Paired with device {}", record.device_id);
+        Ok(record)
+    }
+
+    async fn bounded<F, T>(&self, pairing: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        tokio::time::timeout(self.timeout, pairing)
+            .await
+            .map_err(|_| Error::Timeout)?
+    }
+}
+
+/// 把对端的失败、取消或意外消息转换为错误
+fn verdict(message: PairingMessage) -> Error {
+    match message {
+        PairingMessage::Failed { attempts_left: 0 } => Error::Pairing(PairingFailure::AttemptsExhausted),
+        PairingMessage::Failed { attempts_left } => Error::Pairing(PairingFailure::WrongCode { attempts_left }),
+        PairingMessage::Cancel => Error::Pairing(PairingFailure::Cancelled),
+        _ => Error::Authentication("Unexpected pairing message".to_string()),
+    }
+}
+
+/// 一次尝试的握手记录摘要，签名和MAC都覆盖它
+fn transcript(
+    nonce: &[u8],
+    initiator_identity: &[u8],
+    initiator_key: &[u8],
+    responder_identity: &[u8],
+    responder_key: &[u8],
+) -> Result<digest::Digest> {
+    let keys = [initiator_identity, initiator_key, responder_identity, responder_key];
+    if nonce.len() != NONCE_LEN || keys.iter().any(|key| key.len() != PUBLIC_KEY_LEN) {
+        return Err(Error::Authentication("Malformed pairing message".to_string()));
+    }
+    let mut context = digest::Context::new(&SHA256);
+    context.update(b"softbus pairing v2");
+    context.update(nonce);
+    for key in keys {
+        context.update(key);
+    }
+    Ok(context.finish())
+}
+
+/// 签名和MAC的标签，区分双方的证明
+fn proof_label(role: SessionRole) -> &'static [u8] {
+    match role {
+        SessionRole::Initiator => b"softbus pairing initiator",
+        SessionRole::Responder => b"softbus pairing responder",
+    }
+}
+
+/// 以`role`的身份对握手记录签名并计算MAC
+fn prove(
+    confirm: &hmac::Key,
+    identity: &DeviceIdentity,
+    role: SessionRole,
+    transcript: &digest::Digest,
+) -> (Vec<u8>, Vec<u8>) {
+    let message = [proof_label(role), transcript.as_ref()].concat();
+    let mac = hmac::sign(confirm, &message).as_ref().to_vec();
+    (identity.sign(&message), mac)
+}
+
+fn verify_mac(confirm: &hmac::Key, role: SessionRole, transcript: &digest::Digest, mac: &[u8]) -> bool {
+    hmac::verify(confirm, &[proof_label(role), transcript.as_ref()].concat(), mac).is_ok()
+}
+
+fn verify_signature(
+    public_key: &[u8],
+    role: SessionRole,
+    transcript: &digest::Digest,
+    signature: &[u8],
+) -> Result<()> {
+    signature::UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(&[proof_label(role), transcript.as_ref()].concat(), signature)
+        .map_err(|_| Error::Authentication("Invalid pairing signature".to_string()))
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| Error::Encryption("Failed to generate random bytes".to_string()))?;
+    Ok(bytes)
+}
+
+async fn send(channel: &dyn Channel, message: &PairingMessage) -> Result<()> {
+    let data = bincode::serialize(message).map_err(|e| Error::Serialization(e.to_string()))?;
+    channel.send(Bytes::from(data)).await
+}
+
+async fn recv(channel: &dyn Channel) -> Result<PairingMessage> {
+    let data = channel.recv().await?;
+    bincode::deserialize(&data)
+        .map_err(|e| Error::Authentication(format!("Malformed pairing message: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use crate::security::testing::pipe;
+    use crate::security::Handshake;
+
+    /// 按脚本输入配对码，并记录界面收到的调用
+    #[derive(Default)]
+    struct ScriptedPrompt {
+        codes: Mutex<Vec<Option<PairingCode>>>,
+        shown: Mutex<Vec<String>>,
+        requests: Mutex<Vec<Option<u32>>>,
+    }
+
+    impl ScriptedPrompt {
+        fn entering(codes: Vec<Option<PairingCode>>) -> Arc<Self> {
+            Arc::new(Self {
+                codes: Mutex::new(codes.into_iter().rev().collect()),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[async_trait]
+    impl PairingPrompt for ScriptedPrompt {
+        async fn show_code(&self, code: &PairingCode, qr_payload: &str) {
+            assert!(qr_payload.ends_with(code.as_str()));
+            self.shown.lock().push(qr_payload.to_string());
+        }
+
+        async fn request_code(&self, attempts_left: Option<u32>) -> Option<PairingCode> {
+            self.requests.lock().push(attempts_left);
+            self.codes.lock().pop().flatten()
+        }
+    }
+
+    struct Device {
+        identity: Arc<DeviceIdentity>,
+        auth: Arc<AuthManager>,
+    }
+
+    impl Device {
+        fn new() -> Self {
+            Self {
+                identity: Arc::new(DeviceIdentity::generate().unwrap()),
+                auth: Arc::new(AuthManager::new()),
+            }
+        }
+
+        fn pairing(&self, prompt: Arc<ScriptedPrompt>) -> Pairing {
+            Pairing::new(Arc::clone(&self.identity), Arc::clone(&self.auth), prompt)
+                .with_timeout(Duration::from_secs(5))
+        }
+    }
+
+    /// 在内存通道上同时运行两端的配对
+    async fn pair(initiator: Pairing, responder: Pairing, code: PairingCode) -> (Result<TrustRecord>, Result<TrustRecord>) {
+        let (a, b) = pipe();
+        tokio::join!(initiator.initiate(&*a), responder.respond(&*b, code))
+    }
+
+    fn pin(digits: &str) -> Option<PairingCode> {
+        Some(PairingCode::from_pin(digits).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_pin_pairing_records_mutual_trust() {
+        let (phone, pc) = (Device::new(), Device::new());
+        let code = PairingCode::random_pin().unwrap();
+        let (display, keypad) = (ScriptedPrompt::entering(vec![]), ScriptedPrompt::entering(vec![Some(code.clone())]));
+        let (phone_record, pc_record) =
+            pair(phone.pairing(Arc::clone(&keypad)), pc.pairing(Arc::clone(&display)), code).await;
+        let (phone_record, pc_record) = (phone_record.unwrap(), pc_record.unwrap());
+        assert_eq!(phone_record.device_id, *pc.identity.device_id());
+        assert_eq!(phone_record.public_key.as_deref(), Some(pc.identity.public_key()));
+        assert_eq!(pc_record.device_id, *phone.identity.device_id());
+        assert_eq!(phone.auth.trust_record(pc.identity.device_id()), Some(phone_record));
+        assert!(pc.auth.is_trusted(phone.identity.device_id()));
+        assert_eq!(display.shown.lock().len(), 1);
+        assert_eq!(*keypad.requests.lock(), vec![None]);
+
+        // 配对后握手按验证的身份通过信任检查
+        let (a, b) = pipe();
+        let initiator = Handshake::new(Arc::clone(&phone.identity)).with_auth_manager(Arc::clone(&phone.auth));
+        let responder = Handshake::new(Arc::clone(&pc.identity)).with_auth_manager(Arc::clone(&pc.auth));
+        let (initiated, responded) = tokio::join!(initiator.initiate(&*a), responder.respond(&*b));
+        assert_eq!(initiated.unwrap().session_key(), responded.unwrap().session_key());
+    }
+
+    #[tokio::test]
+    async fn test_wrong_pin_can_be_retried() {
+        let (phone, pc) = (Device::new(), Device::new());
+        let keypad = ScriptedPrompt::entering(vec![pin("000000"), pin("111111"), pin("123456")]);
+        let (phone_record, pc_record) = pair(
+            phone.pairing(Arc::clone(&keypad)),
+            pc.pairing(ScriptedPrompt::entering(vec![])),
+            PairingCode::from_pin("123 456").unwrap(),
+        )
+        .await;
+        assert_eq!(phone_record.unwrap().device_id, *pc.identity.device_id());
+        assert_eq!(pc_record.unwrap().device_id, *phone.identity.device_id());
+        assert_eq!(*keypad.requests.lock(), vec![None, Some(2), Some(1)]);
+    }
+
+    #[tokio::test]
+    async fn test_attempts_are_limited() {
+        let (phone, pc) = (Device::new(), Device::new());
+        let keypad = ScriptedPrompt::entering(vec![pin("000000"), pin("111111"), pin("123456")]);
+        let (initiated, responded) = pair(
+            phone.pairing(Arc::clone(&keypad)),
+            pc.pairing(ScriptedPrompt::entering(vec![])).with_max_attempts(2),
+            PairingCode::from_pin("123456").unwrap(),
+        )
+        .await;
+        assert!(matches!(initiated, Err(Error::Pairing(PairingFailure::AttemptsExhausted))));
+        assert!(matches!(responded, Err(Error::Pairing(PairingFailure::AttemptsExhausted))));
+        assert_eq!(*keypad.requests.lock(), vec![None, Some(1)]);
+        assert!(!phone.auth.is_trusted(pc.identity.device_id()));
+        assert!(!pc.auth.is_trusted(phone.identity.device_id()));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_pairing() {
+        let (phone, pc) = (Device::new(), Device::new());
+        let (initiated, responded) = pair(
+            phone.pairing(ScriptedPrompt::entering(vec![None])),
+            pc.pairing(ScriptedPrompt::entering(vec![])),
+            PairingCode::random_pin().unwrap(),
+        )
+        .await;
+        assert!(matches!(initiated, Err(Error::Pairing(PairingFailure::Cancelled))));
+        assert!(matches!(responded, Err(Error::Pairing(PairingFailure::Cancelled))));
+    }
+
+    #[tokio::test]
+    async fn test_qr_code_pins_the_peer() {
+        let (phone, pc, other) = (Device::new(), Device::new(), Device::new());
+        let code = PairingCode::random_secret().unwrap();
+        assert_eq!(code.as_str().len(), QR_SECRET_LEN * 2);
+
+        let scanned = PairingCode::from_qr_payload(&code.to_qr_payload(pc.identity.device_id())).unwrap();
+        assert_eq!(scanned.expected_peer(), Some(pc.identity.device_id()));
+        let (initiated, _) = pair(
+            phone.pairing(ScriptedPrompt::entering(vec![Some(scanned)])),
+            pc.pairing(ScriptedPrompt::entering(vec![])),
+            code.clone(),
+        )
+        .await;
+        assert!(initiated.is_ok());
+
+        // 知道配对码但不是二维码中的设备
+        let scanned = PairingCode::from_qr_payload(&code.to_qr_payload(pc.identity.device_id())).unwrap();
+        let (initiated, responded) = pair(
+            phone.pairing(ScriptedPrompt::entering(vec![Some(scanned)])),
+            other.pairing(ScriptedPrompt::entering(vec![])),
+            code,
+        )
+        .await;
+        assert!(matches!(
+            initiated,
+            Err(Error::Pairing(PairingFailure::UnexpectedPeer(peer))) if peer == *other.identity.device_id()
+        ));
+        assert!(matches!(responded, Err(Error::Pairing(PairingFailure::Cancelled))));
+    }
+
+    #[test]
+    fn test_shares_do_not_reveal_the_code() {
+        // 窃听者能对份额做的检查只有能否解码为群元素。份额在任何猜测的配对码下
+        // 都能解码并得出确认密钥，错误的猜测与正确的配对码无法区分
+        let nonce = [7u8; NONCE_LEN];
+        let code = PairingCode::from_pin("123456").unwrap();
+        let transcript = digest::digest(&SHA256, b"transcript");
+        for _ in 0..16 {
+            let share = Attempt::new(&code, &nonce).unwrap().share();
+            assert!(CompressedRistretto::from_slice(&share).unwrap().decompress().is_some());
+            for guess in 0..4 {
+                let guess = PairingCode::from_pin(&format!("{:06}", guess)).unwrap();
+                assert!(Attempt::new(&guess, &nonce).unwrap().confirm_key(&share, &transcript).is_ok());
+            }
+        }
+
+        // 非法编码和单位元不能作为份额
+        let attempt = || Attempt::new(&code, &nonce).unwrap();
+        assert!(attempt().confirm_key(&[0xff; PUBLIC_KEY_LEN], &transcript).is_err());
+        assert!(attempt().confirm_key(&[0; PUBLIC_KEY_LEN], &transcript).is_err());
+    }
+
+    #[test]
+    fn test_pairing_codes() {
+        let pin = PairingCode::random_pin().unwrap();
+        assert_eq!(pin.as_str().len(), PIN_DIGITS);
+        assert!(pin.as_str().chars().all(|c| c.is_ascii_digit()));
+        assert!(!format!("{:?}", pin).contains(pin.as_str()));
+
+        assert!(PairingCode::from_pin("12345").is_err());
+        assert!(PairingCode::from_pin("12a456").is_err());
+        assert!(PairingCode::from_qr_payload("softbus-pair:1:").is_err());
+        assert!(PairingCode::from_qr_payload("https://example.com").is_err());
+    }
+}
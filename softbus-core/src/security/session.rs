@@ -0,0 +1,385 @@
+//! 会话加密通道
+//!
+//! `EncryptedChannel`包裹任意`Channel`，用AES-256-GCM加密每条消息。双方由同一个
+//! 会话密钥按方向派生收发密钥，发起方的发送密钥即响应方的接收密钥。
+//!
+//! 每条消息的帧头为12字节：4字节密钥轮次和8字节计数器，既是nonce也作为附加
+//! 认证数据。计数器在同一轮次内严格递增，接收方拒绝重放和乱序的消息。发送的
+//! 消息数或字节数达到阈值后，发送方把密钥单向推进到下一轮次，接收方看到新轮次
+//! 时同步推进。
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use async_trait::async_trait;
+use bytes::Bytes;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::hkdf::{self, HKDF_SHA256};
+use tokio::sync::Mutex;
+use crate::channel::ChannelOptions;
+use crate::{Channel, DeviceId, Error, QosLevel, Result};
+
+/// 帧头长度：轮次和计数器，同时作为nonce
+const HEADER_LEN: usize = NONCE_LEN;
+
+/// 认证标签长度
+const TAG_LEN: usize = 16;
+
+/// 接收方一次最多跳过的轮次，超出时视为伪造的帧
+const MAX_EPOCH_SKIP: u32 = 16;
+
+/// 默认每个密钥加密的最大消息数
+const DEFAULT_REKEY_MESSAGES: u64 = 1 << 20;
+
+/// 默认每个密钥加密的最大字节数
+const DEFAULT_REKEY_BYTES: u64 = 1 << 30;
+
+/// 本端在会话中的角色，决定使用哪个方向的密钥
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionRole {
+    /// 发起连接的一端
+    Initiator,
+    /// 接受连接的一端
+    Responder,
+}
+
+impl SessionRole {
+    /// 本端发送方向的派生标签
+    fn send_label(self) -> &'static [u8] {
+        match self {
+            SessionRole::Initiator => b"softbus session initiator",
+            SessionRole::Responder => b"softbus session responder",
+        }
+    }
+
+    /// 本端接收方向的派生标签，即对端的发送标签
+    fn recv_label(self) -> &'static [u8] {
+        match self {
+            SessionRole::Initiator => SessionRole::Responder.send_label(),
+            SessionRole::Responder => SessionRole::Initiator.send_label(),
+        }
+    }
+}
+
+/// 一个方向的密钥状态
+struct Direction {
+    key: [u8; 32],
+    cipher: LessSafeKey,
+    epoch: u32,
+    /// 发送方向为下一个计数器，接收方向为已接受的最大计数器加一
+    counter: u64,
+    bytes: u64,
+}
+
+impl Direction {
+    fn new(key: [u8; 32], epoch: u32) -> Result<Self> {
+        Ok(Self {
+            cipher: cipher(&key)?,
+            key,
+            epoch,
+            counter: 0,
+            bytes: 0,
+        })
+    }
+
+    /// 推进到下一轮次的密钥
+    fn next(&self) -> Result<Self> {
+        let epoch = self
+            .epoch
+            .checked_add(1)
+            .ok_or_else(|| Error::Encryption("session key exhausted".to_string()))?;
+        Direction::new(derive(&self.key, b"softbus session rekey")?, epoch)
+    }
+}
+
+/// 加密通道
+///
+/// 实现`Channel`，收发的数据在内层通道上都是密文。并发发送按加密顺序写入内层
+/// 通道，保证计数器在线路上递增。无法解密、重放或乱序的帧被丢弃并记录日志，
+/// 不会中断接收。
+pub struct EncryptedChannel {
+    inner: Arc<dyn Channel>,
+    send: Mutex<Direction>,
+    recv: Mutex<Direction>,
+    rekey_messages: u64,
+    rekey_bytes: u64,
+    rejected: AtomicU64,
+    peer: Option<DeviceId>,
+}
+
+impl EncryptedChannel {
+    /// 用双方共享的会话密钥包裹通道
+    pub fn new(inner: Arc<dyn Channel>, session_key: &[u8; 32], role: SessionRole) -> Result<Self> {
+        Ok(Self {
+            inner,
+            send: Mutex::new(Direction::new(derive(session_key, role.send_label())?, 0)?),
+            recv: Mutex::new(Direction::new(derive(session_key, role.recv_label())?, 0)?),
+            rekey_messages: DEFAULT_REKEY_MESSAGES,
+            rekey_bytes: DEFAULT_REKEY_BYTES,
+            rejected: AtomicU64::new(0),
+            peer: None,
+        })
+    }
+
+    /// 记录握手验证过的对端设备
+    pub(crate) fn with_verified_peer(mut self, peer: DeviceId) -> Self {
+        self.peer = Some(peer);
+        self
+    }
+
+    /// 设置换密钥的阈值：一个密钥最多加密的消息数和明文字节数
+    pub fn with_rekey_threshold(mut self, messages: u64, bytes: u64) -> Self {
+        self.rekey_messages = messages.max(1);
+        self.rekey_bytes = bytes.max(1);
+        self
+    }
+
+    /// 被丢弃的帧数量
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    /// 当前发送密钥的轮次
+    pub async fn send_epoch(&self) -> u32 {
+        self.send.lock().await.epoch
+    }
+
+    /// 解密一帧，轮次前进时只有解密成功才推进接收密钥
+    fn open(&self, state: &mut Direction, frame: &[u8]) -> Result<Vec<u8>> {
+        if frame.len() < HEADER_LEN + TAG_LEN {
+            return Err(Error::Encryption("frame too short".to_string()));
+        }
+        let (header, ciphertext) = frame.split_at(HEADER_LEN);
+        let epoch = u32::from_be_bytes(header[..4].try_into().unwrap());
+        let counter = u64::from_be_bytes(header[4..].try_into().unwrap());
+
+        let mut advanced = None;
+        if epoch != state.epoch {
+            if epoch < state.epoch || epoch - state.epoch > MAX_EPOCH_SKIP {
+                return Err(Error::Encryption(format!("unexpected key epoch {}", epoch)));
+            }
+            let mut next = state.next()?;
+            while next.epoch < epoch {
+                next = next.next()?;
+            }
+            advanced = Some(next);
+        } else if counter < state.counter {
+            return Err(Error::Encryption(format!("replayed or reordered frame {}", counter)));
+        }
+
+        let key = advanced.as_ref().unwrap_or(state);
+        let mut in_out = ciphertext.to_vec();
+        let nonce = Nonce::try_assume_unique_for_key(header)
+            .map_err(|_| Error::Encryption("invalid nonce".to_string()))?;
+        let len = key
+            .cipher
+            .open_in_place(nonce, Aad::from(header), &mut in_out)
+            .map_err(|_| Error::Encryption("Decryption failed".to_string()))?
+            .len();
+        in_out.truncate(len);
+
+        if let Some(next) = advanced {
+            *state = next;
+        }
+        state.counter = counter + 1;
+        Ok(in_out)
+    }
+}
+
+#[async_trait]
+impl Channel for EncryptedChannel {
+    async fn send(&self, data: Bytes) -> Result<()> {
+        // 持锁直到写入内层通道，保证帧按计数器顺序发出
+        let mut state = self.send.lock().await;
+        if state.counter >= self.rekey_messages || state.bytes >= self.rekey_bytes {
+            *state = state.next()?;
+            tracing::debug!("Session send key advanced to epoch {}", state.epoch);
+        }
+
+        let mut frame = Vec::with_capacity(HEADER_LEN + data.len() + TAG_LEN);
+        frame.extend_from_slice(&state.epoch.to_be_bytes());
+        frame.extend_from_slice(&state.counter.to_be_bytes());
+        let header: [u8; HEADER_LEN] = frame[..HEADER_LEN].try_into().unwrap();
+        let mut in_out = data.to_vec();
+        state
+            .cipher
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(header), Aad::from(header), &mut in_out)
+            .map_err(|_| Error::Encryption("Encryption failed".to_string()))?;
+        frame.extend_from_slice(&in_out);
+
+        state.counter += 1;
+        state.bytes += data.len() as u64;
+        self.inner.send(Bytes::from(frame)).await
+    }
+
+    async fn recv(&self) -> Result<Bytes> {
+        let mut state = self.recv.lock().await;
+        loop {
+            let frame = self.inner.recv().await?;
+            match self.open(&mut state, &frame) {
+                Ok(plaintext) => return Ok(Bytes::from(plaintext)),
+                Err(e) => {
+                    self.rejected.fetch_add(1, Ordering::Relaxed);
+                    tracing::warn!("Dropping session frame: {}", e);
+                }
+            }
+        }
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    fn is_connected(&self) -> bool {
+        self.inner.is_connected()
+    }
+
+    fn qos_level(&self) -> QosLevel {
+        self.inner.qos_level()
+    }
+
+    fn peer_device_id(&self) -> Option<String> {
+        match &self.peer {
+            Some(peer) => Some(peer.to_string()),
+            None => self.inner.peer_device_id(),
+        }
+    }
+
+    fn verified_peer(&self) -> Option<DeviceId> {
+        self.peer.clone().or_else(|| self.inner.verified_peer())
+    }
+
+    async fn set_options(&self, options: ChannelOptions) -> Result<()> {
+        self.inner.set_options(options).await
+    }
+}
+
+/// HKDF输出长度
+pub(super) struct KeyLen(pub(super) usize);
+
+impl hkdf::KeyType for KeyLen {
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+/// 用HKDF-SHA256从`secret`派生32字节密钥
+fn derive(secret: &[u8], label: &[u8]) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    hkdf::Salt::new(HKDF_SHA256, &[])
+        .extract(secret)
+        .expand(&[label], KeyLen(key.len()))
+        .and_then(|okm| okm.fill(&mut key))
+        .map_err(|_| Error::Encryption("Key derivation failed".to_string()))?;
+    Ok(key)
+}
+
+fn cipher(key: &[u8; 32]) -> Result<LessSafeKey> {
+    UnboundKey::new(&AES_256_GCM, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| Error::Encryption("Invalid key".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::testing::pipe;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    #[tokio::test]
+    async fn test_roundtrip_in_both_directions() {
+        let (a, b) = pipe();
+        let initiator = EncryptedChannel::new(a, &KEY, SessionRole::Initiator).unwrap();
+        let responder = EncryptedChannel::new(b, &KEY, SessionRole::Responder).unwrap();
+
+        initiator.send(Bytes::from_static(b"open camera")).await.unwrap();
+        assert_eq!(responder.recv().await.unwrap(), "open camera");
+        responder.send(Bytes::from_static(b"ok")).await.unwrap();
+        assert_eq!(initiator.recv().await.unwrap(), "ok");
+    }
+
+    #[tokio::test]
+    async fn test_frames_are_encrypted_and_unique() {
+        let (a, raw) = pipe();
+        let channel = EncryptedChannel::new(a, &KEY, SessionRole::Initiator).unwrap();
+
+        channel.send(Bytes::from_static(b"same")).await.unwrap();
+        channel.send(Bytes::from_static(b"same")).await.unwrap();
+        let first = raw.recv().await.unwrap();
+        let second = raw.recv().await.unwrap();
+        assert_eq!(first.len(), HEADER_LEN + 4 + TAG_LEN);
+        assert!(!first.windows(4).any(|w| w == b"same"));
+        assert_ne!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_replayed_and_reordered_frames_are_rejected() {
+        let (a, raw) = pipe();
+        let (b, tap) = pipe();
+        let sender = EncryptedChannel::new(a, &KEY, SessionRole::Initiator).unwrap();
+        let receiver = EncryptedChannel::new(b, &KEY, SessionRole::Responder).unwrap();
+
+        for message in [&b"one"[..], b"two", b"three"] {
+            sender.send(Bytes::from_static(message)).await.unwrap();
+        }
+        let frames = [raw.recv().await.unwrap(), raw.recv().await.unwrap(), raw.recv().await.unwrap()];
+
+        // 第二帧先到，随后重放它并送来更早的第一帧，最后是第三帧
+        for index in [1, 1, 0, 2] {
+            tap.send(frames[index].clone()).await.unwrap();
+        }
+        // 篡改过的帧同样被丢弃
+        let mut forged = frames[2].to_vec();
+        forged[HEADER_LEN - 1] = 10;
+        tap.send(Bytes::from(forged)).await.unwrap();
+        tap.send(Bytes::from_static(b"short")).await.unwrap();
+
+        assert_eq!(receiver.recv().await.unwrap(), "two");
+        assert_eq!(receiver.recv().await.unwrap(), "three");
+        sender.send(Bytes::from_static(b"four")).await.unwrap();
+        tap.send(raw.recv().await.unwrap()).await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), "four");
+        assert_eq!(receiver.rejected(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_rekey_after_threshold() {
+        let (a, b) = pipe();
+        let sender = EncryptedChannel::new(a, &KEY, SessionRole::Initiator)
+            .unwrap()
+            .with_rekey_threshold(3, 1 << 20);
+        let receiver = EncryptedChannel::new(b, &KEY, SessionRole::Responder).unwrap();
+
+        for i in 0..10u8 {
+            sender.send(Bytes::from(vec![i])).await.unwrap();
+            assert_eq!(receiver.recv().await.unwrap(), vec![i]);
+        }
+        assert_eq!(sender.send_epoch().await, 3);
+
+        // 按字节数换密钥
+        let sender = sender.with_rekey_threshold(u64::MAX, 8);
+        let before = sender.send_epoch().await;
+        sender.send(Bytes::from(vec![0u8; 8])).await.unwrap();
+        sender.send(Bytes::from(vec![1u8; 8])).await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), vec![0u8; 8]);
+        assert_eq!(receiver.recv().await.unwrap(), vec![1u8; 8]);
+        assert_eq!(sender.send_epoch().await, before + 1);
+        assert_eq!(receiver.rejected(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_keys_cannot_talk() {
+        let (a, b) = pipe();
+        let sender = EncryptedChannel::new(a, &KEY, SessionRole::Initiator).unwrap();
+        let receiver = Arc::new(EncryptedChannel::new(b, &[8; 32], SessionRole::Responder).unwrap());
+
+        sender.send(Bytes::from_static(b"secret")).await.unwrap();
+        let reader = Arc::clone(&receiver);
+        let result = tokio::time::timeout(std::time::Duration::from_millis(50), async move {
+            reader.recv().await
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(receiver.rejected(), 1);
+    }
+}
@@ -0,0 +1,61 @@
+//! 安全模块测试用的内存通道
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use bytes::Bytes;
+use tokio::sync::{mpsc, Mutex};
+use crate::channel::ChannelOptions;
+use crate::{Channel, Error, QosLevel, Result};
+
+/// 基于内存队列的通道，`raw`一侧可以直接读写线路上的帧
+pub(crate) struct Pipe {
+    tx: mpsc::UnboundedSender<Bytes>,
+    rx: Mutex<mpsc::UnboundedReceiver<Bytes>>,
+}
+
+#[async_trait]
+impl Channel for Pipe {
+    async fn send(&self, data: Bytes) -> Result<()> {
+        self.tx
+            .send(data)
+            .map_err(|_| Error::Connection("Channel closed".to_string()))
+    }
+
+    async fn recv(&self) -> Result<Bytes> {
+        self.rx
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or_else(|| Error::Connection("Channel closed".to_string()))
+    }
+
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        !self.tx.is_closed()
+    }
+
+    fn qos_level(&self) -> QosLevel {
+        QosLevel::Balanced
+    }
+
+    fn peer_device_id(&self) -> Option<String> {
+        None
+    }
+
+    async fn set_options(&self, _options: ChannelOptions) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub(crate) fn pipe() -> (Arc<Pipe>, Arc<Pipe>) {
+    let (tx_a, rx_a) = mpsc::unbounded_channel();
+    let (tx_b, rx_b) = mpsc::unbounded_channel();
+    (
+        Arc::new(Pipe { tx: tx_a, rx: Mutex::new(rx_b) }),
+        Arc::new(Pipe { tx: tx_b, rx: Mutex::new(rx_a) }),
+    )
+}
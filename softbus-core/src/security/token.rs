@@ -0,0 +1,306 @@
+//! 签名令牌和吊销列表
+//!
+//! 令牌自带设备ID、权限范围、签发时间和过期时间，由签发设备的身份密钥签名。
+//! 任何知道签发设备公钥的节点都可以离线验证，无需向签发者查询。令牌格式为
+//! `sbt1.<声明JSON的十六进制>.<签名的十六进制>`。
+//!
+//! 吊销记录同样由签发者签名，通过`RevocationService`在节点间传播：对端连接后
+//! 拉取完整列表，之后接收新吊销的推送。
+
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use bytes::Bytes;
+use ring::signature::{self, ED25519};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use crate::rpc::server::{MethodHandler, RpcService};
+use crate::rpc::{CallContext, RpcClient, RpcServer};
+use crate::{DeviceId, Error, Result};
+use super::auth::AuthManager;
+use super::handshake::DeviceIdentity;
+
+/// 令牌前缀，同时标识格式版本
+pub const TOKEN_PREFIX: &str = "sbt1";
+
+/// 吊销列表服务名
+pub const REVOCATION_SERVICE: &str = "softbus.Revocation";
+
+/// 拉取完整吊销列表的方法
+const LIST_METHOD: &str = "list";
+
+/// 推送新吊销的事件
+const REVOKED_EVENT: &str = "revoked";
+
+/// 令牌声明
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenClaims {
+    /// 令牌ID，用于吊销
+    pub token_id: String,
+    /// 签发设备
+    pub issuer: DeviceId,
+    /// 持有令牌的设备
+    pub device_id: DeviceId,
+    /// 权限范围
+    pub scopes: Vec<String>,
+    /// 签发时间（Unix秒）
+    pub issued_at: i64,
+    /// 过期时间（Unix秒）
+    pub expires_at: i64,
+}
+
+impl TokenClaims {
+    /// 是否包含权限范围`scope`
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    /// 是否会在`margin`内过期，持有者据此决定何时刷新
+    pub fn expires_within(&self, margin: Duration) -> bool {
+        let margin = i64::try_from(margin.as_secs()).unwrap_or(i64::MAX);
+        self.expires_at.saturating_sub(chrono::Utc::now().timestamp()) <= margin
+    }
+
+    /// 用签发者的身份密钥签名，输出令牌字符串
+    pub(crate) fn sign(&self, identity: &DeviceIdentity) -> Result<String> {
+        let claims = serde_json::to_vec(self).map_err(|e| Error::Serialization(e.to_string()))?;
+        let signature = identity.sign(&[&b"softbus token v1"[..], &claims].concat());
+        Ok(format!("{}.{}.{}", TOKEN_PREFIX, to_hex(&claims), to_hex(&signature)))
+    }
+
+    /// 解析令牌并用签发者的公钥验证签名，不检查过期和吊销
+    pub(crate) fn verify(token: &str, issuer_key: impl FnOnce(&DeviceId) -> Result<Vec<u8>>) -> Result<Self> {
+        let (claims, signature) = split_token(token)?;
+        let parsed: TokenClaims = serde_json::from_slice(&claims)
+            .map_err(|e| Error::Authentication(format!("Malformed token: {}", e)))?;
+        signature::UnparsedPublicKey::new(&ED25519, issuer_key(&parsed.issuer)?)
+            .verify(&[&b"softbus token v1"[..], &claims].concat(), &signature)
+            .map_err(|_| Error::Authentication("Invalid token signature".to_string()))?;
+        Ok(parsed)
+    }
+}
+
+/// 吊销记录的键：签发者只能吊销自己签发的令牌
+pub(crate) type RevocationKey = (DeviceId, String);
+
+/// 吊销记录，由令牌的签发者签名
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Revocation {
+    pub token_id: String,
+    pub issuer: DeviceId,
+    /// 被吊销令牌的过期时间，之后记录可以清除
+    pub expires_at: i64,
+    signature: Vec<u8>,
+}
+
+impl Revocation {
+    /// 签发者吊销自己签发的令牌
+    pub(crate) fn new(identity: &DeviceIdentity, claims: &TokenClaims) -> Self {
+        let mut revocation = Self {
+            token_id: claims.token_id.clone(),
+            issuer: claims.issuer.clone(),
+            expires_at: claims.expires_at,
+            signature: Vec::new(),
+        };
+        revocation.signature = identity.sign(&revocation.signed_bytes());
+        revocation
+    }
+
+    pub(crate) fn key(&self) -> RevocationKey {
+        (self.issuer.clone(), self.token_id.clone())
+    }
+
+    /// 用签发者的公钥验证签名
+    pub(crate) fn verify(&self, issuer_key: &[u8]) -> Result<()> {
+        signature::UnparsedPublicKey::new(&ED25519, issuer_key)
+            .verify(&self.signed_bytes(), &self.signature)
+            .map_err(|_| Error::Authentication("Invalid revocation signature".to_string()))
+    }
+
+    fn signed_bytes(&self) -> Vec<u8> {
+        [
+            &b"softbus revocation v1"[..],
+            self.issuer.as_str().as_bytes(),
+            b"\0",
+            self.token_id.as_bytes(),
+            &self.expires_at.to_be_bytes(),
+        ]
+        .concat()
+    }
+}
+
+/// 向对端提供吊销列表的RPC服务
+pub struct RevocationService {
+    auth: Arc<AuthManager>,
+}
+
+impl RevocationService {
+    pub fn new(auth: Arc<AuthManager>) -> Self {
+        Self { auth }
+    }
+
+    /// 把新的吊销推送给服务端的所有对端，返回成功发送的连接数
+    pub async fn publish(server: &RpcServer, revocation: &Revocation) -> Result<usize> {
+        server.publish(REVOCATION_SERVICE, REVOKED_EVENT, revocation).await
+    }
+
+    /// 从对端同步吊销列表
+    ///
+    /// 先订阅推送再拉取完整列表，避免两者之间的吊销丢失。返回的任务持续应用
+    /// 推送的吊销，连接断开后结束；签名无法验证的吊销被忽略。
+    pub async fn follow(client: &RpcClient, auth: Arc<AuthManager>) -> Result<JoinHandle<()>> {
+        let mut revoked = client.subscribe::<Revocation>(REVOCATION_SERVICE, REVOKED_EVENT);
+        let list: Vec<Revocation> = client.call(REVOCATION_SERVICE, LIST_METHOD, ()).await?;
+        for revocation in list {
+            apply(&auth, revocation);
+        }
+        Ok(tokio::spawn(async move {
+            while let Some(revocation) = revoked.next().await {
+                match revocation {
+                    Ok(revocation) => apply(&auth, revocation),
+                    Err(e) => tracing::warn!("Dropping malformed revocation: {}", e),
+                }
+            }
+        }))
+    }
+}
+
+fn apply(auth: &AuthManager, revocation: Revocation) {
+    let token_id = revocation.token_id.clone();
+    if let Err(e) = auth.apply_revocation(revocation) {
+        tracing::warn!("Ignoring revocation of token {}: {}", token_id, e);
+    }
+}
+
+impl RpcService for RevocationService {
+    fn service_name(&self) -> &str {
+        REVOCATION_SERVICE
+    }
+
+    fn method_handlers(&self) -> Vec<(String, Arc<dyn MethodHandler>)> {
+        vec![(
+            LIST_METHOD.to_string(),
+            Arc::new(ListHandler {
+                auth: Arc::clone(&self.auth),
+            }),
+        )]
+    }
+}
+
+struct ListHandler {
+    auth: Arc<AuthManager>,
+}
+
+#[async_trait]
+impl MethodHandler for ListHandler {
+    async fn handle(&self, ctx: &CallContext, _request: Bytes) -> Result<Bytes> {
+        Ok(Bytes::from(ctx.codec().encode(&self.auth.revocations())?))
+    }
+}
+
+fn split_token(token: &str) -> Result<(Vec<u8>, Vec<u8>)> {
+    let malformed = || Error::Authentication("Malformed token".to_string());
+    let mut parts = token.split('.');
+    if parts.next() != Some(TOKEN_PREFIX) {
+        return Err(malformed());
+    }
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(claims), Some(signature), None) => Ok((
+            from_hex(claims).ok_or_else(malformed)?,
+            from_hex(signature).ok_or_else(malformed)?,
+        )),
+        _ => Err(malformed()),
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(issuer: &DeviceIdentity) -> TokenClaims {
+        let now = chrono::Utc::now().timestamp();
+        TokenClaims {
+            token_id: "token-1".to_string(),
+            issuer: issuer.device_id().clone(),
+            device_id: DeviceId::new(),
+            scopes: vec!["camera.capture".to_string()],
+            issued_at: now,
+            expires_at: now + 600,
+        }
+    }
+
+    #[test]
+    fn test_token_roundtrip() {
+        let issuer = DeviceIdentity::generate().unwrap();
+        let claims = claims(&issuer);
+        let token = claims.sign(&issuer).unwrap();
+        assert!(token.starts_with("sbt1."));
+
+        let key = |_: &DeviceId| Ok(issuer.public_key().to_vec());
+        let verified = TokenClaims::verify(&token, key).unwrap();
+        assert_eq!(verified, claims);
+        assert!(verified.has_scope("camera.capture"));
+        assert!(!verified.has_scope("camera.admin"));
+        assert!(verified.expires_within(Duration::from_secs(601)));
+        assert!(!verified.expires_within(Duration::from_secs(60)));
+        assert!(verified.expires_within(Duration::MAX));
+
+        // 对端构造的极端过期时间不会溢出
+        let mut extreme = verified.clone();
+        extreme.expires_at = i64::MIN;
+        assert!(extreme.expires_within(Duration::ZERO));
+        extreme.expires_at = i64::MAX;
+        assert!(!extreme.expires_within(Duration::from_secs(3600)));
+        assert!(extreme.expires_within(Duration::MAX));
+    }
+
+    #[test]
+    fn test_forged_tokens_are_rejected() {
+        let issuer = DeviceIdentity::generate().unwrap();
+        let key = |_: &DeviceId| Ok(issuer.public_key().to_vec());
+
+        // 修改声明后签名不再匹配
+        let mut claims = claims(&issuer);
+        let token = claims.sign(&issuer).unwrap();
+        let signature = token.rsplit('.').next().unwrap();
+        claims.scopes.push("camera.admin".to_string());
+        let elevated = claims.sign(&issuer).unwrap();
+        let forged = format!("{}.{}", elevated.rsplit_once('.').unwrap().0, signature);
+        assert!(matches!(TokenClaims::verify(&forged, key), Err(Error::Authentication(_))));
+
+        // 其他设备签发的令牌
+        let other = DeviceIdentity::generate().unwrap();
+        assert!(TokenClaims::verify(&claims.sign(&other).unwrap(), key).is_err());
+
+        for malformed in ["", "sbt1", "sbt1.zz.00", "sbt2.00.00", "sbt1.00.00.00"] {
+            assert!(TokenClaims::verify(malformed, key).is_err());
+        }
+    }
+
+    #[test]
+    fn test_revocation_signature() {
+        let issuer = DeviceIdentity::generate().unwrap();
+        let revocation = Revocation::new(&issuer, &claims(&issuer));
+        assert!(revocation.verify(issuer.public_key()).is_ok());
+
+        let mut tampered = revocation.clone();
+        tampered.token_id = "token-2".to_string();
+        assert!(tampered.verify(issuer.public_key()).is_err());
+        let other = DeviceIdentity::generate().unwrap();
+        assert!(revocation.verify(other.public_key()).is_err());
+    }
+}
@@ -0,0 +1,398 @@
+//! 信任记录和凭证的存储
+//!
+//! `AuthManager`把信任记录、凭证和吊销记录写入`TrustStore`，重启后从中恢复，无需重新
+//! 配对。`FileTrustStore`把全部内容保存在一个文件中，每次修改先写临时文件再
+//! 原子替换，可以用本地密钥加密；`MemoryTrustStore`只保存在内存中，用于测试。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use crate::{DeviceId, Error, Result};
+use super::auth::{Credential, TrustRecord};
+use super::token::{Revocation, RevocationKey};
+
+/// 加密文件的头部，同时作为附加认证数据
+const ENCRYPTED_MAGIC: &[u8] = b"SBTS\x01";
+
+/// 信任记录、凭证和吊销记录的存储
+///
+/// 每个修改方法返回时修改已经持久化。
+pub trait TrustStore: Send + Sync {
+    /// 全部信任记录
+    fn records(&self) -> Result<Vec<TrustRecord>>;
+
+    /// 写入或替换设备的信任记录
+    fn put_record(&self, record: &TrustRecord) -> Result<()>;
+
+    /// 删除设备的信任记录
+    fn remove_record(&self, device_id: &DeviceId) -> Result<()>;
+
+    /// 全部凭证
+    fn credentials(&self) -> Result<Vec<Credential>>;
+
+    /// 写入或替换设备的凭证
+    fn put_credential(&self, credential: &Credential) -> Result<()>;
+
+    /// 删除设备的凭证
+    fn remove_credential(&self, device_id: &DeviceId) -> Result<()>;
+
+    /// 全部吊销记录
+    fn revocations(&self) -> Result<Vec<Revocation>>;
+
+    /// 写入吊销记录
+    fn put_revocation(&self, revocation: &Revocation) -> Result<()>;
+
+    /// 删除`issuer`签发的令牌`token_id`的吊销记录
+    fn remove_revocation(&self, issuer: &DeviceId, token_id: &str) -> Result<()>;
+}
+
+/// 内存存储，进程退出后内容丢失
+#[derive(Default)]
+pub struct MemoryTrustStore {
+    records: DashMap<DeviceId, TrustRecord>,
+    credentials: DashMap<DeviceId, Credential>,
+    revocations: DashMap<RevocationKey, Revocation>,
+}
+
+impl MemoryTrustStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TrustStore for MemoryTrustStore {
+    fn records(&self) -> Result<Vec<TrustRecord>> {
+        Ok(self.records.iter().map(|entry| entry.value().clone()).collect())
+    }
+
+    fn put_record(&self, record: &TrustRecord) -> Result<()> {
+        self.records.insert(record.device_id.clone(), record.clone());
+        Ok(())
+    }
+
+    fn remove_record(&self, device_id: &DeviceId) -> Result<()> {
+        self.records.remove(device_id);
+        Ok(())
+    }
+
+    fn credentials(&self) -> Result<Vec<Credential>> {
+        Ok(self.credentials.iter().map(|entry| entry.value().clone()).collect())
+    }
+
+    fn put_credential(&self, credential: &Credential) -> Result<()> {
+        self.credentials.insert(credential.device_id.clone(), credential.clone());
+        Ok(())
+    }
+
+    fn remove_credential(&self, device_id: &DeviceId) -> Result<()> {
+        self.credentials.remove(device_id);
+        Ok(())
+    }
+
+    fn revocations(&self) -> Result<Vec<Revocation>> {
+        Ok(self.revocations.iter().map(|entry| entry.value().clone()).collect())
+    }
+
+    fn put_revocation(&self, revocation: &Revocation) -> Result<()> {
+        self.revocations.insert(revocation.key(), revocation.clone());
+        Ok(())
+    }
+
+    fn remove_revocation(&self, issuer: &DeviceId, token_id: &str) -> Result<()> {
+        self.revocations.remove(&(issuer.clone(), token_id.to_string()));
+        Ok(())
+    }
+}
+
+/// 文件内容
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TrustFile {
+    records: BTreeMap<String, TrustRecord>,
+    credentials: BTreeMap<String, Credential>,
+    /// 键为`revocation_key`
+    #[serde(default)]
+    revocations: BTreeMap<String, Revocation>,
+}
+
+/// 文件中吊销记录的键，签发者和令牌ID的JSON数组，避免拼接产生歧义
+fn revocation_key(issuer: &DeviceId, token_id: &str) -> String {
+    serde_json::json!([issuer.as_str(), token_id]).to_string()
+}
+
+/// 文件存储
+///
+/// 内容为JSON，指定本地密钥时用AES-256-GCM加密。修改在内存副本上进行，写入
+/// 成功后才生效；写入时先写同目录下名称唯一的临时文件并同步到磁盘，再重命名
+/// 替换原文件并同步目录，崩溃或断电时文件要么是旧内容要么是新内容。
+///
+/// 内容在打开时读入内存，之后不再重新读取文件，同一路径同一时间只能由一个
+/// 存储打开，多个存储共用路径时后写入的会覆盖其他存储的修改。
+pub struct FileTrustStore {
+    path: PathBuf,
+    key: Option<LessSafeKey>,
+    contents: Mutex<TrustFile>,
+}
+
+impl FileTrustStore {
+    /// 打开明文存储，文件不存在时为空
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        Self::load(path.into(), None)
+    }
+
+    /// 打开用`key`加密的存储，已有的明文文件在下次写入时加密
+    pub fn open_encrypted(path: impl Into<PathBuf>, key: &[u8; 32]) -> Result<Self> {
+        let key = UnboundKey::new(&AES_256_GCM, key)
+            .map(LessSafeKey::new)
+            .map_err(|_| Error::Encryption("Invalid key".to_string()))?;
+        Self::load(path.into(), Some(key))
+    }
+
+    /// 存储文件的路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(path: PathBuf, key: Option<LessSafeKey>) -> Result<Self> {
+        let contents = match fs::read(&path) {
+            Ok(data) => decode(&data, key.as_ref())?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => TrustFile::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            key,
+            contents: Mutex::new(contents),
+        })
+    }
+
+    /// 在内存副本上修改并写入文件，写入失败时不改变内存副本
+    fn update(&self, change: impl FnOnce(&mut TrustFile)) -> Result<()> {
+        let mut contents = self.contents.lock();
+        let mut updated = contents.clone();
+        change(&mut updated);
+        self.write(&updated)?;
+        *contents = updated;
+        Ok(())
+    }
+
+    fn write(&self, contents: &TrustFile) -> Result<()> {
+        let data = encode(contents, self.key.as_ref())?;
+        // 同一路径上的多个存储各用各的临时文件
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(format!(".{}.tmp", uuid::Uuid::new_v4().simple()));
+        let temp = PathBuf::from(temp);
+
+        if let Err(e) = write_new(&temp, &data).and_then(|()| fs::rename(&temp, &self.path)) {
+            let _ = fs::remove_file(&temp);
+            return Err(e.into());
+        }
+        // 重命名记录在目录中，目录也要同步才能在断电后保留
+        #[cfg(unix)]
+        sync_parent(&self.path)?;
+        Ok(())
+    }
+}
+
+/// 创建新文件，写入数据并同步到磁盘
+fn write_new(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    file.write_all(data)?;
+    file.sync_all()
+}
+
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> std::io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::File::open(parent)?.sync_all()
+}
+
+impl TrustStore for FileTrustStore {
+    fn records(&self) -> Result<Vec<TrustRecord>> {
+        Ok(self.contents.lock().records.values().cloned().collect())
+    }
+
+    fn put_record(&self, record: &TrustRecord) -> Result<()> {
+        self.update(|contents| {
+            contents
+                .records
+                .insert(record.device_id.to_string(), record.clone());
+        })
+    }
+
+    fn remove_record(&self, device_id: &DeviceId) -> Result<()> {
+        self.update(|contents| {
+            contents.records.remove(device_id.as_str());
+        })
+    }
+
+    fn credentials(&self) -> Result<Vec<Credential>> {
+        Ok(self.contents.lock().credentials.values().cloned().collect())
+    }
+
+    fn put_credential(&self, credential: &Credential) -> Result<()> {
+        self.update(|contents| {
+            contents
+                .credentials
+                .insert(credential.device_id.to_string(), credential.clone());
+        })
+    }
+
+    fn remove_credential(&self, device_id: &DeviceId) -> Result<()> {
+        self.update(|contents| {
+            contents.credentials.remove(device_id.as_str());
+        })
+    }
+
+    fn revocations(&self) -> Result<Vec<Revocation>> {
+        Ok(self.contents.lock().revocations.values().cloned().collect())
+    }
+
+    fn put_revocation(&self, revocation: &Revocation) -> Result<()> {
+        self.update(|contents| {
+            contents
+                .revocations
+                .insert(revocation_key(&revocation.issuer, &revocation.token_id), revocation.clone());
+        })
+    }
+
+    fn remove_revocation(&self, issuer: &DeviceId, token_id: &str) -> Result<()> {
+        self.update(|contents| {
+            contents.revocations.remove(&revocation_key(issuer, token_id));
+        })
+    }
+}
+
+/// 序列化文件内容，有密钥时输出头部、nonce和密文
+fn encode(contents: &TrustFile, key: Option<&LessSafeKey>) -> Result<Vec<u8>> {
+    let mut data = serde_json::to_vec_pretty(contents).map_err(|e| Error::Serialization(e.to_string()))?;
+    let Some(key) = key else {
+        return Ok(data);
+    };
+
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| Error::Encryption("Failed to generate nonce".to_string()))?;
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(ENCRYPTED_MAGIC), &mut data)
+        .map_err(|_| Error::Encryption("Encryption failed".to_string()))?;
+    Ok([ENCRYPTED_MAGIC, &nonce, &data].concat())
+}
+
+fn decode(data: &[u8], key: Option<&LessSafeKey>) -> Result<TrustFile> {
+    let mut plaintext;
+    let json = match data.strip_prefix(ENCRYPTED_MAGIC) {
+        Some(sealed) => {
+            let key = key.ok_or_else(|| Error::Encryption("Trust store is encrypted".to_string()))?;
+            if sealed.len() < NONCE_LEN {
+                return Err(Error::Encryption("Trust store is truncated".to_string()));
+            }
+            let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+            let nonce = Nonce::try_assume_unique_for_key(nonce)
+                .map_err(|_| Error::Encryption("invalid nonce".to_string()))?;
+            plaintext = ciphertext.to_vec();
+            &*key
+                .open_in_place(nonce, Aad::from(ENCRYPTED_MAGIC), &mut plaintext)
+                .map_err(|_| Error::Encryption("Failed to decrypt trust store".to_string()))?
+        }
+        None => data,
+    };
+    serde_json::from_slice(json).map_err(|e| Error::Serialization(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use crate::security::TrustLevel;
+
+    fn record(level: TrustLevel) -> TrustRecord {
+        TrustRecord {
+            device_id: DeviceId::new(),
+            public_key: Some(vec![7; 32]),
+            trust_level: level,
+            paired_at: 1_700_000_000,
+            last_seen: None,
+        }
+    }
+
+    fn credential(device_id: &DeviceId) -> Credential {
+        Credential {
+            device_id: device_id.clone(),
+            token: "secret-token".to_string(),
+            expires_at: 1_800_000_000,
+        }
+    }
+
+    #[test]
+    fn test_file_store_survives_reopen() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("trust.json");
+        let (trusted, blocked) = (record(TrustLevel::Trusted), record(TrustLevel::Blocked));
+
+        let store = FileTrustStore::open(&path).unwrap();
+        assert!(store.records().unwrap().is_empty());
+        store.put_record(&trusted).unwrap();
+        store.put_record(&blocked).unwrap();
+        store.put_credential(&credential(&trusted.device_id)).unwrap();
+        store.remove_record(&blocked.device_id).unwrap();
+
+        let reopened = FileTrustStore::open(&path).unwrap();
+        assert_eq!(reopened.records().unwrap(), vec![trusted.clone()]);
+        let credentials = reopened.credentials().unwrap();
+        assert_eq!(credentials.len(), 1);
+        assert_eq!(credentials[0].token, "secret-token");
+
+        // 写入通过临时文件完成，不留下残余
+        let files: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn test_encrypted_file_store() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("trust.bin");
+        let trusted = record(TrustLevel::Trusted);
+
+        let store = FileTrustStore::open_encrypted(&path, &[3; 32]).unwrap();
+        store.put_record(&trusted).unwrap();
+        let data = fs::read(&path).unwrap();
+        assert!(data.starts_with(ENCRYPTED_MAGIC));
+        assert!(!data
+            .windows(trusted.device_id.as_str().len())
+            .any(|w| w == trusted.device_id.as_str().as_bytes()));
+
+        let reopened = FileTrustStore::open_encrypted(&path, &[3; 32]).unwrap();
+        assert_eq!(reopened.records().unwrap(), vec![trusted]);
+        assert!(matches!(FileTrustStore::open_encrypted(&path, &[4; 32]), Err(Error::Encryption(_))));
+        assert!(matches!(FileTrustStore::open(&path), Err(Error::Encryption(_))));
+    }
+
+    #[test]
+    fn test_plain_file_is_encrypted_on_next_write() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("trust.json");
+        let (first, second) = (record(TrustLevel::Trusted), record(TrustLevel::Known));
+        FileTrustStore::open(&path).unwrap().put_record(&first).unwrap();
+
+        let store = FileTrustStore::open_encrypted(&path, &[3; 32]).unwrap();
+        store.put_record(&second).unwrap();
+        assert!(fs::read(&path).unwrap().starts_with(ENCRYPTED_MAGIC));
+        assert_eq!(
+            FileTrustStore::open_encrypted(&path, &[3; 32]).unwrap().records().unwrap().len(),
+            2
+        );
+    }
+}
@@ -17,6 +17,12 @@ impl DeviceId {
         Self(s)
     }
 
+    /// 由设备身份公钥派生ID：公钥SHA-256摘要前16字节的十六进制
+    pub fn from_public_key(public_key: &[u8]) -> Self {
+        let digest = ring::digest::digest(&ring::digest::SHA256, public_key);
+        Self(digest.as_ref()[..16].iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
     pub fn as_str(&self) -> &str {
         &self.0
     }
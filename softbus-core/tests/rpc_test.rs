@@ -0,0 +1,1441 @@
+//! RPC端到端测试
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::sync::atomic::{AtomicU32, Ordering};
+use tokio::sync::{mpsc, oneshot, Mutex, Notify};
+use softbus_core::channel::ChannelOptions;
+use softbus_core::rpc::server::MethodHandler;
+use softbus_core::security::auth::Credential;
+use softbus_core::security::{AuthManager, DeviceIdentity, EncryptedChannel, Handshake, RevocationService, SessionRole};
+use softbus_core::rpc::{
+    CallContext, Codec, FrameType, Interceptor, Invoker, Middleware, Next, Proto, RetryPolicy, RpcClient,
+    RpcErrorCode, RpcRequest, RpcResponse, RpcServer, StreamHandler, StreamReader, StreamReceiver,
+    StreamSender, StreamWriter, CODEC_METADATA_KEY, DEADLINE_METADATA_KEY, IDEMPOTENCY_KEY_METADATA_KEY,
+};
+use softbus_core::*;
+
+/// 基于内存队列的双向通道
+struct MemoryChannel {
+    tx: mpsc::UnboundedSender<Bytes>,
+    rx: Mutex<mpsc::UnboundedReceiver<Bytes>>,
+    peer: Option<String>,
+}
+
+impl MemoryChannel {
+    fn pair() -> (Arc<MemoryChannel>, Arc<MemoryChannel>) {
+        Self::pair_with_peer(None)
+    }
+
+    /// 第二个端点报告对端设备为`peer`
+    fn pair_with_peer(peer: Option<&DeviceId>) -> (Arc<MemoryChannel>, Arc<MemoryChannel>) {
+        let (tx_a, rx_a) = mpsc::unbounded_channel();
+        let (tx_b, rx_b) = mpsc::unbounded_channel();
+        (
+            Arc::new(MemoryChannel { tx: tx_a, rx: Mutex::new(rx_b), peer: None }),
+            Arc::new(MemoryChannel { tx: tx_b, rx: Mutex::new(rx_a), peer: peer.map(DeviceId::to_string) }),
+        )
+    }
+}
+
+#[async_trait]
+impl Channel for MemoryChannel {
+    async fn send(&self, data: Bytes) -> Result<()> {
+        self.tx
+            .send(data)
+            .map_err(|_| Error::Connection("Channel closed".to_string()))
+    }
+
+    async fn recv(&self) -> Result<Bytes> {
+        self.rx
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or_else(|| Error::Connection("Channel closed".to_string()))
+    }
+
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        !self.tx.is_closed()
+    }
+
+    fn qos_level(&self) -> QosLevel {
+        QosLevel::Balanced
+    }
+
+    fn peer_device_id(&self) -> Option<String> {
+        self.peer.clone()
+    }
+
+    async fn set_options(&self, _options: ChannelOptions) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// 模拟不稳定链路的通道：接下来的若干次发送失败或丢失
+struct FlakyChannel {
+    inner: Arc<MemoryChannel>,
+    failures: AtomicU32,
+    losses: AtomicU32,
+}
+
+impl FlakyChannel {
+    fn new(inner: Arc<MemoryChannel>) -> Arc<Self> {
+        Arc::new(Self { inner, failures: AtomicU32::new(0), losses: AtomicU32::new(0) })
+    }
+
+    /// 接下来`count`次发送返回网络错误
+    fn fail_next_sends(&self, count: u32) {
+        self.failures.store(count, Ordering::SeqCst);
+    }
+
+    /// 接下来`count`次发送报告成功，但数据丢失
+    fn lose_next_sends(&self, count: u32) {
+        self.losses.store(count, Ordering::SeqCst);
+    }
+}
+
+fn take_one(counter: &AtomicU32) -> bool {
+    counter
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .is_ok()
+}
+
+#[async_trait]
+impl Channel for FlakyChannel {
+    async fn send(&self, data: Bytes) -> Result<()> {
+        if take_one(&self.failures) {
+            return Err(Error::Network("link lost".to_string()));
+        }
+        if take_one(&self.losses) {
+            return Ok(());
+        }
+        self.inner.send(data).await
+    }
+
+    async fn recv(&self) -> Result<Bytes> {
+        self.inner.recv().await
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    fn is_connected(&self) -> bool {
+        self.inner.is_connected()
+    }
+
+    fn qos_level(&self) -> QosLevel {
+        self.inner.qos_level()
+    }
+
+    fn peer_device_id(&self) -> Option<String> {
+        self.inner.peer_device_id()
+    }
+
+    async fn set_options(&self, options: ChannelOptions) -> Result<()> {
+        self.inner.set_options(options).await
+    }
+}
+
+struct AddOneHandler;
+
+#[async_trait]
+impl MethodHandler for AddOneHandler {
+    async fn handle(&self, ctx: &CallContext, request: Bytes) -> Result<Bytes> {
+        let value: i32 = ctx.codec().decode(&request)?;
+        Ok(Bytes::from(ctx.codec().encode(&(value + 1))?))
+    }
+}
+
+/// protobuf编码的图像帧
+#[derive(Clone, PartialEq, prost::Message)]
+struct ImageFrame {
+    #[prost(uint32, tag = "1")]
+    sequence: u32,
+    #[prost(bytes = "vec", tag = "2")]
+    data: Vec<u8>,
+}
+
+/// 返回下一帧，序号加一、数据反转
+struct NextFrameHandler;
+
+#[async_trait]
+impl MethodHandler for NextFrameHandler {
+    async fn handle(&self, ctx: &CallContext, request: Bytes) -> Result<Bytes> {
+        let Proto(mut frame): Proto<ImageFrame> = ctx.codec().decode(&request)?;
+        frame.sequence += 1;
+        frame.data.reverse();
+        Ok(Bytes::from(ctx.codec().encode(&Proto(frame))?))
+    }
+}
+
+/// 每次执行拍一张照片，返回照片序号
+struct PhotoHandler {
+    photos: Arc<AtomicU32>,
+}
+
+#[async_trait]
+impl MethodHandler for PhotoHandler {
+    async fn handle(&self, ctx: &CallContext, _request: Bytes) -> Result<Bytes> {
+        let photo = self.photos.fetch_add(1, Ordering::SeqCst) + 1;
+        Ok(Bytes::from(ctx.codec().encode(&photo)?))
+    }
+}
+
+struct BusyHandler;
+
+#[async_trait]
+impl MethodHandler for BusyHandler {
+    async fn handle(&self, _ctx: &CallContext, _request: Bytes) -> Result<Bytes> {
+        Err(Error::Internal("camera busy".to_string()))
+    }
+}
+
+/// 阻塞直到闸门打开的处理器
+struct GateHandler {
+    gate: Arc<Notify>,
+}
+
+#[async_trait]
+impl MethodHandler for GateHandler {
+    async fn handle(&self, _ctx: &CallContext, request: Bytes) -> Result<Bytes> {
+        self.gate.notified().await;
+        Ok(request)
+    }
+}
+
+/// 模拟耗时拍摄：开始后通过`started`通知，把实际工作交给后台任务，
+/// 后台任务观察到取消后通过`stopped`通知
+struct CaptureHandler {
+    started: mpsc::UnboundedSender<()>,
+    stopped: mpsc::UnboundedSender<()>,
+}
+
+#[async_trait]
+impl MethodHandler for CaptureHandler {
+    async fn handle(&self, ctx: &CallContext, _request: Bytes) -> Result<Bytes> {
+        let token = ctx.cancellation().clone();
+        let stopped = self.stopped.clone();
+        tokio::spawn(async move {
+            token.cancelled().await;
+            let _ = stopped.send(());
+        });
+        let _ = self.started.send(());
+        std::future::pending().await
+    }
+}
+
+/// 只允许受信任设备拍摄，返回调用方设备、通道QoS级别和请求元数据的键
+struct TrustedCaptureHandler {
+    auth: Arc<AuthManager>,
+}
+
+#[async_trait]
+impl MethodHandler for TrustedCaptureHandler {
+    async fn handle(&self, ctx: &CallContext, _request: Bytes) -> Result<Bytes> {
+        let peer = match ctx.peer() {
+            Some(peer) if self.auth.is_trusted(peer) => peer,
+            _ => return Err(Error::Authentication("capture requires a trusted device".to_string())),
+        };
+        assert!(!ctx.request_id().is_empty());
+        let mut keys: Vec<String> = ctx.metadata().keys().cloned().collect();
+        keys.sort();
+        let result = bincode::serialize(&(peer.to_string(), ctx.qos_level(), keys))
+            .map_err(|e| Error::Serialization(e.to_string()))?;
+        Ok(Bytes::from(result))
+    }
+}
+
+fn start_server(server: RpcServer) -> RpcClient {
+    start_server_with_peer(server, None)
+}
+
+/// 启动服务端，服务端一侧的通道报告对端设备为`peer`
+fn start_server_with_peer(server: RpcServer, peer: Option<&DeviceId>) -> RpcClient {
+    let (client_end, server_end) = MemoryChannel::pair_with_peer(peer);
+    tokio::spawn(async move {
+        let _ = server.serve(server_end).await;
+    });
+    RpcClient::new(client_end)
+}
+
+/// 握手后启动服务端，服务端一侧的通道报告握手验证的对端`client`
+async fn start_server_with_handshake(
+    server: RpcServer,
+    client: Arc<DeviceIdentity>,
+    server_identity: Arc<DeviceIdentity>,
+) -> RpcClient {
+    let (client_end, server_end) = MemoryChannel::pair();
+    let (initiator, responder) = (Handshake::new(client), Handshake::new(server_identity));
+    let (client_session, server_session) =
+        tokio::join!(initiator.initiate(&*client_end), responder.respond(&*server_end));
+    let server_end = server_session.unwrap().into_channel(server_end).unwrap();
+    tokio::spawn(async move {
+        let _ = server.serve(Arc::new(server_end)).await;
+    });
+    RpcClient::new(Arc::new(client_session.unwrap().into_channel(client_end).unwrap()))
+}
+
+#[tokio::test]
+async fn test_rpc_call_roundtrip() {
+    let server = RpcServer::new();
+    server.register_method("MathService", "add_one", Arc::new(AddOneHandler));
+    let client = start_server(server);
+
+    let result: i32 = client.call("MathService", "add_one", 41).await.unwrap();
+    assert_eq!(result, 42);
+}
+
+#[tokio::test]
+async fn test_rpc_errors_reach_client() {
+    let server = RpcServer::new();
+    server.register_method("MathService", "add_one", Arc::new(AddOneHandler));
+    server.register_method("CameraService", "capture", Arc::new(BusyHandler));
+    let client = start_server(server);
+
+    let result: Result<Vec<u8>> = client.call("CameraService", "capture", ()).await;
+    assert!(matches!(result, Err(Error::Internal(msg)) if msg.contains("camera busy")));
+
+    let result: Result<bool> = client.call("CameraService", "open", 0).await;
+    assert!(matches!(result, Err(Error::MethodNotFound(_))));
+
+    // 处理器出错后服务端仍然继续服务
+    let result: i32 = client.call("MathService", "add_one", 1).await.unwrap();
+    assert_eq!(result, 2);
+}
+
+/// 读取`count`个请求后按逆序回复，每个响应的值为请求值加一
+async fn reply_in_reverse(channel: Arc<MemoryChannel>, count: usize) {
+    let mut requests = Vec::new();
+    for _ in 0..count {
+        let data = channel.recv().await.unwrap();
+        requests.push(RpcRequest::decode_bytes(&data).unwrap());
+    }
+
+    for request in requests.into_iter().rev() {
+        let value: i32 = bincode::deserialize(&request.payload).unwrap();
+        let payload = Bytes::from(bincode::serialize(&(value + 1)).unwrap());
+        let response = RpcResponse::ok(request.request_id, payload);
+        channel.send(response.encode_bytes()).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_concurrent_calls_share_channel() {
+    let (client_end, server_end) = MemoryChannel::pair();
+    let client = Arc::new(RpcClient::new(client_end));
+    let server = tokio::spawn(reply_in_reverse(server_end, 16));
+
+    let calls = (0..16).map(|i| {
+        let client = Arc::clone(&client);
+        tokio::spawn(async move {
+            let result: i32 = client.call("MathService", "add_one", i).await.unwrap();
+            (i, result)
+        })
+    });
+
+    for call in calls.collect::<Vec<_>>() {
+        let (i, result) = call.await.unwrap();
+        assert_eq!(result, i + 1);
+    }
+    server.await.unwrap();
+    assert_eq!(client.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn test_timed_out_call_frees_slot() {
+    let (client_end, server_end) = MemoryChannel::pair();
+    let client = RpcClient::new(client_end).with_timeout(Duration::from_millis(50));
+
+    // 第一个调用无人应答，超时后槽位被释放
+    let result: Result<i32> = client.call("MathService", "add_one", 1).await;
+    assert!(matches!(result, Err(Error::Timeout)));
+    assert_eq!(client.in_flight(), 0);
+
+    // 请求携带截止时间，超时后客户端发送取消帧
+    let late = server_end.recv().await.unwrap();
+    let late = RpcRequest::decode_bytes(&late).unwrap();
+    assert!(late.metadata.contains_key(DEADLINE_METADATA_KEY));
+    let cancel = RpcRequest::decode_bytes(&server_end.recv().await.unwrap()).unwrap();
+    assert_eq!(cancel.frame_type(), FrameType::Cancel);
+    assert_eq!(cancel.request_id, late.request_id);
+
+    // 迟到的响应被丢弃，不会被下一个调用误收
+    let stale = RpcResponse::ok(late.request_id, Bytes::from(bincode::serialize(&0i32).unwrap()));
+    server_end.send(stale.encode_bytes()).await.unwrap();
+
+    let server = tokio::spawn(reply_in_reverse(server_end, 1));
+    let result: i32 = client.call("MathService", "add_one", 2).await.unwrap();
+    assert_eq!(result, 3);
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pending_calls_fail_when_channel_closes() {
+    let (client_end, server_end) = MemoryChannel::pair();
+    let client = RpcClient::new(client_end);
+
+    let closer = tokio::spawn(async move {
+        let _ = server_end.recv().await;
+        drop(server_end);
+    });
+
+    let result: Result<i32> = client.call("MathService", "add_one", 1).await;
+    assert!(matches!(result, Err(Error::Connection(_))));
+    closer.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_slow_handler_does_not_block_others() {
+    let gate = Arc::new(Notify::new());
+    let server = RpcServer::new();
+    server.register_method("TestService", "wait", Arc::new(GateHandler { gate: Arc::clone(&gate) }));
+    server.register_method("MathService", "add_one", Arc::new(AddOneHandler));
+    let client = Arc::new(start_server(server));
+
+    let slow = {
+        let client = Arc::clone(&client);
+        tokio::spawn(async move {
+            let result: i32 = client.call("TestService", "wait", 7).await.unwrap();
+            result
+        })
+    };
+
+    // 慢请求仍在等待时，后续请求照常完成
+    let result: i32 = client.call("MathService", "add_one", 1).await.unwrap();
+    assert_eq!(result, 2);
+    assert!(!slow.is_finished());
+
+    gate.notify_one();
+    assert_eq!(slow.await.unwrap(), 7);
+}
+
+#[tokio::test]
+async fn test_max_in_flight_limits_concurrency() {
+    let gate = Arc::new(Notify::new());
+    let server = RpcServer::new().with_max_in_flight(1);
+    server.register_method("TestService", "wait", Arc::new(GateHandler { gate: Arc::clone(&gate) }));
+    server.register_method("MathService", "add_one", Arc::new(AddOneHandler));
+    let client = Arc::new(start_server(server));
+
+    let slow = {
+        let client = Arc::clone(&client);
+        tokio::spawn(async move {
+            let result: i32 = client.call("TestService", "wait", 7).await.unwrap();
+            result
+        })
+    };
+    tokio::task::yield_now().await;
+
+    // 唯一的配额被慢请求占用，新请求无法被处理
+    let call = client.call::<_, i32>("MathService", "add_one", 1);
+    assert!(tokio::time::timeout(Duration::from_millis(100), call).await.is_err());
+
+    gate.notify_one();
+    assert_eq!(slow.await.unwrap(), 7);
+}
+
+#[tokio::test]
+async fn test_deadline_cancels_server_handler() {
+    let (started, _) = mpsc::unbounded_channel();
+    let (stopped, mut stopped_rx) = mpsc::unbounded_channel();
+    let server = RpcServer::new();
+    server.register_method("CameraService", "capture", Arc::new(CaptureHandler { started, stopped }));
+    server.register_method("MathService", "add_one", Arc::new(AddOneHandler));
+    let client = start_server(server).with_timeout(Duration::from_millis(50));
+
+    let result: Result<Vec<u8>> = client.call("CameraService", "capture", ()).await;
+    assert!(matches!(result, Err(Error::Timeout)));
+
+    // 服务端同样放弃处理，释放的配额可以继续服务
+    tokio::time::timeout(Duration::from_secs(1), stopped_rx.recv()).await.unwrap().unwrap();
+    let result: i32 = client.call("MathService", "add_one", 1).await.unwrap();
+    assert_eq!(result, 2);
+}
+
+#[tokio::test]
+async fn test_dropped_call_cancels_server_handler() {
+    let (started, mut started_rx) = mpsc::unbounded_channel();
+    let (stopped, mut stopped_rx) = mpsc::unbounded_channel();
+    let server = RpcServer::new().with_max_in_flight(1);
+    server.register_method("CameraService", "capture", Arc::new(CaptureHandler { started, stopped }));
+    server.register_method("MathService", "add_one", Arc::new(AddOneHandler));
+    let client = Arc::new(start_server(server));
+
+    let capture = {
+        let client = Arc::clone(&client);
+        tokio::spawn(async move {
+            let _: Result<Vec<u8>> = client.call("CameraService", "capture", ()).await;
+        })
+    };
+    started_rx.recv().await.unwrap();
+    capture.abort();
+
+    // 调用方放弃后处理器被取消，唯一的配额随之释放
+    tokio::time::timeout(Duration::from_secs(1), stopped_rx.recv()).await.unwrap().unwrap();
+    let result: i32 = client.call("MathService", "add_one", 1).await.unwrap();
+    assert_eq!(result, 2);
+    assert_eq!(client.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn test_cancel_reaches_saturated_server() {
+    let (started, mut started_rx) = mpsc::unbounded_channel();
+    let (stopped, mut stopped_rx) = mpsc::unbounded_channel();
+    let server = RpcServer::new().with_max_in_flight(1);
+    server.register_method("CameraService", "capture", Arc::new(CaptureHandler { started, stopped }));
+    let client = Arc::new(start_server(server));
+    let capture = || {
+        let client = Arc::clone(&client);
+        tokio::spawn(async move {
+            let _: Result<Vec<u8>> = client.call("CameraService", "capture", ()).await;
+        })
+    };
+
+    let first = capture();
+    started_rx.recv().await.unwrap();
+    let (second, third) = (capture(), capture());
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    // 配额用尽时服务端仍在读取，取消帧能送达执行中和排队中的调用
+    third.abort();
+    first.abort();
+    tokio::time::timeout(Duration::from_secs(1), stopped_rx.recv()).await.unwrap().unwrap();
+
+    // 释放的配额交给排队的调用，排队中被取消的调用不再执行
+    tokio::time::timeout(Duration::from_secs(1), started_rx.recv()).await.unwrap().unwrap();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(started_rx.try_recv().is_err());
+    second.abort();
+    tokio::time::timeout(Duration::from_secs(1), stopped_rx.recv()).await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_disconnect_cancels_server_handler() {
+    let (started, mut started_rx) = mpsc::unbounded_channel();
+    let (stopped, mut stopped_rx) = mpsc::unbounded_channel();
+    let server = RpcServer::new();
+    server.register_method("CameraService", "capture", Arc::new(CaptureHandler { started, stopped }));
+    let (client_end, server_end) = MemoryChannel::pair();
+    let serving = tokio::spawn(async move { server.serve(server_end).await });
+
+    // 请求不带截止时间，处理器只能靠断开连接结束
+    let request = RpcRequest {
+        request_id: "req-1".to_string(),
+        service_name: "CameraService".to_string(),
+        method_name: "capture".to_string(),
+        payload: bincode::serialize(&()).unwrap(),
+        ..Default::default()
+    };
+    client_end.send(request.encode_bytes()).await.unwrap();
+    started_rx.recv().await.unwrap();
+    drop(client_end);
+
+    tokio::time::timeout(Duration::from_secs(1), stopped_rx.recv()).await.unwrap().unwrap();
+    let result = tokio::time::timeout(Duration::from_secs(1), serving).await.unwrap().unwrap();
+    assert!(result.is_err());
+}
+
+/// 按请求中的数量依次发送0..count，并记录已发送的条数
+struct CountHandler {
+    sent: Arc<AtomicU32>,
+}
+
+#[async_trait]
+impl StreamHandler for CountHandler {
+    async fn handle(
+        &self,
+        _ctx: &CallContext,
+        request: Bytes,
+        inbound: StreamReader,
+        outbound: StreamWriter,
+    ) -> Result<()> {
+        let count: u32 = inbound.codec().decode(&request)?;
+        let sender = StreamSender::<u32>::new(outbound);
+        for i in 0..count {
+            sender.send(i).await?;
+            self.sent.fetch_add(1, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+}
+
+/// 累加客户端发来的所有数字，返回总和
+struct SumHandler;
+
+#[async_trait]
+impl StreamHandler for SumHandler {
+    async fn handle(
+        &self,
+        _ctx: &CallContext,
+        _request: Bytes,
+        inbound: StreamReader,
+        outbound: StreamWriter,
+    ) -> Result<()> {
+        let mut inbound = StreamReceiver::<u32>::new(inbound);
+        let mut sum = 0u64;
+        while let Some(value) = inbound.next().await {
+            sum += u64::from(value?);
+        }
+        StreamSender::<u64>::new(outbound).send(sum).await
+    }
+}
+
+/// 把收到的每条消息加上前缀后发回
+struct EchoStreamHandler;
+
+#[async_trait]
+impl StreamHandler for EchoStreamHandler {
+    async fn handle(
+        &self,
+        _ctx: &CallContext,
+        request: Bytes,
+        inbound: StreamReader,
+        outbound: StreamWriter,
+    ) -> Result<()> {
+        let prefix: String = inbound.codec().decode(&request)?;
+        let mut inbound = StreamReceiver::<String>::new(inbound);
+        let outbound = StreamSender::<String>::new(outbound);
+        while let Some(message) = inbound.next().await {
+            outbound.send(format!("{}{}", prefix, message?)).await?;
+        }
+        Ok(())
+    }
+}
+
+/// 无限发送，处理任务被中止时通过`stopped`通知
+struct EndlessHandler {
+    stopped: parking_lot::Mutex<Option<oneshot::Sender<()>>>,
+}
+
+#[async_trait]
+impl StreamHandler for EndlessHandler {
+    async fn handle(
+        &self,
+        _ctx: &CallContext,
+        _request: Bytes,
+        _inbound: StreamReader,
+        outbound: StreamWriter,
+    ) -> Result<()> {
+        let _stopped = self.stopped.lock().take();
+        let sender = StreamSender::<u32>::new(outbound);
+        loop {
+            sender.send(0).await?;
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_server_streaming() {
+    let server = RpcServer::new();
+    let sent = Arc::new(AtomicU32::new(0));
+    server.register_stream_method("CameraService", "preview", Arc::new(CountHandler { sent }));
+    let client = start_server(server);
+
+    let mut frames: StreamReceiver<u32> = client.server_stream("CameraService", "preview", 100u32).await.unwrap();
+    let mut received = Vec::new();
+    while let Some(frame) = frames.next().await {
+        received.push(frame.unwrap());
+    }
+    assert_eq!(received, (0..100).collect::<Vec<_>>());
+    assert_eq!(client.open_streams(), 0);
+}
+
+#[tokio::test]
+async fn test_client_streaming() {
+    let server = RpcServer::new();
+    server.register_stream_method("MathService", "sum", Arc::new(SumHandler));
+    let client = start_server(server.with_stream_window(4));
+
+    let call = client.client_stream::<_, u32, u64>("MathService", "sum", ()).await.unwrap();
+    for i in 1..=100 {
+        call.send(i).await.unwrap();
+    }
+    assert_eq!(call.finish().await.unwrap(), 5050);
+}
+
+#[tokio::test]
+async fn test_bidirectional_streaming_alongside_unary_calls() {
+    let server = RpcServer::new();
+    server.register_stream_method("ChatService", "echo", Arc::new(EchoStreamHandler));
+    server.register_method("MathService", "add_one", Arc::new(AddOneHandler));
+    let client = start_server(server);
+
+    let (sender, mut receiver): (StreamSender<String>, StreamReceiver<String>) =
+        client.open_stream("ChatService", "echo", "> ".to_string()).await.unwrap();
+
+    for word in ["hello", "softbus"] {
+        sender.send(word.to_string()).await.unwrap();
+        assert_eq!(receiver.next().await.unwrap().unwrap(), format!("> {}", word));
+
+        // 同一通道上的普通调用不受流影响
+        let result: i32 = client.call("MathService", "add_one", 1).await.unwrap();
+        assert_eq!(result, 2);
+    }
+
+    sender.finish().await.unwrap();
+    assert!(receiver.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_stream_flow_control_limits_unread_messages() {
+    let server = RpcServer::new();
+    let sent = Arc::new(AtomicU32::new(0));
+    server.register_stream_method("CameraService", "preview", Arc::new(CountHandler { sent: Arc::clone(&sent) }));
+    let client = start_server(server).with_stream_window(4);
+
+    let mut frames: StreamReceiver<u32> = client.server_stream("CameraService", "preview", 20u32).await.unwrap();
+
+    // 客户端不读取时，服务端最多发送一个窗口的消息
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(sent.load(Ordering::SeqCst), 4);
+
+    let mut count = 0;
+    while let Some(frame) = frames.next().await {
+        assert_eq!(frame.unwrap(), count);
+        count += 1;
+    }
+    assert_eq!(count, 20);
+}
+
+#[tokio::test]
+async fn test_dropping_receiver_cancels_server_handler() {
+    let (stopped_tx, stopped_rx) = oneshot::channel();
+    let server = RpcServer::new();
+    server.register_stream_method(
+        "CameraService",
+        "preview",
+        Arc::new(EndlessHandler { stopped: parking_lot::Mutex::new(Some(stopped_tx)) }),
+    );
+    let client = start_server(server);
+
+    let mut frames: StreamReceiver<u32> = client.server_stream("CameraService", "preview", ()).await.unwrap();
+    assert_eq!(frames.next().await.unwrap().unwrap(), 0);
+    drop(frames);
+
+    // 取消帧到达后处理任务被中止
+    tokio::time::timeout(Duration::from_secs(1), stopped_rx).await.unwrap().unwrap_err();
+    assert_eq!(client.open_streams(), 0);
+}
+
+/// 向客户端发送调用方设备ID后一直等待，取消令牌触发后通知`stopped`
+struct WatchHandler {
+    started: mpsc::UnboundedSender<()>,
+    stopped: mpsc::UnboundedSender<()>,
+}
+
+#[async_trait]
+impl StreamHandler for WatchHandler {
+    async fn handle(
+        &self,
+        ctx: &CallContext,
+        _request: Bytes,
+        _inbound: StreamReader,
+        outbound: StreamWriter,
+    ) -> Result<()> {
+        let token = ctx.cancellation().clone();
+        let stopped = self.stopped.clone();
+        tokio::spawn(async move {
+            token.cancelled().await;
+            let _ = stopped.send(());
+        });
+        let _ = self.started.send(());
+        let peer = ctx.peer().map(DeviceId::to_string).unwrap_or_default();
+        StreamSender::<String>::new(outbound).send(peer).await?;
+        std::future::pending().await
+    }
+}
+
+#[tokio::test]
+async fn test_stream_handlers_see_call_context() {
+    let (started, mut started_rx) = mpsc::unbounded_channel();
+    let (stopped, mut stopped_rx) = mpsc::unbounded_channel();
+    let server = RpcServer::new();
+    server.register_stream_method("CameraService", "watch", Arc::new(WatchHandler { started, stopped }));
+
+    // 处理器看到握手验证的调用方，客户端取消流时触发取消令牌
+    let (phone, camera) = (Arc::new(DeviceIdentity::generate().unwrap()), Arc::new(DeviceIdentity::generate().unwrap()));
+    let client = start_server_with_handshake(server.clone(), Arc::clone(&phone), camera).await;
+    let mut frames: StreamReceiver<String> = client.server_stream("CameraService", "watch", ()).await.unwrap();
+    started_rx.recv().await.unwrap();
+    assert_eq!(frames.next().await.unwrap().unwrap(), phone.device_id().to_string());
+    drop(frames);
+    tokio::time::timeout(Duration::from_secs(1), stopped_rx.recv()).await.unwrap().unwrap();
+
+    // 通道断开时同样触发
+    let (client_end, server_end) = MemoryChannel::pair();
+    let serving = tokio::spawn(async move { server.serve(server_end).await });
+    let mut open = RpcRequest::stream_frame("watch-1".to_string(), FrameType::StreamOpen, bincode::serialize(&()).unwrap(), 1);
+    open.service_name = "CameraService".to_string();
+    open.method_name = "watch".to_string();
+    client_end.send(open.encode_bytes()).await.unwrap();
+    started_rx.recv().await.unwrap();
+    drop(client_end);
+
+    tokio::time::timeout(Duration::from_secs(1), stopped_rx.recv()).await.unwrap().unwrap();
+    let result = tokio::time::timeout(Duration::from_secs(1), serving).await.unwrap().unwrap();
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_stream_errors_reach_client() {
+    let server = RpcServer::new();
+    server.register_stream_method("MathService", "sum", Arc::new(SumHandler));
+    let client = start_server(server);
+
+    let mut missing: StreamReceiver<u32> = client.server_stream("CameraService", "preview", ()).await.unwrap();
+    assert!(matches!(missing.next().await, Some(Err(Error::ServiceNotFound(_)))));
+    assert!(missing.next().await.is_none());
+
+    // 流式方法不能按普通调用访问
+    let result: Result<u64> = client.call("MathService", "sum", ()).await;
+    assert!(matches!(result, Err(Error::MethodNotFound(_))));
+
+    // 客户端发送了无法解码的消息，处理器的错误随结束帧返回
+    let call = client.client_stream::<_, u8, u64>("MathService", "sum", ()).await.unwrap();
+    call.send(1).await.unwrap();
+    assert!(matches!(call.finish().await, Err(Error::Serialization(_))));
+}
+
+#[tokio::test]
+async fn test_streams_fail_when_channel_closes() {
+    let (client_end, server_end) = MemoryChannel::pair();
+    let client = RpcClient::new(client_end);
+
+    let mut frames: StreamReceiver<u32> = client.server_stream("CameraService", "preview", ()).await.unwrap();
+    drop(server_end);
+
+    assert!(matches!(frames.next().await, Some(Err(Error::Connection(_)))));
+    assert_eq!(client.open_streams(), 0);
+}
+
+/// 记录收到的单向调用，处理越早的值耗时越长
+struct RecordHandler {
+    seen: mpsc::UnboundedSender<u32>,
+}
+
+#[async_trait]
+impl MethodHandler for RecordHandler {
+    async fn handle(&self, _ctx: &CallContext, request: Bytes) -> Result<Bytes> {
+        let value: u32 = bincode::deserialize(&request)
+            .map_err(|e| Error::Serialization(e.to_string()))?;
+        tokio::time::sleep(Duration::from_millis(u64::from(5 - value.min(5)))).await;
+        let _ = self.seen.send(value);
+        Ok(Bytes::new())
+    }
+}
+
+#[tokio::test]
+async fn test_oneway_calls_run_in_order() {
+    let (seen, mut received) = mpsc::unbounded_channel();
+    let server = RpcServer::new();
+    server.register_method("RemoteControl", "key", Arc::new(RecordHandler { seen }));
+    server.register_method("MathService", "add_one", Arc::new(AddOneHandler));
+    let client = start_server(server);
+
+    // 找不到方法的通知被丢弃，不会产生响应
+    client.notify("RemoteControl", "missing", 0u32).await.unwrap();
+    for value in 0..5u32 {
+        client.notify("RemoteControl", "key", value).await.unwrap();
+    }
+
+    let mut values = Vec::new();
+    for _ in 0..5 {
+        values.push(received.recv().await.unwrap());
+    }
+    assert_eq!(values, [0, 1, 2, 3, 4]);
+
+    let result: i32 = client.call("MathService", "add_one", 1).await.unwrap();
+    assert_eq!(result, 2);
+    assert_eq!(client.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn test_events_reach_subscribers() {
+    let server = RpcServer::new();
+    server.register_method("MathService", "add_one", Arc::new(AddOneHandler));
+    let client = start_server(server.clone());
+
+    let mut disconnected = client.subscribe::<i32>("CameraService", "disconnected");
+    let mut other = client.subscribe::<String>("CameraService", "renamed");
+    // 完成一次调用，确保服务端已开始服务该通道
+    let _: i32 = client.call("MathService", "add_one", 0).await.unwrap();
+    assert_eq!(server.peers(), 1);
+
+    assert_eq!(server.publish("CameraService", "disconnected", &3).await.unwrap(), 1);
+    assert_eq!(server.publish("CameraService", "renamed", "rear").await.unwrap(), 1);
+    assert_eq!(disconnected.next().await.unwrap().unwrap(), 3);
+    assert_eq!(other.next().await.unwrap().unwrap(), "rear");
+
+    // 类型不匹配的事件以错误交给订阅者
+    server.publish("CameraService", "disconnected", &1u8).await.unwrap();
+    assert!(matches!(disconnected.next().await, Some(Err(Error::Serialization(_)))));
+}
+
+#[tokio::test]
+async fn test_subscriptions_end_when_channel_closes() {
+    let (client_end, server_end) = MemoryChannel::pair();
+    let client = RpcClient::new(client_end);
+
+    let mut events = client.subscribe::<i32>("CameraService", "disconnected");
+    drop(server_end);
+
+    assert!(events.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_handlers_see_authenticated_caller() {
+    let device = DeviceId::new();
+    let auth = Arc::new(AuthManager::new());
+    auth.add_credential(Credential {
+        device_id: device.clone(),
+        token: "secret".to_string(),
+        expires_at: chrono::Utc::now().timestamp() + 3600,
+    })
+    .unwrap();
+    let server = RpcServer::new().with_auth_manager(Arc::clone(&auth));
+    server.register_method("CameraService", "capture", Arc::new(TrustedCaptureHandler { auth: Arc::clone(&auth) }));
+
+    type CallerInfo = (String, Option<QosLevel>, Vec<String>);
+    let anonymous = start_server_with_peer(server.clone(), Some(&device));
+    let forged = start_server_with_peer(server.clone(), Some(&device)).with_auth_token("guess");
+    let caller = start_server_with_peer(server.clone(), Some(&device)).with_auth_token("secret");
+
+    // 没有令牌或令牌错误时调用方未认证
+    let result: Result<CallerInfo> = anonymous.call("CameraService", "capture", ()).await;
+    assert!(matches!(result, Err(Error::Authentication(msg)) if msg.contains("trusted device")));
+    let result: Result<CallerInfo> = forged.call("CameraService", "capture", ()).await;
+    assert!(matches!(result, Err(Error::Authentication(_))));
+
+    // 认证通过但设备未受信任
+    let result: Result<CallerInfo> = caller.call("CameraService", "capture", ()).await;
+    assert!(matches!(result, Err(Error::Authentication(_))));
+
+    auth.trust_device(device.clone()).unwrap();
+    let (peer, qos, keys): CallerInfo = caller.call("CameraService", "capture", ()).await.unwrap();
+    assert_eq!(peer, device.to_string());
+    assert_eq!(qos, Some(QosLevel::Balanced));
+    // 令牌不会暴露给处理器
+    assert_eq!(keys, vec![CODEC_METADATA_KEY.to_string(), DEADLINE_METADATA_KEY.to_string()]);
+}
+
+#[tokio::test]
+async fn test_requests_carry_client_codec() {
+    let (client_end, server_end) = MemoryChannel::pair();
+    let client = RpcClient::new(client_end).with_codec(Codec::Json);
+
+    let call = tokio::spawn(async move { client.call::<_, i32>("MathService", "add_one", 41).await });
+    let request = RpcRequest::decode_bytes(&server_end.recv().await.unwrap()).unwrap();
+    assert_eq!(request.metadata.get(CODEC_METADATA_KEY).map(String::as_str), Some("json"));
+    assert_eq!(request.payload, b"41");
+
+    server_end
+        .send(RpcResponse::ok(request.request_id, Bytes::from_static(b"42")).encode_bytes())
+        .await
+        .unwrap();
+    assert_eq!(call.await.unwrap().unwrap(), 42);
+}
+
+#[tokio::test]
+async fn test_server_answers_in_caller_codec() {
+    let server = RpcServer::new();
+    server.register_method("MathService", "add_one", Arc::new(AddOneHandler));
+    server.register_method("CameraService", "next_frame", Arc::new(NextFrameHandler));
+    server.register_stream_method("CameraService", "preview", Arc::new(CountHandler { sent: Arc::default() }));
+
+    let json = start_server(server.clone()).with_codec(Codec::Json);
+    let result: i32 = json.call("MathService", "add_one", 41).await.unwrap();
+    assert_eq!(result, 42);
+    let mut frames: StreamReceiver<u32> = json.server_stream("CameraService", "preview", 3u32).await.unwrap();
+    let mut received = Vec::new();
+    while let Some(frame) = frames.next().await {
+        received.push(frame.unwrap());
+    }
+    assert_eq!(received, vec![0, 1, 2]);
+
+    let protobuf = start_server(server.clone()).with_codec(Codec::Protobuf);
+    let frame = ImageFrame { sequence: 7, data: vec![1, 2, 3] };
+    let Proto(next): Proto<ImageFrame> = protobuf.call("CameraService", "next_frame", Proto(frame)).await.unwrap();
+    assert_eq!(next, ImageFrame { sequence: 8, data: vec![3, 2, 1] });
+    // 普通serde类型不能按protobuf编码
+    let result: Result<i32> = protobuf.call("MathService", "add_one", 1).await;
+    assert!(matches!(result, Err(Error::Serialization(_))));
+
+    // 服务端不支持的编解码器直接拒绝
+    let (client_end, server_end) = MemoryChannel::pair();
+    tokio::spawn(async move {
+        let _ = server.serve(server_end).await;
+    });
+    let request = RpcRequest {
+        request_id: "req-1".to_string(),
+        service_name: "MathService".to_string(),
+        method_name: "add_one".to_string(),
+        payload: bincode::serialize(&1i32).unwrap(),
+        metadata: [(CODEC_METADATA_KEY.to_string(), "msgpack".to_string())].into(),
+        ..Default::default()
+    };
+    client_end.send(request.encode_bytes()).await.unwrap();
+    let response = RpcResponse::decode_bytes(&client_end.recv().await.unwrap()).unwrap();
+    assert!(matches!(response.into_result(), Err(Error::Serialization(msg)) if msg.contains("msgpack")));
+}
+
+#[tokio::test]
+async fn test_events_use_server_codec() {
+    let server = RpcServer::new().with_codec(Codec::Json);
+    server.register_method("MathService", "add_one", Arc::new(AddOneHandler));
+    let client = start_server(server.clone());
+
+    let mut renamed = client.subscribe::<String>("CameraService", "renamed");
+    let _: i32 = client.call("MathService", "add_one", 0).await.unwrap();
+
+    assert_eq!(server.publish("CameraService", "renamed", "rear").await.unwrap(), 1);
+    assert_eq!(renamed.next().await.unwrap().unwrap(), "rear");
+}
+
+/// 重试间隔很短的策略，避免拖慢测试
+fn fast_retry() -> RetryPolicy {
+    RetryPolicy::default().with_backoff(Duration::from_millis(1), Duration::from_millis(10))
+}
+
+#[tokio::test]
+async fn test_calls_retry_transient_failures() {
+    let server = RpcServer::new();
+    server.register_method("MathService", "add_one", Arc::new(AddOneHandler));
+    server.register_method("CameraService", "capture", Arc::new(BusyHandler));
+    let (client_end, server_end) = MemoryChannel::pair();
+    tokio::spawn(async move {
+        let _ = server.serve(server_end).await;
+    });
+    let link = FlakyChannel::new(client_end);
+    let client = RpcClient::new(link.clone()).with_retry_policy(fast_retry());
+
+    link.fail_next_sends(2);
+    let result: i32 = client.call_idempotent("MathService", "add_one", 41).await.unwrap();
+    assert_eq!(result, 42);
+
+    // 尝试次数用完后返回最后一次的错误
+    link.fail_next_sends(3);
+    let result: Result<i32> = client.call_idempotent("MathService", "add_one", 41).await;
+    assert!(matches!(result, Err(Error::Network(_))));
+
+    // 业务错误不重试
+    link.fail_next_sends(0);
+    let result: Result<Vec<u8>> = client.call("CameraService", "capture", ()).await;
+    assert!(matches!(result, Err(Error::Internal(_))));
+
+    // 非幂等调用默认不重试
+    link.fail_next_sends(1);
+    let result: Result<i32> = client.call("MathService", "add_one", 41).await;
+    assert!(matches!(result, Err(Error::Network(_))));
+}
+
+#[tokio::test]
+async fn test_retried_call_runs_once() {
+    // 服务端只为认证过的调用方去重
+    let device = DeviceId::new();
+    let auth = Arc::new(AuthManager::new());
+    auth.add_credential(Credential {
+        device_id: device.clone(),
+        token: "secret".to_string(),
+        expires_at: chrono::Utc::now().timestamp() + 3600,
+    })
+    .unwrap();
+    let server = RpcServer::new().with_auth_manager(auth);
+    let photos = Arc::new(AtomicU32::new(0));
+    server.register_method("CameraService", "capture", Arc::new(PhotoHandler { photos: Arc::clone(&photos) }));
+    let connect = |token: Option<&str>| {
+        let (client_end, server_end) = MemoryChannel::pair_with_peer(Some(&device));
+        let server_end = FlakyChannel::new(server_end);
+        let link = Arc::clone(&server_end);
+        let server = server.clone();
+        tokio::spawn(async move {
+            let _ = server.serve(server_end).await;
+        });
+        let client = RpcClient::new(client_end)
+            .with_timeout(Duration::from_millis(100))
+            .with_retry_policy(fast_retry())
+            .with_call_retry(true);
+        match token {
+            Some(token) => (client.with_auth_token(token), link),
+            None => (client, link),
+        }
+    };
+    let (client, link) = connect(Some("secret"));
+
+    // 第一次的响应丢失，重试得到同一张照片
+    link.lose_next_sends(1);
+    let photo: u32 = client.call("CameraService", "capture", ()).await.unwrap();
+    assert_eq!(photo, 1);
+    assert_eq!(photos.load(Ordering::SeqCst), 1);
+
+    // 每个调用使用新的幂等键
+    let photo: u32 = client.call("CameraService", "capture", ()).await.unwrap();
+    assert_eq!(photo, 2);
+
+    // 幂等调用不去重，重试时再次执行
+    link.lose_next_sends(1);
+    let photo: u32 = client.call_idempotent("CameraService", "capture", ()).await.unwrap();
+    assert_eq!(photo, 4);
+
+    // 未认证的调用方不去重
+    let (anonymous, link) = connect(None);
+    link.lose_next_sends(1);
+    let photo: u32 = anonymous.call("CameraService", "capture", ()).await.unwrap();
+    assert_eq!(photo, 6);
+}
+
+#[tokio::test]
+async fn test_retried_calls_share_idempotency_key() {
+    let (client_end, server_end) = MemoryChannel::pair();
+    let client = RpcClient::new(client_end)
+        .with_timeout(Duration::from_millis(50))
+        .with_retry_policy(fast_retry().with_max_attempts(2))
+        .with_call_retry(true);
+
+    let call = tokio::spawn(async move { client.call::<_, i32>("MathService", "add_one", 1).await });
+    let first = RpcRequest::decode_bytes(&server_end.recv().await.unwrap()).unwrap();
+    let cancel = RpcRequest::decode_bytes(&server_end.recv().await.unwrap()).unwrap();
+    assert_eq!(cancel.frame_type(), FrameType::Cancel);
+    let second = RpcRequest::decode_bytes(&server_end.recv().await.unwrap()).unwrap();
+
+    let key = first.metadata.get(IDEMPOTENCY_KEY_METADATA_KEY).unwrap();
+    assert_eq!(second.metadata.get(IDEMPOTENCY_KEY_METADATA_KEY), Some(key));
+    assert_ne!(first.request_id, second.request_id);
+    assert!(matches!(call.await.unwrap(), Err(Error::Timeout)));
+}
+
+/// softbus-idl-compiler为示例摄像头服务生成的代码，与快照保持一致
+#[allow(dead_code)]
+mod camera {
+    include!("../../softbus-idl-compiler/tests/snapshots/rust/camera_service_types.rs");
+    include!("../../softbus-idl-compiler/tests/snapshots/rust/camera_service.rs");
+}
+
+struct ListCamerasHandler;
+
+#[async_trait]
+impl MethodHandler for ListCamerasHandler {
+    async fn handle(&self, ctx: &CallContext, _request: Bytes) -> Result<Bytes> {
+        Ok(Bytes::from(ctx.codec().encode(&vec!["rear".to_string()])?))
+    }
+}
+
+#[tokio::test]
+async fn test_idempotent_proxy_methods_retry_by_default() {
+    let server = RpcServer::new();
+    server.register_method(camera::CAMERA_SERVICE_NAME, "list_cameras", Arc::new(ListCamerasHandler));
+    let (client_end, server_end) = MemoryChannel::pair();
+    tokio::spawn(async move {
+        let _ = server.serve(server_end).await;
+    });
+    let link = FlakyChannel::new(client_end);
+    let proxy = camera::CameraServiceProxy::new(Arc::new(RpcClient::new(link.clone())));
+
+    // IDL中标记为idempotent的方法按默认策略重试
+    link.fail_next_sends(2);
+    assert_eq!(proxy.list_cameras().await.unwrap(), vec!["rear".to_string()]);
+
+    // 其他方法默认不重试
+    link.fail_next_sends(1);
+    assert!(matches!(proxy.capture().await, Err(Error::Network(_))));
+}
+
+/// 返回处理器看到的`x-`开头的元数据
+struct HeadersHandler;
+
+#[async_trait]
+impl MethodHandler for HeadersHandler {
+    async fn handle(&self, ctx: &CallContext, _request: Bytes) -> Result<Bytes> {
+        let mut headers: Vec<(String, String)> = ctx
+            .metadata()
+            .iter()
+            .filter(|(key, _)| key.starts_with("x-"))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        headers.sort();
+        Ok(Bytes::from(ctx.codec().encode(&headers)?))
+    }
+}
+
+/// 处理前等待一段时间
+struct SlowHandler;
+
+#[async_trait]
+impl MethodHandler for SlowHandler {
+    async fn handle(&self, ctx: &CallContext, _request: Bytes) -> Result<Bytes> {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        Ok(Bytes::from(ctx.codec().encode(&())?))
+    }
+}
+
+type Log = Arc<parking_lot::Mutex<Vec<String>>>;
+
+/// 为请求加上`x-<name>`头，并记录调用经过的顺序和结果
+struct RecordingInterceptor {
+    name: &'static str,
+    log: Log,
+}
+
+#[async_trait]
+impl Interceptor for RecordingInterceptor {
+    async fn intercept(&self, mut request: RpcRequest, next: Invoker<'_>) -> Result<RpcResponse> {
+        request.metadata.insert(format!("x-{}", self.name), "1".to_string());
+        self.log.lock().push(format!("{} > {}", self.name, request.method_name));
+        let result = next.invoke(request).await;
+        let outcome = match &result {
+            Ok(response) if response.error_code() == RpcErrorCode::Ok => "ok",
+            Ok(_) => "error",
+            Err(_) => "failed",
+        };
+        self.log.lock().push(format!("{} < {}", self.name, outcome));
+        result
+    }
+}
+
+/// 不允许调用`reset`
+struct DenyResetInterceptor;
+
+#[async_trait]
+impl Interceptor for DenyResetInterceptor {
+    async fn intercept(&self, request: RpcRequest, next: Invoker<'_>) -> Result<RpcResponse> {
+        if request.method_name == "reset" {
+            return Err(Error::Authentication("reset is not allowed".to_string()));
+        }
+        next.invoke(request).await
+    }
+}
+
+/// 为每个请求加上固定的头
+struct HeaderInterceptor(&'static str, &'static str);
+
+#[async_trait]
+impl Interceptor for HeaderInterceptor {
+    async fn intercept(&self, mut request: RpcRequest, next: Invoker<'_>) -> Result<RpcResponse> {
+        request.metadata.insert(self.0.to_string(), self.1.to_string());
+        next.invoke(request).await
+    }
+}
+
+/// 要求请求携带API密钥，为处理器补充租户信息，并记录每个调用的结果和耗时
+struct ApiKeyMiddleware {
+    log: Log,
+    latencies: Arc<parking_lot::Mutex<Vec<Duration>>>,
+}
+
+#[async_trait]
+impl Middleware for ApiKeyMiddleware {
+    async fn handle(&self, ctx: &CallContext, mut request: RpcRequest, next: Next<'_>) -> Result<Bytes> {
+        let method = format!("{}.{}", request.service_name, request.method_name);
+        if request.metadata.remove("x-api-key").as_deref() != Some("secret") {
+            self.log.lock().push(format!("{} rejected", method));
+            return Err(Error::Authentication("missing api key".to_string()));
+        }
+        request.metadata.insert("x-tenant".to_string(), "camera-team".to_string());
+
+        let started = Instant::now();
+        let result = next.run(ctx, request).await;
+        self.latencies.lock().push(started.elapsed());
+        let outcome = if result.is_ok() { "ok" } else { "failed" };
+        self.log.lock().push(format!("{} {}", method, outcome));
+        result
+    }
+}
+
+#[tokio::test]
+async fn test_client_interceptors_wrap_calls() {
+    let server = RpcServer::new();
+    server.register_method("DebugService", "headers", Arc::new(HeadersHandler));
+    server.register_method("CameraService", "capture", Arc::new(BusyHandler));
+    let log = Log::default();
+    let client = start_server(server)
+        .with_interceptor(Arc::new(RecordingInterceptor { name: "outer", log: Arc::clone(&log) }))
+        .with_interceptor(Arc::new(RecordingInterceptor { name: "inner", log: Arc::clone(&log) }))
+        .with_interceptor(Arc::new(DenyResetInterceptor));
+
+    let headers: Vec<(String, String)> = client.call("DebugService", "headers", ()).await.unwrap();
+    assert_eq!(headers, [("x-inner".to_string(), "1".to_string()), ("x-outer".to_string(), "1".to_string())]);
+
+    let result: Result<Vec<u8>> = client.call("CameraService", "capture", ()).await;
+    assert!(matches!(result, Err(Error::Internal(_))));
+
+    // 拦截器拒绝的调用不会发往服务端
+    let result: Result<()> = client.call("CameraService", "reset", ()).await;
+    assert!(matches!(result, Err(Error::Authentication(msg)) if msg.contains("not allowed")));
+    assert_eq!(client.in_flight(), 0);
+
+    assert_eq!(
+        *log.lock(),
+        [
+            "outer > headers", "inner > headers", "inner < ok", "outer < ok",
+            "outer > capture", "inner > capture", "inner < error", "outer < error",
+            "outer > reset", "inner > reset", "inner < failed", "outer < failed",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_server_middleware_wraps_handlers() {
+    let log = Log::default();
+    let latencies = Arc::default();
+    let server = RpcServer::new().with_middleware(Arc::new(ApiKeyMiddleware {
+        log: Arc::clone(&log),
+        latencies: Arc::clone(&latencies),
+    }));
+    server.register_method("DebugService", "headers", Arc::new(HeadersHandler));
+    server.register_method("DebugService", "slow", Arc::new(SlowHandler));
+    let anonymous = start_server(server.clone());
+    let client = start_server(server).with_interceptor(Arc::new(HeaderInterceptor("x-api-key", "secret")));
+
+    let result: Result<Vec<(String, String)>> = anonymous.call("DebugService", "headers", ()).await;
+    assert!(matches!(result, Err(Error::Authentication(_))));
+
+    // 处理器看到中间件修改后的元数据
+    let headers: Vec<(String, String)> = client.call("DebugService", "headers", ()).await.unwrap();
+    assert_eq!(headers, [("x-tenant".to_string(), "camera-team".to_string())]);
+
+    client.call::<_, ()>("DebugService", "slow", ()).await.unwrap();
+    let result: Result<()> = client.call("DebugService", "missing", ()).await;
+    assert!(matches!(result, Err(Error::MethodNotFound(_))));
+
+    assert_eq!(
+        *log.lock(),
+        [
+            "DebugService.headers rejected",
+            "DebugService.headers ok",
+            "DebugService.slow ok",
+            "DebugService.missing failed",
+        ]
+    );
+    assert!(latencies.lock()[1] >= Duration::from_millis(20));
+}
+
+#[tokio::test]
+async fn test_rpc_over_encrypted_channel() {
+    let key = [42u8; 32];
+    let server = RpcServer::new();
+    server.register_method("MathService", "add_one", Arc::new(AddOneHandler));
+
+    // 客户端 <-> 线路 <-> 服务端，线路上转发的帧中不应出现明文
+    let (client_end, wire_client) = MemoryChannel::pair();
+    let (wire_server, server_end) = MemoryChannel::pair();
+    let server_end = EncryptedChannel::new(server_end, &key, SessionRole::Responder).unwrap();
+    tokio::spawn(async move {
+        let _ = server.serve(Arc::new(server_end)).await;
+    });
+    let relay = |from: Arc<MemoryChannel>, to: Arc<MemoryChannel>| async move {
+        while let Ok(frame) = from.recv().await {
+            assert!(!frame.windows(b"MathService".len()).any(|w| w == b"MathService"));
+            to.send(frame).await.unwrap();
+        }
+    };
+    tokio::spawn(relay(Arc::clone(&wire_client), Arc::clone(&wire_server)));
+    tokio::spawn(relay(wire_server, wire_client));
+
+    let client_end = EncryptedChannel::new(client_end, &key, SessionRole::Initiator)
+        .unwrap()
+        .with_rekey_threshold(2, 1 << 20);
+    let client = RpcClient::new(Arc::new(client_end));
+    for i in 0..5 {
+        let result: i32 = client.call("MathService", "add_one", i).await.unwrap();
+        assert_eq!(result, i + 1);
+    }
+}
+
+#[tokio::test]
+async fn test_handlers_see_handshake_verified_peer() {
+    let phone = Arc::new(DeviceIdentity::generate().unwrap());
+    let camera = Arc::new(DeviceIdentity::generate().unwrap());
+    let auth = Arc::new(AuthManager::new());
+    let server = RpcServer::new();
+    server.register_method("CameraService", "capture", Arc::new(TrustedCaptureHandler { auth: Arc::clone(&auth) }));
+
+    // 服务端一侧的通道自称对端是另一台受信任的设备，握手验证的身份优先
+    let impostor = DeviceId::new();
+    auth.trust_device(impostor.clone()).unwrap();
+    let (client_end, server_end) = MemoryChannel::pair_with_peer(Some(&impostor));
+    let (initiator, responder) = (Handshake::new(Arc::clone(&phone)), Handshake::new(camera));
+    let (client_session, server_session) =
+        tokio::join!(initiator.initiate(&*client_end), responder.respond(&*server_end));
+    let server_end = server_session.unwrap().into_channel(server_end).unwrap();
+    tokio::spawn(async move {
+        let _ = server.serve(Arc::new(server_end)).await;
+    });
+    let client = RpcClient::new(Arc::new(client_session.unwrap().into_channel(client_end).unwrap()));
+
+    type CallerInfo = (String, Option<QosLevel>, Vec<String>);
+    let result: Result<CallerInfo> = client.call("CameraService", "capture", ()).await;
+    assert!(matches!(result, Err(Error::Authentication(_))));
+
+    // 信任验证过的身份后无需令牌
+    auth.trust_device(phone.device_id().clone()).unwrap();
+    let (peer, _, _): CallerInfo = client.call("CameraService", "capture", ()).await.unwrap();
+    assert_eq!(peer, phone.device_id().to_string());
+}
+
+#[tokio::test]
+async fn test_signed_tokens_and_revocation_sync() {
+    // 摄像头签发令牌，平板信任摄像头的公钥并从摄像头同步吊销列表
+    let camera = Arc::new(DeviceIdentity::generate().unwrap());
+    let camera_auth = Arc::new(AuthManager::new().with_identity(Arc::clone(&camera)));
+    let tablet_auth =
+        Arc::new(AuthManager::new().with_token_issuer(camera.device_id().clone(), camera.public_key().to_vec()));
+
+    let phone = Arc::new(DeviceIdentity::generate().unwrap());
+    let ttl = Duration::from_secs(600);
+    let (first, second) = (
+        camera_auth.issue_token(phone.device_id(), &["camera.capture"], ttl).unwrap(),
+        camera_auth.issue_token(phone.device_id(), &["camera.capture"], ttl).unwrap(),
+    );
+
+    // 签名令牌用于RPC认证，令牌必须签发给握手验证的对端
+    let server = RpcServer::new().with_auth_manager(Arc::clone(&camera_auth));
+    server.register_method("CameraService", "capture", Arc::new(TrustedCaptureHandler { auth: Arc::clone(&camera_auth) }));
+    server.register_service(&RevocationService::new(Arc::clone(&camera_auth)));
+    camera_auth.trust_device(phone.device_id().clone()).unwrap();
+    let caller = start_server_with_handshake(server.clone(), Arc::clone(&phone), Arc::clone(&camera))
+        .await
+        .with_auth_token(&first);
+    type CallerInfo = (String, Option<QosLevel>, Vec<String>);
+    let (peer, _, _): CallerInfo = caller.call("CameraService", "capture", ()).await.unwrap();
+    assert_eq!(peer, phone.device_id().to_string());
+
+    // 对端只是自称为令牌持有者，或握手验证的是另一台设备时不接受令牌
+    let claimed = start_server_with_peer(server.clone(), Some(phone.device_id())).with_auth_token(&first);
+    let result: Result<CallerInfo> = claimed.call("CameraService", "capture", ()).await;
+    assert!(matches!(result, Err(Error::Authentication(_))));
+    let laptop = Arc::new(DeviceIdentity::generate().unwrap());
+    camera_auth.trust_device(laptop.device_id().clone()).unwrap();
+    let stolen = start_server_with_handshake(server.clone(), laptop, Arc::clone(&camera))
+        .await
+        .with_auth_token(&first);
+    let result: Result<CallerInfo> = stolen.call("CameraService", "capture", ()).await;
+    assert!(matches!(result, Err(Error::Authentication(_))));
+
+    // 连接前的吊销通过拉取列表同步，之后的吊销通过推送同步
+    camera_auth.revoke_token(&first).unwrap();
+    let tablet = start_server(server.clone());
+    let follower = RevocationService::follow(&tablet, Arc::clone(&tablet_auth)).await.unwrap();
+    assert!(tablet_auth.verify_token(&first).is_err());
+    assert!(tablet_auth.verify_token(&second).is_ok());
+
+    let revocation = camera_auth.revoke_token(&second).unwrap();
+    assert!(RevocationService::publish(&server, &revocation).await.unwrap() >= 1);
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while tablet_auth.verify_token(&second).is_ok() {
+        assert!(Instant::now() < deadline, "revocation was not propagated");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(tablet_auth.revocations().len(), 2);
+
+    // 吊销后令牌不能再用于认证
+    let result: Result<CallerInfo> = caller.call("CameraService", "capture", ()).await;
+    assert!(matches!(result, Err(Error::Authentication(_))));
+    follower.abort();
+}
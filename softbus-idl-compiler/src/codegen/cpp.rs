@@ -1,15 +1,102 @@
 //! C++代码生成器
+//!
+//! 为每个服务生成一对`.h`/`.cpp`：纯虚服务接口和基于`RpcClient`的同步客户端
+//! 代理，为IDL中的struct/enum生成头文件及其bincode编解码。代理把参数按bincode
+//! 编码为`RpcRequest`载荷，与Rust端生成的服务端桩兼容；帧编解码和调用复用的
+//! 运行时写入`softbus_rpc.h`/`softbus_rpc.cpp`。
 
-use crate::parser::ServiceDef;
-use crate::codegen::Codegen;
+use crate::parser::{IdlFile, ServiceDef, StructDef, TypeDef, UserType};
+use crate::codegen::{ensure_supported, Codegen, Language};
+use crate::codegen::naming::{to_pascal_case, to_snake_case, to_upper_snake_case};
+use handlebars::Handlebars;
+use serde::Serialize;
 use std::path::Path;
 
+const HEADER_TEMPLATE: &str = include_str!("templates/cpp.hbs");
+const SOURCE_TEMPLATE: &str = include_str!("templates/cpp_source.hbs");
+const TYPES_TEMPLATE: &str = include_str!("templates/cpp_types.hbs");
+const RUNTIME_HEADER: &str = include_str!("templates/softbus_rpc.h");
+const RUNTIME_SOURCE: &str = include_str!("templates/softbus_rpc.cpp");
+
+/// RPC运行时头文件名
+pub const RUNTIME_HEADER_NAME: &str = "softbus_rpc.h";
+
+/// RPC运行时源文件名
+pub const RUNTIME_SOURCE_NAME: &str = "softbus_rpc.cpp";
+
 /// C++代码生成器
-pub struct CppCodegen;
+pub struct CppCodegen {
+    registry: Handlebars<'static>,
+}
 
 impl CppCodegen {
     pub fn new() -> Self {
-        Self
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(true);
+        registry.register_escape_fn(handlebars::no_escape);
+        registry
+            .register_template_string("header", HEADER_TEMPLATE)
+            .expect("built-in C++ header template is valid");
+        registry
+            .register_template_string("source", SOURCE_TEMPLATE)
+            .expect("built-in C++ source template is valid");
+        registry
+            .register_template_string("types", TYPES_TEMPLATE)
+            .expect("built-in C++ types template is valid");
+
+        Self { registry }
+    }
+
+    /// 生成服务接口和客户端代理的头文件
+    ///
+    /// `types_header`为同一IDL文件的类型头文件名，服务引用了struct/enum时需要。
+    pub fn render_header(&self, service: &ServiceDef, types_header: Option<&str>) -> anyhow::Result<String> {
+        ensure_supported(service, Language::Cpp)?;
+        let view = ServiceView::new(service, types_header);
+        Ok(self.registry.render("header", &view)?)
+    }
+
+    /// 生成客户端代理的实现
+    pub fn render_source(&self, service: &ServiceDef) -> anyhow::Result<String> {
+        ensure_supported(service, Language::Cpp)?;
+        let view = ServiceView::new(service, None);
+        Ok(self.registry.render("source", &view)?)
+    }
+
+    /// 生成IDL文件中struct/enum声明的C++头文件
+    pub fn render_types(&self, file: &IdlFile, module: &str) -> anyhow::Result<String> {
+        let view = TypesView::new(file, module);
+        Ok(self.registry.render("types", &view)?)
+    }
+
+    /// 服务对应的头文件名
+    pub fn header_file_name(service: &ServiceDef) -> String {
+        format!("{}.h", to_snake_case(&service.name))
+    }
+
+    /// 服务对应的源文件名
+    pub fn source_file_name(service: &ServiceDef) -> String {
+        format!("{}.cpp", to_snake_case(&service.name))
+    }
+
+    /// 类型声明对应的头文件名
+    pub fn types_file_name(module: &str) -> String {
+        format!("{}_types.h", to_snake_case(module))
+    }
+
+    fn generate_service(&self, service: &ServiceDef, types_header: Option<&str>, output_dir: &Path) -> anyhow::Result<()> {
+        let header = self.render_header(service, types_header)?;
+        let source = self.render_source(service)?;
+        std::fs::create_dir_all(output_dir)?;
+        std::fs::write(output_dir.join(Self::header_file_name(service)), header)?;
+        std::fs::write(output_dir.join(Self::source_file_name(service)), source)?;
+        self.write_runtime(output_dir)
+    }
+
+    fn write_runtime(&self, output_dir: &Path) -> anyhow::Result<()> {
+        std::fs::write(output_dir.join(RUNTIME_HEADER_NAME), RUNTIME_HEADER)?;
+        std::fs::write(output_dir.join(RUNTIME_SOURCE_NAME), RUNTIME_SOURCE)?;
+        Ok(())
     }
 }
 
@@ -21,9 +108,354 @@ impl Default for CppCodegen {
 
 impl Codegen for CppCodegen {
     fn generate(&self, service: &ServiceDef, output_dir: &Path) -> anyhow::Result<()> {
-        // TODO: 实现C++代码生成
-        println!("Generating C++ code for service: {}", service.name);
-        println!("Output directory: {:?}", output_dir);
+        self.generate_service(service, None, output_dir)
+    }
+
+    fn generate_types(&self, file: &IdlFile, module: &str, output_dir: &Path) -> anyhow::Result<()> {
+        let code = self.render_types(file, module)?;
+        std::fs::create_dir_all(output_dir)?;
+        std::fs::write(output_dir.join(Self::types_file_name(module)), code)?;
+        self.write_runtime(output_dir)
+    }
+
+    /// 服务头文件需要包含同一IDL文件的类型头文件
+    fn generate_file(&self, file: &IdlFile, module: &str, output_dir: &Path) -> anyhow::Result<()> {
+        let types_header = file.has_types().then(|| Self::types_file_name(module));
+        if file.has_types() {
+            self.generate_types(file, module, output_dir)?;
+        }
+        for service in &file.services {
+            self.generate_service(service, types_header.as_deref(), output_dir)?;
+        }
         Ok(())
     }
 }
+
+/// 模板使用的服务视图
+#[derive(Serialize)]
+struct ServiceView {
+    name: String,
+    guard: String,
+    header: String,
+    /// 类型头文件名，为空时不包含
+    types_header: String,
+    interface: String,
+    proxy: String,
+    methods: Vec<MethodView>,
+}
+
+#[derive(Serialize)]
+struct MethodView {
+    /// 线路上使用的原始方法名
+    name: String,
+    fn_name: String,
+    params_decl: String,
+    return_type: String,
+    /// 依次写入请求载荷的语句
+    writes: Vec<String>,
+    /// 读取返回值的表达式，void方法为空
+    read: String,
+    /// 单向方法只发送请求，不等待响应
+    oneway: bool,
+}
+
+impl ServiceView {
+    fn new(service: &ServiceDef, types_header: Option<&str>) -> Self {
+        let pascal = to_pascal_case(&service.name);
+        let methods = service
+            .methods
+            .iter()
+            .map(|method| {
+                let params_decl = method
+                    .params
+                    .iter()
+                    .map(|p| format!("{} {}", param_type(&p.param_type), to_snake_case(&p.name)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let writes = method
+                    .params
+                    .iter()
+                    .map(|p| write_expr(&p.param_type, "writer", &to_snake_case(&p.name), 0))
+                    .collect();
+
+                MethodView {
+                    name: method.name.clone(),
+                    fn_name: to_snake_case(&method.name),
+                    params_decl,
+                    return_type: method.return_type.to_cpp_type(),
+                    writes,
+                    read: read_expr(&method.return_type, "reader", 0),
+                    oneway: method.oneway,
+                }
+            })
+            .collect();
+
+        Self {
+            name: service.name.clone(),
+            guard: format!("SOFTBUS_GENERATED_{}_H", to_upper_snake_case(&service.name)),
+            header: CppCodegen::header_file_name(service),
+            types_header: types_header.unwrap_or_default().to_string(),
+            interface: pascal.clone(),
+            proxy: format!("{}Proxy", pascal),
+            methods,
+        }
+    }
+}
+
+/// 参数类型，标量按值传递，其余按常量引用传递
+fn param_type(type_def: &TypeDef) -> String {
+    match type_def {
+        TypeDef::Bool
+        | TypeDef::I32
+        | TypeDef::I64
+        | TypeDef::U32
+        | TypeDef::U64
+        | TypeDef::F32
+        | TypeDef::F64 => type_def.to_cpp_type(),
+        _ => format!("const {}&", type_def.to_cpp_type()),
+    }
+}
+
+/// 生成把`value`按bincode写入`writer`的表达式
+///
+/// `depth`用于为嵌套的lambda参数取不重复的名字。
+fn write_expr(type_def: &TypeDef, writer: &str, value: &str, depth: usize) -> String {
+    let primitive = |method: &str| format!("{}.{}({})", writer, method, value);
+    let lambda = |inner: &TypeDef| {
+        let (w, v) = (format!("w{}", depth + 1), format!("v{}", depth + 1));
+        format!(
+            "[](BincodeWriter& {}, const auto& {}) {{ {}; }}",
+            w,
+            v,
+            write_expr(inner, &w, &v, depth + 1)
+        )
+    };
+
+    match type_def {
+        TypeDef::Void => String::new(),
+        TypeDef::Bool => primitive("write_bool"),
+        TypeDef::I32 => primitive("write_i32"),
+        TypeDef::I64 => primitive("write_i64"),
+        TypeDef::U32 => primitive("write_u32"),
+        TypeDef::U64 => primitive("write_u64"),
+        TypeDef::F32 => primitive("write_f32"),
+        TypeDef::F64 => primitive("write_f64"),
+        TypeDef::String => primitive("write_string"),
+        TypeDef::Bytes => primitive("write_bytes"),
+        TypeDef::Custom(name) => format!("{}Codec::encode({}, {})", name, writer, value),
+        TypeDef::Array(inner) => format!("{}.write_list({}, {})", writer, value, lambda(inner)),
+        TypeDef::Map(k, v) => format!("{}.write_map({}, {}, {})", writer, value, lambda(k), lambda(v)),
+        TypeDef::Stream(_) => unreachable!("streaming methods are rejected before rendering"),
+    }
+}
+
+/// 生成从`reader`按bincode读取一个值的表达式
+fn read_expr(type_def: &TypeDef, reader: &str, depth: usize) -> String {
+    let primitive = |method: &str| format!("{}.{}()", reader, method);
+    let lambda = |inner: &TypeDef| {
+        let r = format!("r{}", depth + 1);
+        format!("[](BincodeReader& {}) {{ return {}; }}", r, read_expr(inner, &r, depth + 1))
+    };
+
+    match type_def {
+        TypeDef::Void => String::new(),
+        TypeDef::Bool => primitive("read_bool"),
+        TypeDef::I32 => primitive("read_i32"),
+        TypeDef::I64 => primitive("read_i64"),
+        TypeDef::U32 => primitive("read_u32"),
+        TypeDef::U64 => primitive("read_u64"),
+        TypeDef::F32 => primitive("read_f32"),
+        TypeDef::F64 => primitive("read_f64"),
+        TypeDef::String => primitive("read_string"),
+        TypeDef::Bytes => primitive("read_bytes"),
+        TypeDef::Custom(name) => format!("{}Codec::decode({})", name, reader),
+        TypeDef::Array(inner) => format!("{}.read_list({})", reader, lambda(inner)),
+        TypeDef::Map(k, v) => format!("{}.read_map({}, {})", reader, lambda(k), lambda(v)),
+        TypeDef::Stream(_) => unreachable!("streaming methods are rejected before rendering"),
+    }
+}
+
+/// 模板使用的类型声明视图
+#[derive(Serialize)]
+struct TypesView {
+    module: String,
+    guard: String,
+    decls: Vec<DeclView>,
+}
+
+#[derive(Serialize)]
+struct DeclView {
+    name: String,
+    is_enum: bool,
+    variants: Vec<VariantView>,
+    fields: Vec<FieldView>,
+}
+
+#[derive(Serialize)]
+struct VariantView {
+    name: String,
+    /// 成员声明，如`Rgb = 1`
+    decl: String,
+    /// 成员序号，bincode按序号而不是成员值编码枚举
+    index: usize,
+}
+
+#[derive(Serialize)]
+struct FieldView {
+    name: String,
+    cpp_type: String,
+    /// 标量字段值初始化，避免未初始化读取
+    initializer: &'static str,
+    write: String,
+    read: String,
+}
+
+impl TypesView {
+    fn new(file: &IdlFile, module: &str) -> Self {
+        let enums = file.enums.iter().map(|def| DeclView {
+            name: def.name.clone(),
+            is_enum: true,
+            variants: def
+                .variants
+                .iter()
+                .enumerate()
+                .map(|(index, variant)| VariantView {
+                    name: variant.name.clone(),
+                    decl: match variant.value {
+                        Some(value) => format!("{} = {}", variant.name, value),
+                        None => variant.name.clone(),
+                    },
+                    index,
+                })
+                .collect(),
+            fields: Vec::new(),
+        });
+
+        let structs = sorted_structs(file).into_iter().map(|def| DeclView {
+            name: def.name.clone(),
+            is_enum: false,
+            variants: Vec::new(),
+            fields: def
+                .fields
+                .iter()
+                .map(|field| FieldView {
+                    name: field.name.clone(),
+                    cpp_type: field.field_type.to_cpp_type(),
+                    initializer: initializer(file, &field.field_type),
+                    write: write_expr(&field.field_type, "writer", &format!("value.{}", field.name), 0),
+                    read: read_expr(&field.field_type, "reader", 0),
+                })
+                .collect(),
+        });
+
+        Self {
+            module: module.to_string(),
+            guard: format!("SOFTBUS_GENERATED_{}_TYPES_H", to_upper_snake_case(module)),
+            decls: enums.chain(structs).collect(),
+        }
+    }
+}
+
+/// 按依赖顺序排列结构体，被引用的结构体先定义
+///
+/// 存在循环引用时保留声明顺序，由C++编译器报告错误。
+fn sorted_structs(file: &IdlFile) -> Vec<&StructDef> {
+    fn visit<'a>(file: &'a IdlFile, def: &'a StructDef, visiting: &mut Vec<&'a str>, sorted: &mut Vec<&'a StructDef>) {
+        if sorted.iter().any(|s| s.name == def.name) || visiting.contains(&def.name.as_str()) {
+            return;
+        }
+        visiting.push(&def.name);
+        for field in &def.fields {
+            for name in field.field_type.custom_names() {
+                if let Some(UserType::Struct(dep)) = file.resolve(name) {
+                    visit(file, dep, visiting, sorted);
+                }
+            }
+        }
+        visiting.pop();
+        sorted.push(def);
+    }
+
+    let mut sorted = Vec::with_capacity(file.structs.len());
+    for def in &file.structs {
+        visit(file, def, &mut Vec::new(), &mut sorted);
+    }
+    sorted
+}
+
+fn initializer(file: &IdlFile, type_def: &TypeDef) -> &'static str {
+    match type_def {
+        TypeDef::Bool
+        | TypeDef::I32
+        | TypeDef::I64
+        | TypeDef::U32
+        | TypeDef::U64
+        | TypeDef::F32
+        | TypeDef::F64 => "{}",
+        TypeDef::Custom(name) => match file.resolve(name) {
+            Some(UserType::Enum(_)) => "{}",
+            _ => "",
+        },
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn test_render_types() {
+        let source = include_str!("../../../examples/camera-service/camera_service.idl");
+        let file = parse(source).unwrap();
+        let code = CppCodegen::new().render_types(&file, "camera_service").unwrap();
+
+        assert!(code.contains("enum class PixelFormat : int32_t {\n    Rgb888 = 0,\n    Yuv420,\n"));
+        assert!(code.contains("struct CameraInfo {\n    int32_t id{};\n    std::string name;\n    Resolution resolution;\n    std::vector<PixelFormat> formats;\n};"));
+        assert!(code.contains("            case PixelFormat::Jpeg:\n                writer.write_u32(3);\n"));
+        assert!(code.contains("        writer.write_list(value.formats, [](BincodeWriter& w1, const auto& v1) { PixelFormatCodec::encode(w1, v1); });\n"));
+        assert!(code.contains("        value.resolution = ResolutionCodec::decode(reader);\n"));
+        assert!(code.contains("#ifndef SOFTBUS_GENERATED_CAMERA_SERVICE_TYPES_H\n"));
+        assert_eq!(CppCodegen::types_file_name("camera_service"), "camera_service_types.h");
+    }
+
+    #[test]
+    fn test_render_service() {
+        let file = parse(
+            "service Store {\n\
+             \x20   get(key: string, ids: array<u64>) -> map<string, array<bytes>>;\n\
+             \x20   clear_all(force: bool);\n\
+             }",
+        )
+        .unwrap();
+        let codegen = CppCodegen::new();
+        let service = &file.services[0];
+        let header = codegen.render_header(service, Some("store_types.h")).unwrap();
+        let source = codegen.render_source(service).unwrap();
+
+        assert!(header.contains("#include \"store_types.h\"\n"));
+        assert!(header.contains(
+            "    virtual std::unordered_map<std::string, std::vector<std::vector<uint8_t>>> get(const std::string& key, const std::vector<uint64_t>& ids) = 0;\n"
+        ));
+        assert!(header.contains("    void clear_all(bool force) override;\n"));
+        assert!(header.contains("class StoreProxy : public Store {\n"));
+        assert!(source.contains("    writer.write_list(ids, [](BincodeWriter& w1, const auto& v1) { w1.write_u64(v1); });\n"));
+        assert!(source.contains(
+            "    return reader.read_map([](BincodeReader& r1) { return r1.read_string(); }, [](BincodeReader& r1) { return r1.read_list([](BincodeReader& r2) { return r2.read_bytes(); }); });\n"
+        ));
+        assert!(source.contains("    client_->call(kServiceName, \"clear_all\", writer.take());\n"));
+        assert_eq!(CppCodegen::header_file_name(service), "store.h");
+        assert_eq!(CppCodegen::source_file_name(service), "store.cpp");
+
+        let standalone = codegen.render_header(service, None).unwrap();
+        assert!(!standalone.contains("_types.h"));
+    }
+
+    #[test]
+    fn test_structs_sorted_by_dependency() {
+        let file = parse("struct A { b: array<B>, c: C } struct B { c: map<string, C> } struct C { x: i32 }").unwrap();
+        let names: Vec<&str> = sorted_structs(&file).iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["C", "B", "A"]);
+    }
+}
@@ -1,15 +1,81 @@
 //! C#代码生成器
+//!
+//! 为每个服务生成服务接口和基于`RpcClient`的异步客户端代理，为IDL中的
+//! struct/enum生成DTO类及其bincode编解码类。代理把参数按bincode编码为
+//! `RpcRequest`载荷，与Rust端生成的服务端桩兼容；所需的帧编解码和调用复用
+//! 代码写入`SoftBusRpc.cs`。
 
-use crate::parser::ServiceDef;
-use crate::codegen::Codegen;
+use crate::parser::{IdlFile, ServiceDef, TypeDef, UserType};
+use crate::codegen::{ensure_supported, Codegen, Language};
+use crate::codegen::naming::{to_camel_case, to_pascal_case};
+use handlebars::Handlebars;
+use serde::Serialize;
 use std::path::Path;
 
+const TEMPLATE: &str = include_str!("templates/csharp.hbs");
+const TYPES_TEMPLATE: &str = include_str!("templates/csharp_types.hbs");
+const RUNTIME_TEMPLATE: &str = include_str!("templates/csharp_runtime.hbs");
+
+/// 生成代码所在的命名空间
+pub const NAMESPACE: &str = "SoftBus.Generated";
+
+/// RPC运行时文件名
+pub const RUNTIME_FILE_NAME: &str = "SoftBusRpc.cs";
+
 /// C#代码生成器
-pub struct CSharpCodegen;
+pub struct CSharpCodegen {
+    registry: Handlebars<'static>,
+}
 
 impl CSharpCodegen {
     pub fn new() -> Self {
-        Self
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(true);
+        registry.register_escape_fn(handlebars::no_escape);
+        registry
+            .register_template_string("service", TEMPLATE)
+            .expect("built-in C# template is valid");
+        registry
+            .register_template_string("types", TYPES_TEMPLATE)
+            .expect("built-in C# types template is valid");
+        registry
+            .register_template_string("runtime", RUNTIME_TEMPLATE)
+            .expect("built-in C# runtime template is valid");
+
+        Self { registry }
+    }
+
+    /// 生成服务接口和客户端代理的C#源码
+    pub fn render(&self, service: &ServiceDef) -> anyhow::Result<String> {
+        ensure_supported(service, Language::CSharp)?;
+        let view = ServiceView::new(service);
+        Ok(self.registry.render("service", &view)?)
+    }
+
+    /// 生成IDL文件中struct/enum声明的C#源码
+    pub fn render_types(&self, file: &IdlFile, module: &str) -> anyhow::Result<String> {
+        let view = TypesView::new(file, module);
+        Ok(self.registry.render("types", &view)?)
+    }
+
+    /// 生成RPC运行时的C#源码
+    pub fn render_runtime(&self) -> anyhow::Result<String> {
+        Ok(self.registry.render("runtime", &serde_json::json!({ "namespace": NAMESPACE }))?)
+    }
+
+    /// 服务对应的输出文件名
+    pub fn file_name(service: &ServiceDef) -> String {
+        format!("{}.cs", to_pascal_case(&service.name))
+    }
+
+    /// 类型声明对应的输出文件名
+    pub fn types_file_name(module: &str) -> String {
+        format!("{}Types.cs", to_pascal_case(module))
+    }
+
+    fn write_runtime(&self, output_dir: &Path) -> anyhow::Result<()> {
+        std::fs::write(output_dir.join(RUNTIME_FILE_NAME), self.render_runtime()?)?;
+        Ok(())
     }
 }
 
@@ -21,9 +87,298 @@ impl Default for CSharpCodegen {
 
 impl Codegen for CSharpCodegen {
     fn generate(&self, service: &ServiceDef, output_dir: &Path) -> anyhow::Result<()> {
-        // TODO: 实现C#代码生成
-        println!("Generating C# code for service: {}", service.name);
-        println!("Output directory: {:?}", output_dir);
-        Ok(())
+        let code = self.render(service)?;
+        std::fs::create_dir_all(output_dir)?;
+        std::fs::write(output_dir.join(Self::file_name(service)), code)?;
+        self.write_runtime(output_dir)
+    }
+
+    fn generate_types(&self, file: &IdlFile, module: &str, output_dir: &Path) -> anyhow::Result<()> {
+        let code = self.render_types(file, module)?;
+        std::fs::create_dir_all(output_dir)?;
+        std::fs::write(output_dir.join(Self::types_file_name(module)), code)?;
+        self.write_runtime(output_dir)
+    }
+}
+
+/// 模板使用的服务视图
+#[derive(Serialize)]
+struct ServiceView {
+    name: String,
+    namespace: &'static str,
+    interface: String,
+    proxy: String,
+    methods: Vec<MethodView>,
+}
+
+#[derive(Serialize)]
+struct MethodView {
+    /// 线路上使用的原始方法名
+    name: String,
+    fn_name: String,
+    /// 参数声明，非空时以`, `结尾，后接CancellationToken
+    params_decl: String,
+    return_type: String,
+    /// 依次写入请求载荷的语句
+    writes: Vec<String>,
+    /// 读取返回值的表达式，void方法为空
+    read: String,
+    /// 单向方法只发送请求，不等待响应
+    oneway: bool,
+}
+
+impl ServiceView {
+    fn new(service: &ServiceDef) -> Self {
+        let pascal = to_pascal_case(&service.name);
+        let methods = service
+            .methods
+            .iter()
+            .map(|method| {
+                let params_decl = method
+                    .params
+                    .iter()
+                    .map(|p| format!("{} {}, ", p.param_type.to_csharp_type(), to_camel_case(&p.name)))
+                    .collect();
+                let writes = method
+                    .params
+                    .iter()
+                    .map(|p| write_expr(&p.param_type, "writer", &to_camel_case(&p.name), 0))
+                    .collect();
+                let (return_type, read) = match method.return_type {
+                    TypeDef::Void => ("Task".to_string(), String::new()),
+                    ref ty => (
+                        format!("Task<{}>", ty.to_csharp_type()),
+                        read_expr(ty, "reader", 0),
+                    ),
+                };
+
+                MethodView {
+                    name: method.name.clone(),
+                    fn_name: format!("{}Async", to_pascal_case(&method.name)),
+                    params_decl,
+                    return_type,
+                    writes,
+                    read,
+                    oneway: method.oneway,
+                }
+            })
+            .collect();
+
+        Self {
+            name: service.name.clone(),
+            namespace: NAMESPACE,
+            interface: format!("I{}", pascal),
+            proxy: format!("{}Proxy", pascal),
+            methods,
+        }
+    }
+}
+
+/// 生成把`value`按bincode写入`writer`的表达式
+///
+/// `depth`用于为嵌套的lambda参数取不重复的名字。
+fn write_expr(type_def: &TypeDef, writer: &str, value: &str, depth: usize) -> String {
+    let primitive = |method: &str| format!("{}.{}({})", writer, method, value);
+    let lambda = |inner: &TypeDef| {
+        let (w, v) = (format!("w{}", depth + 1), format!("v{}", depth + 1));
+        format!("({}, {}) => {}", w, v, write_expr(inner, &w, &v, depth + 1))
+    };
+
+    match type_def {
+        TypeDef::Void => String::new(),
+        TypeDef::Bool => primitive("WriteBool"),
+        TypeDef::I32 => primitive("WriteI32"),
+        TypeDef::I64 => primitive("WriteI64"),
+        TypeDef::U32 => primitive("WriteU32"),
+        TypeDef::U64 => primitive("WriteU64"),
+        TypeDef::F32 => primitive("WriteF32"),
+        TypeDef::F64 => primitive("WriteF64"),
+        TypeDef::String => primitive("WriteString"),
+        TypeDef::Bytes => primitive("WriteBytes"),
+        TypeDef::Custom(name) => format!("{}Codec.Encode({}, {})", name, writer, value),
+        TypeDef::Array(inner) => format!("{}.WriteList({}, {})", writer, value, lambda(inner)),
+        TypeDef::Map(k, v) => format!("{}.WriteMap({}, {}, {})", writer, value, lambda(k), lambda(v)),
+        TypeDef::Stream(_) => unreachable!("streaming methods are rejected before rendering"),
+    }
+}
+
+/// 生成从`reader`按bincode读取一个值的表达式
+fn read_expr(type_def: &TypeDef, reader: &str, depth: usize) -> String {
+    let primitive = |method: &str| format!("{}.{}()", reader, method);
+    let lambda = |inner: &TypeDef| {
+        let r = format!("r{}", depth + 1);
+        format!("{} => {}", r, read_expr(inner, &r, depth + 1))
+    };
+
+    match type_def {
+        TypeDef::Void => String::new(),
+        TypeDef::Bool => primitive("ReadBool"),
+        TypeDef::I32 => primitive("ReadI32"),
+        TypeDef::I64 => primitive("ReadI64"),
+        TypeDef::U32 => primitive("ReadU32"),
+        TypeDef::U64 => primitive("ReadU64"),
+        TypeDef::F32 => primitive("ReadF32"),
+        TypeDef::F64 => primitive("ReadF64"),
+        TypeDef::String => primitive("ReadString"),
+        TypeDef::Bytes => primitive("ReadBytes"),
+        TypeDef::Custom(name) => format!("{}Codec.Decode({})", name, reader),
+        TypeDef::Array(inner) => format!("{}.ReadList({})", reader, lambda(inner)),
+        TypeDef::Map(k, v) => format!("{}.ReadMap({}, {})", reader, lambda(k), lambda(v)),
+        TypeDef::Stream(_) => unreachable!("streaming methods are rejected before rendering"),
+    }
+}
+
+/// 模板使用的类型声明视图
+#[derive(Serialize)]
+struct TypesView {
+    module: String,
+    namespace: &'static str,
+    decls: Vec<DeclView>,
+}
+
+#[derive(Serialize)]
+struct DeclView {
+    name: String,
+    is_enum: bool,
+    variants: Vec<VariantView>,
+    fields: Vec<FieldView>,
+}
+
+#[derive(Serialize)]
+struct VariantView {
+    name: String,
+    /// 成员声明，如`Rgb = 1`
+    decl: String,
+    /// 成员序号，bincode按序号而不是成员值编码枚举
+    index: usize,
+}
+
+#[derive(Serialize)]
+struct FieldView {
+    property: String,
+    csharp_type: String,
+    /// 引用类型的默认值，避免出现null
+    initializer: String,
+    write: String,
+    read: String,
+}
+
+impl TypesView {
+    fn new(file: &IdlFile, module: &str) -> Self {
+        let enums = file.enums.iter().map(|def| DeclView {
+            name: def.name.clone(),
+            is_enum: true,
+            variants: def
+                .variants
+                .iter()
+                .enumerate()
+                .map(|(index, variant)| VariantView {
+                    name: variant.name.clone(),
+                    decl: match variant.value {
+                        Some(value) => format!("{} = {}", variant.name, value),
+                        None => variant.name.clone(),
+                    },
+                    index,
+                })
+                .collect(),
+            fields: Vec::new(),
+        });
+
+        let structs = file.structs.iter().map(|def| DeclView {
+            name: def.name.clone(),
+            is_enum: false,
+            variants: Vec::new(),
+            fields: def
+                .fields
+                .iter()
+                .map(|field| {
+                    let property = to_pascal_case(&field.name);
+                    FieldView {
+                        csharp_type: field.field_type.to_csharp_type(),
+                        initializer: initializer(file, &field.field_type).to_string(),
+                        write: write_expr(&field.field_type, "writer", &format!("value.{}", property), 0),
+                        read: read_expr(&field.field_type, "reader", 0),
+                        property,
+                    }
+                })
+                .collect(),
+        });
+
+        Self {
+            module: module.to_string(),
+            namespace: NAMESPACE,
+            decls: enums.chain(structs).collect(),
+        }
+    }
+}
+
+fn initializer(file: &IdlFile, type_def: &TypeDef) -> &'static str {
+    match type_def {
+        TypeDef::String => " = \"\";",
+        TypeDef::Bytes => " = Array.Empty<byte>();",
+        TypeDef::Array(_) | TypeDef::Map(_, _) => " = new();",
+        TypeDef::Custom(name) => match file.resolve(name) {
+            Some(UserType::Struct(_)) => " = new();",
+            _ => "",
+        },
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn test_render_types() {
+        let source = include_str!("../../../examples/camera-service/camera_service.idl");
+        let file = parse(source).unwrap();
+        let code = CSharpCodegen::new().render_types(&file, "camera_service").unwrap();
+
+        assert!(code.contains("namespace SoftBus.Generated\n{\n    /// <summary>PixelFormat枚举</summary>"));
+        assert!(code.contains("    public enum PixelFormat\n    {\n        Rgb888 = 0,\n        Yuv420,\n"));
+        assert!(code.contains("        public int Id { get; set; }\n"));
+        assert!(code.contains("        public string Name { get; set; } = \"\";\n"));
+        assert!(code.contains("        public Resolution Resolution { get; set; } = new();\n"));
+        assert!(code.contains("        public List<PixelFormat> Formats { get; set; } = new();\n"));
+        assert!(code.contains("                PixelFormat.Jpeg => 3u,\n"));
+        assert!(code.contains("            writer.WriteList(value.Formats, (w1, v1) => PixelFormatCodec.Encode(w1, v1));\n"));
+        assert_eq!(CSharpCodegen::types_file_name("camera_service"), "CameraServiceTypes.cs");
+    }
+
+    #[test]
+    fn test_render_service() {
+        let file = parse(
+            "service Store {\n\
+             \x20   get(key: string, ids: array<u64>) -> map<string, array<bytes>>;\n\
+             \x20   clear_all();\n\
+             }",
+        )
+        .unwrap();
+        let code = CSharpCodegen::new().render(&file.services[0]).unwrap();
+
+        assert!(code.contains("    public interface IStore\n"));
+        assert!(code.contains(
+            "        Task<Dictionary<string, List<byte[]>>> GetAsync(string key, List<ulong> ids, CancellationToken cancellationToken = default);\n"
+        ));
+        assert!(code.contains("        Task ClearAllAsync(CancellationToken cancellationToken = default);\n"));
+        assert!(code.contains("            writer.WriteList(ids, (w1, v1) => w1.WriteU64(v1));\n"));
+        assert!(code.contains(
+            "            return reader.ReadMap(r1 => r1.ReadString(), r1 => r1.ReadList(r2 => r2.ReadBytes()));\n"
+        ));
+        assert!(code.contains("            await _client.CallAsync(ServiceName, \"clear_all\", writer.ToArray(), cancellationToken).ConfigureAwait(false);\n"));
+    }
+
+    #[test]
+    fn test_nested_write_expr() {
+        let ty = TypeDef::Map(
+            Box::new(TypeDef::String),
+            Box::new(TypeDef::Array(Box::new(TypeDef::Custom("Info".to_string())))),
+        );
+        assert_eq!(
+            write_expr(&ty, "writer", "value", 0),
+            "writer.WriteMap(value, (w1, v1) => w1.WriteString(v1), (w1, v1) => w1.WriteList(v1, (w2, v2) => InfoCodec.Encode(w2, v2)))"
+        );
     }
 }
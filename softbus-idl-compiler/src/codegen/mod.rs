@@ -3,16 +3,134 @@
 pub mod rust;
 pub mod csharp;
 pub mod cpp;
+pub mod naming;
 
 pub use rust::RustCodegen;
 pub use csharp::CSharpCodegen;
 pub use cpp::CppCodegen;
 
-use crate::parser::ServiceDef;
+use crate::parser::{IdlFile, ServiceDef};
 use std::path::Path;
+use std::str::FromStr;
 
 /// 代码生成器trait
 pub trait Codegen {
     /// 生成代码
     fn generate(&self, service: &ServiceDef, output_dir: &Path) -> anyhow::Result<()>;
+
+    /// 生成IDL文件中声明的struct/enum，`module`为IDL文件名（不含扩展名）
+    fn generate_types(&self, file: &IdlFile, module: &str, output_dir: &Path) -> anyhow::Result<()>;
+
+    /// 生成整个IDL文件：先生成类型声明，再逐个生成服务
+    fn generate_file(&self, file: &IdlFile, module: &str, output_dir: &Path) -> anyhow::Result<()> {
+        if file.has_types() {
+            self.generate_types(file, module, output_dir)?;
+        }
+        for service in &file.services {
+            self.generate(service, output_dir)?;
+        }
+        Ok(())
+    }
+}
+
+/// 拒绝尚未支持流式方法和事件的生成器处理含有它们的服务
+pub(crate) fn ensure_supported(service: &ServiceDef, language: Language) -> anyhow::Result<()> {
+    if let Some(method) = service.methods.iter().find(|method| method.kind().is_streaming()) {
+        anyhow::bail!(
+            "streaming method `{}.{}` is not supported by the {} generator",
+            service.name,
+            method.name,
+            language
+        );
+    }
+    if let Some(event) = service.events.first() {
+        anyhow::bail!(
+            "event `{}.{}` is not supported by the {} generator",
+            service.name,
+            event.name,
+            language
+        );
+    }
+    Ok(())
+}
+
+/// 目标语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    Rust,
+    CSharp,
+    Cpp,
+}
+
+impl Language {
+    /// 所有支持的目标语言
+    pub const ALL: [Language; 3] = [Language::Rust, Language::CSharp, Language::Cpp];
+
+    /// 获取该语言的代码生成器
+    pub fn codegen(&self) -> Box<dyn Codegen> {
+        match self {
+            Language::Rust => Box::new(RustCodegen::new()),
+            Language::CSharp => Box::new(CSharpCodegen::new()),
+            Language::Cpp => Box::new(CppCodegen::new()),
+        }
+    }
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "rust" | "rs" => Ok(Language::Rust),
+            "csharp" | "cs" | "c#" => Ok(Language::CSharp),
+            "cpp" | "c++" | "cxx" => Ok(Language::Cpp),
+            other => Err(format!("unsupported language `{}` (expected rust, csharp or cpp)", other)),
+        }
+    }
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Language::Rust => write!(f, "rust"),
+            Language::CSharp => write!(f, "csharp"),
+            Language::Cpp => write!(f, "cpp"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_from_str() {
+        assert_eq!("rust".parse::<Language>(), Ok(Language::Rust));
+        assert_eq!("C#".parse::<Language>(), Ok(Language::CSharp));
+        assert_eq!("c++".parse::<Language>(), Ok(Language::Cpp));
+        assert!("java".parse::<Language>().is_err());
+
+        for lang in Language::ALL {
+            assert_eq!(lang.to_string().parse::<Language>(), Ok(lang));
+        }
+    }
+
+    #[test]
+    fn test_streams_and_events_rejected_by_csharp_and_cpp() {
+        let file = crate::parser::parse("service Feed { watch(topic: string) -> stream<string>; }").unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+
+        for lang in [Language::CSharp, Language::Cpp] {
+            let err = lang.codegen().generate(&file.services[0], dir.path()).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("streaming method `Feed.watch` is not supported by the {} generator", lang)
+            );
+        }
+        Language::Rust.codegen().generate(&file.services[0], dir.path()).unwrap();
+
+        let file = crate::parser::parse("service Camera { event lost(id: i32); }").unwrap();
+        let err = Language::Cpp.codegen().generate(&file.services[0], dir.path()).unwrap_err();
+        assert_eq!(err.to_string(), "event `Camera.lost` is not supported by the cpp generator");
+    }
 }
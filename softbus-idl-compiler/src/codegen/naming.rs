@@ -0,0 +1,76 @@
+//! 标识符命名转换
+
+/// 拆分标识符为小写单词，兼容snake_case和camelCase/PascalCase
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+
+    for c in name.chars() {
+        if c == '_' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+
+        if c.is_uppercase() && prev_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        current.extend(c.to_lowercase());
+    }
+
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// 转换为snake_case
+pub fn to_snake_case(name: &str) -> String {
+    words(name).join("_")
+}
+
+/// 转换为PascalCase
+pub fn to_pascal_case(name: &str) -> String {
+    words(name).iter().map(|w| capitalize(w)).collect()
+}
+
+/// 转换为camelCase
+pub fn to_camel_case(name: &str) -> String {
+    let pascal = to_pascal_case(name);
+    let mut chars = pascal.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// 转换为SCREAMING_SNAKE_CASE
+pub fn to_upper_snake_case(name: &str) -> String {
+    to_snake_case(name).to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_conversion() {
+        assert_eq!(to_snake_case("CameraService"), "camera_service");
+        assert_eq!(to_snake_case("listCameras"), "list_cameras");
+        assert_eq!(to_pascal_case("list_cameras"), "ListCameras");
+        assert_eq!(to_camel_case("set_parameter"), "setParameter");
+        assert_eq!(to_upper_snake_case("CameraService"), "CAMERA_SERVICE");
+    }
+}
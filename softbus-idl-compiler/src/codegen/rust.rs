@@ -1,15 +1,63 @@
 //! Rust代码生成器
+//!
+//! 为每个服务生成：服务trait、请求参数结构体、基于`RpcClient`的客户端代理，
+//! 把trait实现适配为`MethodHandler`/`StreamHandler`的服务端桩，以及声明了事件时
+//! 的事件结构体和基于`RpcServer`的事件发布端。
+//!
+//! trait方法第一个参数为`CallContext`，携带调用方身份、截止时间和取消令牌。
+//! 流式方法的请求结构体只包含非流参数。服务端实现通过`StreamReceiver`读取客户端的流参数，
+//! 通过名为`sink`的`StreamSender`写出返回的流。
 
-use crate::parser::ServiceDef;
+use crate::parser::{IdlFile, MethodKind, ServiceDef};
 use crate::codegen::Codegen;
+use crate::codegen::naming::{to_pascal_case, to_snake_case, to_upper_snake_case};
+use handlebars::Handlebars;
+use serde::Serialize;
 use std::path::Path;
 
+const TEMPLATE: &str = include_str!("templates/rust.hbs");
+const TYPES_TEMPLATE: &str = include_str!("templates/rust_types.hbs");
+
 /// Rust代码生成器
-pub struct RustCodegen;
+pub struct RustCodegen {
+    registry: Handlebars<'static>,
+}
 
 impl RustCodegen {
     pub fn new() -> Self {
-        Self
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(true);
+        registry.register_escape_fn(handlebars::no_escape);
+        registry
+            .register_template_string("service", TEMPLATE)
+            .expect("built-in Rust template is valid");
+        registry
+            .register_template_string("types", TYPES_TEMPLATE)
+            .expect("built-in Rust types template is valid");
+
+        Self { registry }
+    }
+
+    /// 生成服务对应的Rust源码
+    pub fn render(&self, service: &ServiceDef) -> anyhow::Result<String> {
+        let view = ServiceView::new(service);
+        Ok(self.registry.render("service", &view)?)
+    }
+
+    /// 生成IDL文件中struct/enum声明的Rust源码
+    pub fn render_types(&self, file: &IdlFile, module: &str) -> anyhow::Result<String> {
+        let view = TypesView::new(file, module);
+        Ok(self.registry.render("types", &view)?)
+    }
+
+    /// 服务对应的输出文件名
+    pub fn file_name(service: &ServiceDef) -> String {
+        format!("{}.rs", to_snake_case(&service.name))
+    }
+
+    /// 类型声明对应的输出文件名
+    pub fn types_file_name(module: &str) -> String {
+        format!("{}_types.rs", to_snake_case(module))
     }
 }
 
@@ -21,19 +69,393 @@ impl Default for RustCodegen {
 
 impl Codegen for RustCodegen {
     fn generate(&self, service: &ServiceDef, output_dir: &Path) -> anyhow::Result<()> {
-        // TODO: 实现Rust代码生成
-        println!("Generating Rust code for service: {}", service.name);
-        println!("Output directory: {:?}", output_dir);
+        let code = self.render(service)?;
+        std::fs::create_dir_all(output_dir)?;
+        std::fs::write(output_dir.join(Self::file_name(service)), code)?;
         Ok(())
     }
+
+    fn generate_types(&self, file: &IdlFile, module: &str, output_dir: &Path) -> anyhow::Result<()> {
+        let code = self.render_types(file, module)?;
+        std::fs::create_dir_all(output_dir)?;
+        std::fs::write(output_dir.join(Self::types_file_name(module)), code)?;
+        Ok(())
+    }
+}
+
+/// 模板使用的服务视图
+#[derive(Serialize)]
+struct ServiceView {
+    name: String,
+    const_name: String,
+    proxy: String,
+    stub: String,
+    /// `softbus_core::rpc::server`中用到的名称
+    server_imports: String,
+    /// `softbus_core::rpc`中用到的名称
+    rpc_imports: String,
+    has_streams: bool,
+    methods: Vec<MethodView>,
+    publisher: String,
+    events: Vec<EventView>,
+}
+
+#[derive(Serialize)]
+struct EventView {
+    /// 线路上使用的原始事件名
+    name: String,
+    fn_name: String,
+    event_struct: String,
+    params: Vec<ParamView>,
+    params_decl: String,
+    event_init: String,
+}
+
+#[derive(Serialize)]
+struct MethodView {
+    /// 线路上使用的原始方法名
+    name: String,
+    fn_name: String,
+    request_struct: String,
+    handler_struct: String,
+    /// 请求结构体字段，不含流参数
+    params: Vec<ParamView>,
+    /// 服务trait方法的参数列表
+    params_decl: String,
+    request_init: String,
+    /// 服务端桩调用trait方法的实参
+    call_args: String,
+    /// 服务trait方法返回的`Result`内类型
+    return_type: String,
+    /// 客户端代理方法的参数列表
+    proxy_params_decl: String,
+    /// 客户端代理方法返回的`Result`内类型
+    proxy_return_type: String,
+    /// 客户端代理使用的`RpcClient`方法
+    client_call: String,
+    streaming: bool,
+    /// 是否只返回单个响应
+    single_response: bool,
+}
+
+#[derive(Serialize)]
+struct ParamView {
+    name: String,
+    rust_type: String,
+}
+
+impl ServiceView {
+    fn new(service: &ServiceDef) -> Self {
+        let methods: Vec<MethodView> = service
+            .methods
+            .iter()
+            .map(|method| {
+                let type_prefix = format!("{}{}", service.name, to_pascal_case(&method.name));
+                let kind = method.kind();
+                let params: Vec<ParamView> = method
+                    .params
+                    .iter()
+                    .filter(|param| !param.param_type.is_stream())
+                    .map(|param| ParamView {
+                        name: param.name.clone(),
+                        rust_type: param.param_type.to_rust_type(),
+                    })
+                    .collect();
+
+                let proxy_params_decl: String = params
+                    .iter()
+                    .map(|p| format!(", {}: {}", p.name, p.rust_type))
+                    .collect();
+                let field_names: Vec<&str> = params.iter().map(|p| p.name.as_str()).collect();
+                let request_struct = format!("{}Request", type_prefix);
+                let request_init = if field_names.is_empty() {
+                    format!("{} {{}}", request_struct)
+                } else {
+                    format!("{} {{ {} }}", request_struct, field_names.join(", "))
+                };
+                let mut call_args: Vec<String> = field_names
+                    .iter()
+                    .map(|name| format!("request.{}", name))
+                    .collect();
+
+                let mut params_decl = format!(", ctx: &CallContext{}", proxy_params_decl);
+                call_args.insert(0, "ctx".to_string());
+                if let Some(param) = method.stream_param() {
+                    let item = param.param_type.to_rust_type();
+                    params_decl.push_str(&format!(", {}: StreamReceiver<{}>", param.name, item));
+                    call_args.push("StreamReceiver::new(inbound)".to_string());
+                }
+                let output = method.return_type.to_rust_type();
+                let input = method.stream_param().map(|p| p.param_type.to_rust_type());
+                let (return_type, proxy_return_type, client_call) = match kind {
+                    // 幂等方法按客户端的重试策略自动重试，不需要服务端去重
+                    MethodKind::Unary if method.idempotent => (output.clone(), output, "call_idempotent"),
+                    MethodKind::Unary => (output.clone(), output, "call"),
+                    MethodKind::Oneway => (output.clone(), output, "notify"),
+                    MethodKind::ServerStreaming => (
+                        "()".to_string(),
+                        format!("StreamReceiver<{}>", output),
+                        "server_stream",
+                    ),
+                    MethodKind::ClientStreaming => (
+                        output.clone(),
+                        format!("ClientStreaming<{}, {}>", input.unwrap_or_default(), output),
+                        "client_stream",
+                    ),
+                    MethodKind::Bidirectional => (
+                        "()".to_string(),
+                        format!(
+                            "(StreamSender<{}>, StreamReceiver<{}>)",
+                            input.unwrap_or_default(),
+                            output
+                        ),
+                        "open_stream",
+                    ),
+                };
+                if method.return_type.is_stream() {
+                    params_decl.push_str(&format!(
+                        ", sink: StreamSender<{}>",
+                        method.return_type.to_rust_type()
+                    ));
+                    call_args.push("StreamSender::new(outbound)".to_string());
+                }
+
+                MethodView {
+                    name: method.name.clone(),
+                    fn_name: to_snake_case(&method.name),
+                    request_struct,
+                    handler_struct: format!("{}Handler", type_prefix),
+                    params,
+                    params_decl,
+                    request_init,
+                    call_args: call_args.join(", "),
+                    return_type,
+                    proxy_params_decl,
+                    proxy_return_type,
+                    client_call: client_call.to_string(),
+                    streaming: kind.is_streaming(),
+                    single_response: !method.return_type.is_stream(),
+                }
+            })
+            .collect();
+
+        let events: Vec<EventView> = service
+            .events
+            .iter()
+            .map(|event| {
+                let params: Vec<ParamView> = event
+                    .params
+                    .iter()
+                    .map(|param| ParamView {
+                        name: param.name.clone(),
+                        rust_type: param.param_type.to_rust_type(),
+                    })
+                    .collect();
+                let params_decl = params
+                    .iter()
+                    .map(|p| format!(", {}: {}", p.name, p.rust_type))
+                    .collect();
+                let field_names: Vec<&str> = params.iter().map(|p| p.name.as_str()).collect();
+                let event_struct = format!("{}{}Event", service.name, to_pascal_case(&event.name));
+                let event_init = if field_names.is_empty() {
+                    format!("{} {{}}", event_struct)
+                } else {
+                    format!("{} {{ {} }}", event_struct, field_names.join(", "))
+                };
+
+                EventView {
+                    name: event.name.clone(),
+                    fn_name: to_snake_case(&event.name),
+                    event_struct,
+                    params,
+                    params_decl,
+                    event_init,
+                }
+            })
+            .collect();
+
+        // 只导入用到的名称，生成的代码在`deny(warnings)`下也能编译
+        let kinds: Vec<MethodKind> = service.methods.iter().map(|method| method.kind()).collect();
+        let has_streams = kinds.iter().any(|kind| kind.is_streaming());
+        let mut server_imports = vec!["MethodHandler", "RpcService"];
+        let mut rpc_imports = Vec::new();
+        if !kinds.is_empty() {
+            rpc_imports.push("CallContext");
+        }
+        if kinds.contains(&MethodKind::ClientStreaming) {
+            rpc_imports.push("ClientStreaming");
+        }
+        if !events.is_empty() {
+            rpc_imports.push("EventSubscription");
+        }
+        rpc_imports.extend(["RpcClient", "RpcServer"]);
+        if has_streams {
+            server_imports.push("StreamHandler");
+            rpc_imports.extend([
+                "StreamReader",
+                "StreamReceiver",
+                "StreamSender",
+                "StreamWriter",
+            ]);
+        }
+
+        Self {
+            name: service.name.clone(),
+            const_name: format!("{}_NAME", to_upper_snake_case(&service.name)),
+            proxy: format!("{}Proxy", service.name),
+            stub: format!("{}Stub", service.name),
+            server_imports: server_imports.join(", "),
+            rpc_imports: rpc_imports.join(", "),
+            has_streams,
+            methods,
+            publisher: format!("{}Events", service.name),
+            events,
+        }
+    }
+}
+
+/// 模板使用的类型声明视图
+#[derive(Serialize)]
+struct TypesView {
+    module: String,
+    enums: Vec<EnumView>,
+    structs: Vec<StructView>,
+}
+
+#[derive(Serialize)]
+struct EnumView {
+    name: String,
+    /// 成员声明，如`Rgb = 1`
+    variants: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct StructView {
+    name: String,
+    fields: Vec<ParamView>,
+}
+
+impl TypesView {
+    fn new(file: &IdlFile, module: &str) -> Self {
+        let enums = file
+            .enums
+            .iter()
+            .map(|def| EnumView {
+                name: def.name.clone(),
+                variants: def
+                    .variants
+                    .iter()
+                    .map(|variant| match variant.value {
+                        Some(value) => format!("{} = {}", variant.name, value),
+                        None => variant.name.clone(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let structs = file
+            .structs
+            .iter()
+            .map(|def| StructView {
+                name: def.name.clone(),
+                fields: def
+                    .fields
+                    .iter()
+                    .map(|field| ParamView {
+                        name: field.name.clone(),
+                        rust_type: field.field_type.to_rust_type(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            module: module.to_string(),
+            enums,
+            structs,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::parse;
 
     #[test]
     fn test_rust_codegen_creation() {
         let _codegen = RustCodegen::new();
     }
+
+    #[test]
+    fn test_render_camera_service() {
+        let source = include_str!("../../../examples/camera-service/camera_service.idl");
+        let file = parse(source).unwrap();
+        let code = RustCodegen::new().render(&file.services[0]).unwrap();
+
+        assert!(code.contains("pub trait CameraService: Send + Sync + 'static {"));
+        assert!(code.contains("    async fn open(&self, ctx: &CallContext, camera_id: i32) -> Result<bool>;"));
+        assert!(code.contains("pub struct CameraServiceSetParameterRequest {\n    pub key: String,\n    pub value: String,\n}"));
+        assert!(code.contains("pub struct CameraServiceCloseRequest {}"));
+        assert!(code.contains("pub async fn list_cameras(&self) -> Result<Vec<String>> {"));
+        assert!(code.contains("self.client.call(CAMERA_SERVICE_NAME, \"capture\", request).await"));
+        assert!(code.contains("self.client.call_idempotent(CAMERA_SERVICE_NAME, \"get_camera_info\", request).await"));
+        assert!(code.contains("impl<T: CameraService> MethodHandler for CameraServiceOpenHandler<T> {"));
+        assert!(!code.contains("HashMap"));
+        assert!(code.contains("async fn get_camera_info(&self, ctx: &CallContext, camera_id: i32) -> Result<CameraInfo>;"));
+        assert!(code.contains("self.inner.open(ctx, request.camera_id).await?"));
+        assert!(code.contains("pub async fn open(&self, camera_id: i32) -> Result<bool> {"));
+        assert!(code.contains("self.client.notify(CAMERA_SERVICE_NAME, \"set_flash\", request).await"));
+        assert!(!code.contains("EventSubscription"));
+    }
+
+    #[test]
+    fn test_render_events() {
+        let source = "service Camera {\n\
+                      \x20   event disconnected(camera_id: i32, reason: string);\n\
+                      \x20   event ready();\n\
+                      }";
+        let file = parse(source).unwrap();
+        let code = RustCodegen::new().render(&file.services[0]).unwrap();
+
+        assert!(code.contains("pub struct CameraDisconnectedEvent {\n    pub camera_id: i32,\n    pub reason: String,\n}"));
+        assert!(code.contains("pub struct CameraReadyEvent {}"));
+        assert!(code.contains("pub fn subscribe_disconnected(&self) -> EventSubscription<CameraDisconnectedEvent> {"));
+        assert!(code.contains("pub async fn disconnected(&self, camera_id: i32, reason: String) -> Result<usize> {"));
+        assert!(code.contains("self.server.publish(CAMERA_NAME, \"ready\", &event).await"));
+    }
+
+    #[test]
+    fn test_render_streaming_methods() {
+        let source = "service Feed {\n\
+                      \x20   watch(topic: string) -> stream<bytes>;\n\
+                      \x20   upload(name: string, chunks: stream<bytes>) -> u64;\n\
+                      \x20   chat(messages: stream<string>) -> stream<string>;\n\
+                      }";
+        let file = parse(source).unwrap();
+        let code = RustCodegen::new().render(&file.services[0]).unwrap();
+
+        assert!(code.contains("async fn watch(&self, ctx: &CallContext, topic: String, sink: StreamSender<Vec<u8>>) -> Result<()>;"));
+        assert!(code.contains("async fn upload(&self, ctx: &CallContext, name: String, chunks: StreamReceiver<Vec<u8>>) -> Result<u64>;"));
+        assert!(code.contains("pub struct FeedUploadRequest {\n    pub name: String,\n}"));
+        assert!(code.contains("pub async fn watch(&self, topic: String) -> Result<StreamReceiver<Vec<u8>>> {"));
+        assert!(code.contains("pub async fn upload(&self, name: String) -> Result<ClientStreaming<Vec<u8>, u64>> {"));
+        assert!(code.contains("pub async fn chat(&self) -> Result<(StreamSender<String>, StreamReceiver<String>)> {"));
+        assert!(code.contains("impl<T: Feed> StreamHandler for FeedWatchHandler<T> {"));
+        assert!(code.contains("fn stream_handlers(&self) -> Vec<(String, Arc<dyn StreamHandler>)> {"));
+        assert!(code.contains("StreamSender::new(outbound).send(response).await"));
+        assert!(code.contains("self.inner.chat(ctx, StreamReceiver::new(inbound), StreamSender::new(outbound)).await"));
+    }
+
+    #[test]
+    fn test_render_types() {
+        let source = include_str!("../../../examples/camera-service/camera_service.idl");
+        let file = parse(source).unwrap();
+        let code = RustCodegen::new().render_types(&file, "camera_service").unwrap();
+
+        assert!(code.contains("pub enum PixelFormat {\n    Rgb888 = 0,\n    Yuv420,\n"));
+        assert!(code.contains("pub struct CameraInfo {\n    pub id: i32,\n    pub name: String,\n    pub resolution: Resolution,\n    pub formats: Vec<PixelFormat>,\n}"));
+        assert!(!code.contains("\nuse "));
+        assert_eq!(RustCodegen::types_file_name("camera_service"), "camera_service_types.rs");
+    }
 }
@@ -0,0 +1,117 @@
+//! build.rs使用的IDL编译API
+
+use crate::codegen::Language;
+use crate::codegen::naming::to_snake_case;
+use crate::parser::{self, Report};
+use anyhow::{anyhow, Context};
+use std::path::{Path, PathBuf};
+
+/// IDL文件对应的模块名，用于类型声明等按文件生成的输出
+pub fn module_name(path: &Path) -> String {
+    let stem = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    to_snake_case(&stem)
+}
+
+/// 创建编译指定IDL文件的构建器
+pub fn compile_idls<P: AsRef<Path>>(idls: &[P]) -> Builder {
+    Builder::new(idls.iter().map(|p| p.as_ref().to_path_buf()).collect())
+}
+
+/// IDL编译构建器
+///
+/// 默认输出到`OUT_DIR`、只生成Rust代码，并为每个输入文件输出
+/// `cargo:rerun-if-changed`
+#[derive(Debug, Clone)]
+pub struct Builder {
+    idls: Vec<PathBuf>,
+    out_dir: Option<PathBuf>,
+    languages: Vec<Language>,
+    emit_rerun_if_changed: bool,
+}
+
+impl Builder {
+    fn new(idls: Vec<PathBuf>) -> Self {
+        Self {
+            idls,
+            out_dir: None,
+            languages: vec![Language::Rust],
+            emit_rerun_if_changed: true,
+        }
+    }
+
+    /// 设置输出目录，默认使用`OUT_DIR`环境变量
+    pub fn out_dir(mut self, out_dir: impl Into<PathBuf>) -> Self {
+        self.out_dir = Some(out_dir.into());
+        self
+    }
+
+    /// 设置目标语言
+    pub fn languages(mut self, languages: &[Language]) -> Self {
+        self.languages = languages.to_vec();
+        self
+    }
+
+    /// 是否输出`cargo:rerun-if-changed`
+    pub fn emit_rerun_if_changed(mut self, enable: bool) -> Self {
+        self.emit_rerun_if_changed = enable;
+        self
+    }
+
+    /// 解析所有IDL文件并生成代码
+    ///
+    /// 任一文件解析或语义检查失败时不生成任何代码，错误信息包含渲染后的诊断
+    pub fn compile(self) -> anyhow::Result<()> {
+        let out_dir = match self.out_dir {
+            Some(out_dir) => out_dir,
+            None => std::env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or_else(|| anyhow!("OUT_DIR is not set, specify an output directory with `out_dir`"))?,
+        };
+
+        let mut files = Vec::with_capacity(self.idls.len());
+        for path in &self.idls {
+            if self.emit_rerun_if_changed {
+                println!("cargo:rerun-if-changed={}", path.display());
+            }
+
+            let source = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let file = parser::parse(&source)
+                .map_err(Report::from)
+                .and_then(|file| parser::validate(&file).map(|()| file))
+                .map_err(|report| anyhow!("{}", report.render(&source, &path.display().to_string())))?;
+            files.push((file, module_name(path)));
+        }
+
+        for language in &self.languages {
+            let codegen = language.codegen();
+            for (file, module) in &files {
+                codegen
+                    .generate_file(file, module, &out_dir)
+                    .with_context(|| format!("failed to generate {} code for {}", language, module))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_defaults() {
+        let builder = compile_idls(&["a.idl", "b.idl"]);
+        assert_eq!(builder.idls, [PathBuf::from("a.idl"), PathBuf::from("b.idl")]);
+        assert_eq!(builder.languages, [Language::Rust]);
+        assert!(builder.out_dir.is_none());
+        assert!(builder.emit_rerun_if_changed);
+    }
+
+    #[test]
+    fn test_module_name() {
+        assert_eq!(module_name(Path::new("idl/CameraService.idl")), "camera_service");
+        assert_eq!(module_name(Path::new("camera_service.idl")), "camera_service");
+    }
+}
@@ -0,0 +1,27 @@
+//! SoftBus IDL编译器库
+//!
+//! 供其他crate在build.rs中编译IDL文件：
+//!
+//! ```no_run
+//! // build.rs
+//! fn main() -> anyhow::Result<()> {
+//!     softbus_idl_compiler::compile_idls(&["idl/camera_service.idl"]).compile()
+//! }
+//! ```
+//!
+//! 生成的Rust代码按服务名写入`OUT_DIR/<service_snake_case>.rs`，IDL中声明的
+//! struct/enum写入`OUT_DIR/<idl文件名>_types.rs`，二者需引入到同一模块：
+//!
+//! ```ignore
+//! mod camera {
+//!     include!(concat!(env!("OUT_DIR"), "/camera_service_types.rs"));
+//!     include!(concat!(env!("OUT_DIR"), "/camera_service.rs"));
+//! }
+//! ```
+
+pub mod parser;
+pub mod codegen;
+pub mod compile;
+
+pub use compile::{compile_idls, module_name, Builder};
+pub use codegen::Language;
@@ -1,12 +1,14 @@
 //! SoftBus IDL编译器
-//! 
+//!
 //! 将IDL定义编译为Rust/C#/C++代码
 
+use anyhow::Context;
 use clap::Parser;
 use std::path::PathBuf;
+use std::process::ExitCode;
 
-mod parser;
-mod codegen;
+use softbus_idl_compiler::parser::{self, Report};
+use softbus_idl_compiler::{module_name, Language};
 
 /// IDL编译器命令行参数
 #[derive(Parser, Debug)]
@@ -18,30 +20,73 @@ struct Args {
     input: PathBuf,
 
     /// 输出目录
-    #[arg(short, long)]
-    output: PathBuf,
+    #[arg(short, long, required_unless_present = "check")]
+    output: Option<PathBuf>,
 
     /// 目标语言 (rust, csharp, cpp)
     #[arg(short, long, default_value = "rust")]
-    lang: String,
+    lang: Language,
+
+    /// 只检查IDL是否合法，不生成代码
+    #[arg(long)]
+    check: bool,
 
     /// 详细输出
     #[arg(short, long)]
     verbose: bool,
 }
 
-fn main() -> anyhow::Result<()> {
+fn main() -> ExitCode {
     let args = Args::parse();
 
+    match run(&args) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args) -> anyhow::Result<ExitCode> {
     if args.verbose {
         println!("Input file: {:?}", args.input);
         println!("Output directory: {:?}", args.output);
         println!("Target language: {}", args.lang);
     }
 
-    // TODO: 实现IDL编译逻辑
-    println!("IDL compiler is not fully implemented yet.");
-    println!("This is a placeholder for the IDL compilation functionality.");
+    let source = std::fs::read_to_string(&args.input)
+        .with_context(|| format!("failed to read {}", args.input.display()))?;
+
+    let parsed = parser::parse(&source)
+        .map_err(Report::from)
+        .and_then(|file| parser::validate(&file).map(|()| file));
+    let file = match parsed {
+        Ok(file) => file,
+        Err(report) => {
+            eprint!("{}", report.render(&source, &args.input.display().to_string()));
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    if args.check {
+        if args.verbose {
+            println!("{}: ok ({} services)", args.input.display(), file.services.len());
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // required_unless_present保证非check模式下一定有输出目录
+    let output = args.output.as_deref().expect("--output is required");
+    if args.verbose {
+        for service in &file.services {
+            println!("Generating {} code for service: {}", args.lang, service.name);
+        }
+    }
+    args.lang
+        .codegen()
+        .generate_file(&file, &module_name(&args.input), output)
+        .with_context(|| format!("failed to generate {} code", args.lang))?;
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
@@ -1,12 +1,92 @@
 //! 抽象语法树定义
 
 use serde::{Serialize, Deserialize};
+use super::diagnostic::Span;
+
+/// IDL文件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IdlFile {
+    pub structs: Vec<StructDef>,
+    pub enums: Vec<EnumDef>,
+    pub services: Vec<ServiceDef>,
+}
+
+impl IdlFile {
+    /// 按名称查找用户定义类型
+    pub fn resolve(&self, name: &str) -> Option<UserType<'_>> {
+        if let Some(def) = self.structs.iter().find(|s| s.name == name) {
+            return Some(UserType::Struct(def));
+        }
+        self.enums.iter().find(|e| e.name == name).map(UserType::Enum)
+    }
+
+    /// 是否声明了struct或enum
+    pub fn has_types(&self) -> bool {
+        !self.structs.is_empty() || !self.enums.is_empty()
+    }
+}
+
+/// `TypeDef::Custom`引用的用户定义类型
+#[derive(Debug, Clone, Copy)]
+pub enum UserType<'a> {
+    Struct(&'a StructDef),
+    Enum(&'a EnumDef),
+}
+
+/// 结构体定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructDef {
+    pub name: String,
+    pub fields: Vec<FieldDef>,
+    /// 名称在源码中的位置
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// 结构体字段定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldDef {
+    pub name: String,
+    pub field_type: TypeDef,
+    /// 名称在源码中的位置
+    #[serde(default)]
+    pub span: Span,
+    /// 字段类型在源码中的位置
+    #[serde(default)]
+    pub type_span: Span,
+}
+
+/// 枚举定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnumDef {
+    pub name: String,
+    pub variants: Vec<EnumVariant>,
+    /// 名称在源码中的位置
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// 枚举成员，`value`为显式指定的整数值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnumVariant {
+    pub name: String,
+    pub value: Option<i64>,
+    /// 名称在源码中的位置
+    #[serde(default)]
+    pub span: Span,
+}
 
 /// 服务定义
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceDef {
     pub name: String,
     pub methods: Vec<MethodDef>,
+    /// 服务端推送给客户端的事件
+    #[serde(default)]
+    pub events: Vec<EventDef>,
+    /// 名称在源码中的位置
+    #[serde(default)]
+    pub span: Span,
 }
 
 /// 方法定义
@@ -15,6 +95,74 @@ pub struct MethodDef {
     pub name: String,
     pub params: Vec<ParamDef>,
     pub return_type: TypeDef,
+    /// 名称在源码中的位置
+    #[serde(default)]
+    pub span: Span,
+    /// 返回类型在源码中的位置，无返回类型时为方法名位置
+    #[serde(default)]
+    pub return_span: Span,
+    /// 单向方法：客户端只发送请求，不等待响应
+    #[serde(default)]
+    pub oneway: bool,
+    /// 幂等方法：重复执行与执行一次效果相同，客户端可自动重试
+    #[serde(default)]
+    pub idempotent: bool,
+}
+
+/// 事件定义，事件的参数即推送给订阅者的数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventDef {
+    pub name: String,
+    pub params: Vec<ParamDef>,
+    /// 名称在源码中的位置
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// 方法的调用方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MethodKind {
+    /// 一次请求、一次响应
+    Unary,
+    /// 一次请求、没有响应
+    Oneway,
+    /// 一次请求、一组响应
+    ServerStreaming,
+    /// 一组请求、一次响应
+    ClientStreaming,
+    /// 双向消息流
+    Bidirectional,
+}
+
+impl MethodKind {
+    /// 是否通过流帧传输
+    pub fn is_streaming(self) -> bool {
+        matches!(
+            self,
+            MethodKind::ServerStreaming | MethodKind::ClientStreaming | MethodKind::Bidirectional
+        )
+    }
+}
+
+impl MethodDef {
+    /// 流参数，客户端流和双向流方法的最后一个参数
+    pub fn stream_param(&self) -> Option<&ParamDef> {
+        self.params.last().filter(|param| param.param_type.is_stream())
+    }
+
+    /// 按`oneway`标记、流参数和返回类型判断调用方式
+    pub fn kind(&self) -> MethodKind {
+        if self.oneway {
+            return MethodKind::Oneway;
+        }
+        let client_streaming = self.params.iter().any(|param| param.param_type.is_stream());
+        match (client_streaming, self.return_type.is_stream()) {
+            (false, false) => MethodKind::Unary,
+            (false, true) => MethodKind::ServerStreaming,
+            (true, false) => MethodKind::ClientStreaming,
+            (true, true) => MethodKind::Bidirectional,
+        }
+    }
 }
 
 /// 参数定义
@@ -22,10 +170,16 @@ pub struct MethodDef {
 pub struct ParamDef {
     pub name: String,
     pub param_type: TypeDef,
+    /// 名称在源码中的位置
+    #[serde(default)]
+    pub span: Span,
+    /// 参数类型在源码中的位置
+    #[serde(default)]
+    pub type_span: Span,
 }
 
 /// 类型定义
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TypeDef {
     Void,
     Bool,
@@ -40,10 +194,41 @@ pub enum TypeDef {
     Custom(String),
     Array(Box<TypeDef>),
     Map(Box<TypeDef>, Box<TypeDef>),
+    /// 消息流，只能直接用作方法参数或返回类型
+    Stream(Box<TypeDef>),
+}
+
+impl std::fmt::Display for TypeDef {
+    /// 以IDL语法输出类型
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeDef::Void => write!(f, "void"),
+            TypeDef::Bool => write!(f, "bool"),
+            TypeDef::I32 => write!(f, "i32"),
+            TypeDef::I64 => write!(f, "i64"),
+            TypeDef::U32 => write!(f, "u32"),
+            TypeDef::U64 => write!(f, "u64"),
+            TypeDef::F32 => write!(f, "f32"),
+            TypeDef::F64 => write!(f, "f64"),
+            TypeDef::String => write!(f, "string"),
+            TypeDef::Bytes => write!(f, "bytes"),
+            TypeDef::Custom(name) => write!(f, "{}", name),
+            TypeDef::Array(inner) => write!(f, "array<{}>", inner),
+            TypeDef::Map(k, v) => write!(f, "map<{}, {}>", k, v),
+            TypeDef::Stream(inner) => write!(f, "stream<{}>", inner),
+        }
+    }
 }
 
 impl TypeDef {
+    /// 是否为消息流
+    pub fn is_stream(&self) -> bool {
+        matches!(self, TypeDef::Stream(_))
+    }
+
     /// 转换为Rust类型字符串
+    ///
+    /// 流转换为消息的类型，收发端的包装由代码生成器决定，其他语言同理。
     pub fn to_rust_type(&self) -> String {
         match self {
             TypeDef::Void => "()".to_string(),
@@ -58,7 +243,66 @@ impl TypeDef {
             TypeDef::Bytes => "Vec<u8>".to_string(),
             TypeDef::Custom(name) => name.clone(),
             TypeDef::Array(inner) => format!("Vec<{}>", inner.to_rust_type()),
-            TypeDef::Map(k, v) => format!("HashMap<{}, {}>", k.to_rust_type(), v.to_rust_type()),
+            TypeDef::Map(k, v) => format!(
+                "std::collections::HashMap<{}, {}>",
+                k.to_rust_type(),
+                v.to_rust_type()
+            ),
+            TypeDef::Stream(inner) => inner.to_rust_type(),
+        }
+    }
+
+    /// 转换为C#类型字符串
+    pub fn to_csharp_type(&self) -> String {
+        match self {
+            TypeDef::Void => "void".to_string(),
+            TypeDef::Bool => "bool".to_string(),
+            TypeDef::I32 => "int".to_string(),
+            TypeDef::I64 => "long".to_string(),
+            TypeDef::U32 => "uint".to_string(),
+            TypeDef::U64 => "ulong".to_string(),
+            TypeDef::F32 => "float".to_string(),
+            TypeDef::F64 => "double".to_string(),
+            TypeDef::String => "string".to_string(),
+            TypeDef::Bytes => "byte[]".to_string(),
+            TypeDef::Custom(name) => name.clone(),
+            TypeDef::Array(inner) => format!("List<{}>", inner.to_csharp_type()),
+            TypeDef::Map(k, v) => format!("Dictionary<{}, {}>", k.to_csharp_type(), v.to_csharp_type()),
+            TypeDef::Stream(inner) => inner.to_csharp_type(),
+        }
+    }
+
+    /// 转换为C++类型字符串
+    pub fn to_cpp_type(&self) -> String {
+        match self {
+            TypeDef::Void => "void".to_string(),
+            TypeDef::Bool => "bool".to_string(),
+            TypeDef::I32 => "int32_t".to_string(),
+            TypeDef::I64 => "int64_t".to_string(),
+            TypeDef::U32 => "uint32_t".to_string(),
+            TypeDef::U64 => "uint64_t".to_string(),
+            TypeDef::F32 => "float".to_string(),
+            TypeDef::F64 => "double".to_string(),
+            TypeDef::String => "std::string".to_string(),
+            TypeDef::Bytes => "std::vector<uint8_t>".to_string(),
+            TypeDef::Custom(name) => name.clone(),
+            TypeDef::Array(inner) => format!("std::vector<{}>", inner.to_cpp_type()),
+            TypeDef::Map(k, v) => format!("std::unordered_map<{}, {}>", k.to_cpp_type(), v.to_cpp_type()),
+            TypeDef::Stream(inner) => inner.to_cpp_type(),
+        }
+    }
+
+    /// 遍历类型中引用的所有用户定义类型名称
+    pub fn custom_names(&self) -> Vec<&str> {
+        match self {
+            TypeDef::Custom(name) => vec![name.as_str()],
+            TypeDef::Array(inner) | TypeDef::Stream(inner) => inner.custom_names(),
+            TypeDef::Map(k, v) => {
+                let mut names = k.custom_names();
+                names.extend(v.custom_names());
+                names
+            }
+            _ => Vec::new(),
         }
     }
 }
@@ -73,4 +317,21 @@ mod tests {
         assert_eq!(TypeDef::String.to_rust_type(), "String");
         assert_eq!(TypeDef::Array(Box::new(TypeDef::I32)).to_rust_type(), "Vec<i32>");
     }
+
+    #[test]
+    fn test_type_def_to_other_languages() {
+        let map = TypeDef::Map(
+            Box::new(TypeDef::String),
+            Box::new(TypeDef::Array(Box::new(TypeDef::Custom("Info".to_string())))),
+        );
+        assert_eq!(map.to_csharp_type(), "Dictionary<string, List<Info>>");
+        assert_eq!(map.to_cpp_type(), "std::unordered_map<std::string, std::vector<Info>>");
+        assert_eq!(map.custom_names(), ["Info"]);
+        assert_eq!(TypeDef::Bytes.to_cpp_type(), "std::vector<uint8_t>");
+        assert_eq!(map.to_string(), "map<string, array<Info>>");
+
+        let stream = TypeDef::Stream(Box::new(TypeDef::Bytes));
+        assert_eq!(stream.to_string(), "stream<bytes>");
+        assert_eq!(stream.to_rust_type(), "Vec<u8>");
+    }
 }
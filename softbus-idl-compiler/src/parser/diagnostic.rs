@@ -0,0 +1,168 @@
+//! 诊断信息与源码位置
+
+use serde::{Serialize, Deserialize};
+use std::ops::Range;
+
+/// 源码位置
+///
+/// `start`/`end`为字节偏移，`line`/`column`从1开始计数。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    /// 根据源码中的字节区间计算位置
+    pub fn from_range(source: &str, range: Range<usize>) -> Self {
+        let before = &source[..range.start];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let column = source[line_start..range.start].chars().count() + 1;
+
+        Self {
+            start: range.start,
+            end: range.end,
+            line,
+            column,
+        }
+    }
+}
+
+/// 编译诊断
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{}:{}: {message}", span.line, span.column)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+}
+
+impl Diagnostic {
+    /// 创建新的诊断
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// 渲染为带源码片段的错误信息
+    pub fn render(&self, source: &str, file_name: &str) -> String {
+        let line_text = source.lines().nth(self.span.line.saturating_sub(1)).unwrap_or("");
+        let gutter = " ".repeat(self.span.line.to_string().len());
+
+        // 下划线长度按字符计，且不超过本行剩余部分
+        let rest = line_text.chars().count().saturating_sub(self.span.column - 1);
+        let width = source
+            .get(self.span.start..self.span.end)
+            .map(|text| text.chars().take_while(|&c| c != '\n').count())
+            .unwrap_or(0)
+            .clamp(1, rest.max(1));
+
+        format!(
+            "error: {message}\n{gutter}--> {file}:{line}:{column}\n{gutter} |\n{line} | {text}\n{gutter} | {pad}{marker}\n",
+            message = self.message,
+            file = file_name,
+            line = self.span.line,
+            column = self.span.column,
+            text = line_text,
+            pad = " ".repeat(self.span.column - 1),
+            marker = "^".repeat(width),
+        )
+    }
+}
+
+/// 由多条诊断组成的报告
+///
+/// 语义检查会一次收集所有问题，而不是在第一个错误处停止。
+#[derive(Debug, Clone, Default, PartialEq, Eq, thiserror::Error)]
+#[error("{}", self.diagnostics.iter().map(|d| d.to_string()).collect::<Vec<_>>().join("\n"))]
+pub struct Report {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Report {
+    /// 添加一条诊断
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// 是否没有任何诊断
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// 没有诊断时返回`Ok`
+    pub fn into_result(self) -> Result<(), Report> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// 依次渲染所有诊断，多于一条时附加汇总行
+    pub fn render(&self, source: &str, file_name: &str) -> String {
+        let mut output = self
+            .diagnostics
+            .iter()
+            .map(|d| d.render(source, file_name))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if self.diagnostics.len() > 1 {
+            output.push_str(&format!("\nerror: aborting due to {} previous errors\n", self.diagnostics.len()));
+        }
+        output
+    }
+}
+
+impl From<Diagnostic> for Report {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Self {
+            diagnostics: vec![diagnostic],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_span_from_range() {
+        let source = "service A {\n    open() -> bool\n}";
+        let start = source.find("bool").unwrap();
+        let span = Span::from_range(source, start..start + 4);
+        assert_eq!((span.line, span.column), (2, 15));
+    }
+
+    #[test]
+    fn test_render_diagnostic() {
+        let source = "service A {\n    open() -> bool\n}";
+        let start = source.find("bool").unwrap();
+        let diagnostic = Diagnostic::new("expected `;`", Span::from_range(source, start..start + 4));
+
+        let rendered = diagnostic.render(source, "a.idl");
+        assert!(rendered.contains("--> a.idl:2:15"));
+        assert!(rendered.contains("2 |     open() -> bool"));
+        assert!(rendered.ends_with("|               ^^^^\n"));
+    }
+
+    #[test]
+    fn test_render_report() {
+        let source = "service A {\n    open() -> bool\n}";
+        let mut report = Report::default();
+        report.push(Diagnostic::new("first", Span::from_range(source, 0..7)));
+        assert!(!report.render(source, "a.idl").contains("aborting"));
+
+        report.push(Diagnostic::new("second", Span::from_range(source, 16..20)));
+        let rendered = report.render(source, "a.idl");
+        assert!(rendered.contains("error: first\n"));
+        assert!(rendered.contains("error: second\n"));
+        assert!(rendered.ends_with("error: aborting due to 2 previous errors\n"));
+        assert_eq!(report.to_string(), "1:1: first\n2:5: second");
+    }
+}
@@ -1,10 +1,11 @@
 //! 词法分析器
 
 use logos::Logos;
+use super::diagnostic::{Diagnostic, Span};
 
 /// IDL token定义
 #[derive(Logos, Debug, PartialEq, Clone)]
-#[logos(skip r"[ \t\n\f]+")]
+#[logos(skip r"[ \t\r\n\f]+")]
 #[logos(skip r"//[^\n]*")]
 pub enum Token {
     #[token("service")]
@@ -19,6 +20,15 @@ pub enum Token {
     #[token("enum")]
     Enum,
 
+    #[token("oneway")]
+    Oneway,
+
+    #[token("idempotent")]
+    Idempotent,
+
+    #[token("event")]
+    Event,
+
     #[regex("[a-zA-Z_][a-zA-Z0-9_]*", |lex| lex.slice().to_string())]
     Identifier(String),
 
@@ -43,6 +53,18 @@ pub enum Token {
     #[token(":")]
     Colon,
 
+    #[token("=")]
+    Equals,
+
+    #[token("->")]
+    Arrow,
+
+    #[token("<")]
+    LAngle,
+
+    #[token(">")]
+    RAngle,
+
     #[regex(r#""([^"\\]|\\.)*""#, |lex| lex.slice().to_string())]
     String(String),
 
@@ -50,6 +72,34 @@ pub enum Token {
     Number(i64),
 }
 
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Service => write!(f, "`service`"),
+            Token::Interface => write!(f, "`interface`"),
+            Token::Struct => write!(f, "`struct`"),
+            Token::Enum => write!(f, "`enum`"),
+            Token::Oneway => write!(f, "`oneway`"),
+            Token::Idempotent => write!(f, "`idempotent`"),
+            Token::Event => write!(f, "`event`"),
+            Token::Identifier(name) => write!(f, "identifier `{}`", name),
+            Token::LBrace => write!(f, "`{{`"),
+            Token::RBrace => write!(f, "`}}`"),
+            Token::LParen => write!(f, "`(`"),
+            Token::RParen => write!(f, "`)`"),
+            Token::Semicolon => write!(f, "`;`"),
+            Token::Comma => write!(f, "`,`"),
+            Token::Colon => write!(f, "`:`"),
+            Token::Equals => write!(f, "`=`"),
+            Token::Arrow => write!(f, "`->`"),
+            Token::LAngle => write!(f, "`<`"),
+            Token::RAngle => write!(f, "`>`"),
+            Token::String(value) => write!(f, "string {}", value),
+            Token::Number(value) => write!(f, "number `{}`", value),
+        }
+    }
+}
+
 /// 词法分析器
 pub struct Lexer<'a> {
     inner: logos::Lexer<'a, Token>,
@@ -67,6 +117,29 @@ impl<'a> Lexer<'a> {
     pub fn next_token(&mut self) -> Option<Token> {
         self.inner.next().and_then(|r| r.ok())
     }
+
+    /// 将全部输入切分为带位置的token
+    ///
+    /// 遇到无法识别的字符时返回指向该位置的诊断。
+    pub fn tokenize(mut self) -> Result<Vec<(Token, Span)>, Diagnostic> {
+        let source = self.inner.source();
+        let mut tokens = Vec::new();
+
+        while let Some(result) = self.inner.next() {
+            let span = Span::from_range(source, self.inner.span());
+            match result {
+                Ok(token) => tokens.push((token, span)),
+                Err(()) => {
+                    return Err(Diagnostic::new(
+                        format!("unexpected character `{}`", self.inner.slice()),
+                        span,
+                    ))
+                }
+            }
+        }
+
+        Ok(tokens)
+    }
 }
 
 #[cfg(test)]
@@ -83,4 +156,25 @@ mod tests {
         assert_eq!(lexer.next_token(), Some(Token::LBrace));
         assert_eq!(lexer.next_token(), Some(Token::RBrace));
     }
+
+    #[test]
+    fn test_lexer_generics_and_arrow() {
+        let input = "list() -> map<string, array<i32>>; // comment";
+        let tokens: Vec<Token> = Lexer::new(input)
+            .tokenize()
+            .unwrap()
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect();
+
+        assert_eq!(tokens[3], Token::Arrow);
+        assert_eq!(tokens[5], Token::LAngle);
+        assert_eq!(&tokens[tokens.len() - 3..], &[Token::RAngle, Token::RAngle, Token::Semicolon]);
+    }
+
+    #[test]
+    fn test_lexer_reports_position() {
+        let err = Lexer::new("service A {\n  @\n}").tokenize().unwrap_err();
+        assert_eq!((err.span.line, err.span.column), (2, 3));
+    }
 }
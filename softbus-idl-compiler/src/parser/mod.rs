@@ -2,6 +2,15 @@
 
 pub mod lexer;
 pub mod ast;
+pub mod diagnostic;
+pub mod parse;
+pub mod validate;
 
 pub use lexer::Lexer;
-pub use ast::{ServiceDef, MethodDef, TypeDef};
+pub use ast::{
+    IdlFile, ServiceDef, MethodDef, MethodKind, EventDef, ParamDef, TypeDef, StructDef, FieldDef,
+    EnumDef, EnumVariant, UserType,
+};
+pub use diagnostic::{Diagnostic, Report, Span};
+pub use parse::{parse, Parser};
+pub use validate::validate;
@@ -0,0 +1,552 @@
+//! 语法分析器
+//!
+//! 将token流解析为AST，语法如下：
+//!
+//! ```text
+//! file    := (service | struct | enum)*
+//! service := ("service" | "interface") IDENT "{" (method | event)* "}"
+//! struct  := "struct" IDENT "{" [field ("," field)* [","]] "}"
+//! field   := IDENT ":" type
+//! enum    := "enum" IDENT "{" [variant ("," variant)* [","]] "}"
+//! variant := IDENT ["=" NUMBER]
+//! method  := ["idempotent"] ["oneway"] IDENT "(" [param ("," param)* [","]] ")" ["->" type] ";"
+//! event   := "event" IDENT "(" [param ("," param)* [","]] ")" ";"
+//! param   := IDENT ":" type
+//! type    := IDENT | "array" "<" type ">" | "map" "<" type "," type ">"
+//!          | "stream" "<" type ">"
+//! ```
+//!
+//! 非内置类型名解析为`TypeDef::Custom`，是否引用了已声明的struct或enum
+//! 由`validate`检查。
+
+use super::ast::{
+    EnumDef, EnumVariant, EventDef, FieldDef, IdlFile, MethodDef, ParamDef, ServiceDef, StructDef,
+    TypeDef,
+};
+use super::diagnostic::{Diagnostic, Span};
+use super::lexer::{Lexer, Token};
+
+/// 解析IDL源码
+pub fn parse(source: &str) -> Result<IdlFile, Diagnostic> {
+    Parser::new(source)?.parse_file()
+}
+
+/// 内置类型名，不能用作struct/enum名称
+const BUILTIN_TYPES: &[&str] = &[
+    "void", "bool", "i32", "i64", "u32", "u64", "f32", "f64", "string", "bytes", "array", "map",
+    "stream",
+];
+
+/// 语法分析器
+pub struct Parser {
+    tokens: Vec<(Token, Span)>,
+    pos: usize,
+    eof: Span,
+}
+
+impl Parser {
+    /// 对源码做词法分析并创建语法分析器
+    pub fn new(source: &str) -> Result<Self, Diagnostic> {
+        let tokens = Lexer::new(source).tokenize()?;
+        let eof = Span::from_range(source, source.len()..source.len());
+
+        Ok(Self { tokens, pos: 0, eof })
+    }
+
+    /// 解析整个文件
+    pub fn parse_file(&mut self) -> Result<IdlFile, Diagnostic> {
+        let mut file = IdlFile::default();
+
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Service | Token::Interface => file.services.push(self.parse_service()?),
+                Token::Struct => file.structs.push(self.parse_struct()?),
+                Token::Enum => file.enums.push(self.parse_enum()?),
+                _ => return Err(self.unexpected("`service`, `struct` or `enum`")),
+            }
+        }
+
+        Ok(file)
+    }
+
+    fn parse_type_name(&mut self, what: &str) -> Result<(String, Span), Diagnostic> {
+        let (name, span) = self.expect_identifier(what)?;
+
+        if BUILTIN_TYPES.contains(&name.as_str()) {
+            return Err(Diagnostic::new(format!("`{}` is a built-in type", name), span));
+        }
+        Ok((name, span))
+    }
+
+    fn parse_struct(&mut self) -> Result<StructDef, Diagnostic> {
+        self.advance();
+        let (name, span) = self.parse_type_name("struct name")?;
+        self.expect(Token::LBrace)?;
+
+        let mut fields = Vec::new();
+        while self.peek() != Some(&Token::RBrace) {
+            let (field_name, field_span) = self.expect_identifier("field name")?;
+            self.expect(Token::Colon)?;
+            let (field_type, type_span) = self.parse_value_type()?;
+            fields.push(FieldDef {
+                name: field_name,
+                field_type,
+                span: field_span,
+                type_span,
+            });
+
+            if self.peek() == Some(&Token::Comma) {
+                self.advance();
+            } else if self.peek() != Some(&Token::RBrace) {
+                return Err(self.unexpected("`,` or `}`"));
+            }
+        }
+        self.expect(Token::RBrace)?;
+
+        Ok(StructDef { name, fields, span })
+    }
+
+    fn parse_enum(&mut self) -> Result<EnumDef, Diagnostic> {
+        self.advance();
+        let (name, span) = self.parse_type_name("enum name")?;
+        self.expect(Token::LBrace)?;
+
+        let mut variants = Vec::new();
+        while self.peek() != Some(&Token::RBrace) {
+            let (variant_name, variant_span) = self.expect_identifier("enum variant")?;
+            let value = if self.peek() == Some(&Token::Equals) {
+                self.advance();
+                match self.tokens.get(self.pos) {
+                    Some((Token::Number(value), _)) => {
+                        let value = *value;
+                        self.pos += 1;
+                        Some(value)
+                    }
+                    _ => return Err(self.unexpected("number")),
+                }
+            } else {
+                None
+            };
+            variants.push(EnumVariant {
+                name: variant_name,
+                value,
+                span: variant_span,
+            });
+
+            if self.peek() == Some(&Token::Comma) {
+                self.advance();
+            } else if self.peek() != Some(&Token::RBrace) {
+                return Err(self.unexpected("`,` or `}`"));
+            }
+        }
+        self.expect(Token::RBrace)?;
+
+        Ok(EnumDef { name, variants, span })
+    }
+
+    fn parse_service(&mut self) -> Result<ServiceDef, Diagnostic> {
+        self.advance();
+        let (name, span) = self.expect_identifier("service name")?;
+        self.expect(Token::LBrace)?;
+
+        let mut methods = Vec::new();
+        let mut events = Vec::new();
+        while self.peek() != Some(&Token::RBrace) {
+            match self.peek() {
+                None => return Err(self.unexpected("`}`")),
+                Some(Token::Event) => events.push(self.parse_event()?),
+                Some(_) => methods.push(self.parse_method()?),
+            }
+        }
+        self.expect(Token::RBrace)?;
+
+        Ok(ServiceDef { name, methods, events, span })
+    }
+
+    fn parse_method(&mut self) -> Result<MethodDef, Diagnostic> {
+        // 方法名前的`oneway`、`idempotent`标记
+        let (mut oneway, mut idempotent) = (false, false);
+        loop {
+            match self.peek() {
+                Some(Token::Oneway) => oneway = true,
+                Some(Token::Idempotent) => idempotent = true,
+                _ => break,
+            }
+            self.advance();
+        }
+
+        let (name, span) = self.expect_identifier("method name")?;
+        let params = self.parse_params(true)?;
+
+        let (return_type, return_span) = if self.peek() == Some(&Token::Arrow) {
+            self.advance();
+            self.parse_type()?
+        } else {
+            (TypeDef::Void, span)
+        };
+        self.expect(Token::Semicolon)?;
+
+        Ok(MethodDef {
+            name,
+            params,
+            return_type,
+            span,
+            return_span,
+            oneway,
+            idempotent,
+        })
+    }
+
+    fn parse_event(&mut self) -> Result<EventDef, Diagnostic> {
+        self.advance();
+        let (name, span) = self.expect_identifier("event name")?;
+        let params = self.parse_params(false)?;
+        self.expect(Token::Semicolon)?;
+
+        Ok(EventDef { name, params, span })
+    }
+
+    /// 解析括号内的参数列表，`allow_stream`为false时拒绝流参数
+    fn parse_params(&mut self, allow_stream: bool) -> Result<Vec<ParamDef>, Diagnostic> {
+        self.expect(Token::LParen)?;
+
+        let mut params = Vec::new();
+        while self.peek() != Some(&Token::RParen) {
+            let (param_name, param_span) = self.expect_identifier("parameter name")?;
+            self.expect(Token::Colon)?;
+            let (param_type, type_span) = if allow_stream {
+                self.parse_param_type()?
+            } else {
+                self.parse_value_type()?
+            };
+            params.push(ParamDef {
+                name: param_name,
+                param_type,
+                span: param_span,
+                type_span,
+            });
+
+            if self.peek() == Some(&Token::Comma) {
+                self.advance();
+            } else if self.peek() != Some(&Token::RParen) {
+                return Err(self.unexpected("`,` or `)`"));
+            }
+        }
+        self.expect(Token::RParen)?;
+
+        Ok(params)
+    }
+
+    /// 解析类型，返回的位置覆盖整个类型表达式
+    fn parse_type(&mut self) -> Result<(TypeDef, Span), Diagnostic> {
+        let (name, start) = self.expect_identifier("type")?;
+
+        let type_def = match name.as_str() {
+            "void" => TypeDef::Void,
+            "bool" => TypeDef::Bool,
+            "i32" => TypeDef::I32,
+            "i64" => TypeDef::I64,
+            "u32" => TypeDef::U32,
+            "u64" => TypeDef::U64,
+            "f32" => TypeDef::F32,
+            "f64" => TypeDef::F64,
+            "string" => TypeDef::String,
+            "bytes" => TypeDef::Bytes,
+            "array" => {
+                self.expect(Token::LAngle)?;
+                let (inner, _) = self.parse_value_type()?;
+                self.expect(Token::RAngle)?;
+                TypeDef::Array(Box::new(inner))
+            }
+            "map" => {
+                self.expect(Token::LAngle)?;
+                let (key, _) = self.parse_value_type()?;
+                self.expect(Token::Comma)?;
+                let (value, _) = self.parse_value_type()?;
+                self.expect(Token::RAngle)?;
+                TypeDef::Map(Box::new(key), Box::new(value))
+            }
+            "stream" => {
+                self.expect(Token::LAngle)?;
+                let (inner, _) = self.parse_value_type()?;
+                self.expect(Token::RAngle)?;
+                TypeDef::Stream(Box::new(inner))
+            }
+            _ => TypeDef::Custom(name),
+        };
+
+        let end = self.tokens[self.pos - 1].1;
+        Ok((type_def, Span { end: end.end, ..start }))
+    }
+
+    /// 解析参数类型，`void`只能作为返回类型
+    fn parse_param_type(&mut self) -> Result<(TypeDef, Span), Diagnostic> {
+        let (type_def, span) = self.parse_type()?;
+        if type_def == TypeDef::Void {
+            return Err(Diagnostic::new("`void` is only allowed as a return type", span));
+        }
+        Ok((type_def, span))
+    }
+
+    /// 解析字段或泛型实参类型，`stream`只能直接用作参数或返回类型
+    fn parse_value_type(&mut self) -> Result<(TypeDef, Span), Diagnostic> {
+        let (type_def, span) = self.parse_param_type()?;
+        if type_def.is_stream() {
+            return Err(Diagnostic::new(
+                "`stream` is only allowed as a method parameter or return type",
+                span,
+            ));
+        }
+        Ok((type_def, span))
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn current_span(&self) -> Span {
+        self.tokens.get(self.pos).map(|(_, span)| *span).unwrap_or(self.eof)
+    }
+
+    fn advance(&mut self) -> Option<(Token, Span)> {
+        let token = self.tokens.get(self.pos).cloned();
+        if token.is_some() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<Span, Diagnostic> {
+        if self.peek() == Some(&expected) {
+            let (_, span) = self.advance().expect("peeked token");
+            Ok(span)
+        } else {
+            Err(self.unexpected(&expected.to_string()))
+        }
+    }
+
+    fn expect_identifier(&mut self, what: &str) -> Result<(String, Span), Diagnostic> {
+        match self.tokens.get(self.pos) {
+            Some((Token::Identifier(name), span)) => {
+                let result = (name.clone(), *span);
+                self.pos += 1;
+                Ok(result)
+            }
+            _ => Err(self.unexpected(what)),
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> Diagnostic {
+        let found = match self.peek() {
+            Some(token) => token.to_string(),
+            None => "end of file".to_string(),
+        };
+        Diagnostic::new(format!("expected {}, found {}", expected, found), self.current_span())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ast::UserType;
+    use crate::parser::ast::MethodKind;
+
+    #[test]
+    fn test_parse_camera_service() {
+        let source = include_str!("../../../examples/camera-service/camera_service.idl");
+        let file = parse(source).unwrap();
+
+        assert_eq!(file.services.len(), 1);
+        let service = &file.services[0];
+        assert_eq!(service.name, "CameraService");
+
+        let names: Vec<&str> = service.methods.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "open", "close", "capture", "list_cameras", "set_parameter", "get_camera_info",
+                "set_pixel_format", "set_flash",
+            ]
+        );
+        assert!(service.methods.iter().all(|m| m.oneway == (m.name == "set_flash")));
+
+        let open = &service.methods[0];
+        assert_eq!(open.params[0].name, "camera_id");
+        assert_eq!(open.params[0].param_type, TypeDef::I32);
+        assert_eq!(open.return_type, TypeDef::Bool);
+
+        assert_eq!(
+            service.methods[3].return_type,
+            TypeDef::Array(Box::new(TypeDef::String))
+        );
+        assert_eq!(service.methods[4].params.len(), 2);
+        assert_eq!(
+            service.methods[5].return_type,
+            TypeDef::Custom("CameraInfo".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_struct_and_enum() {
+        let source = "service S { get() -> Info; }\n\
+                      struct Info { id: i32, format: Format, tags: array<string>, }\n\
+                      enum Format { Rgb = 1, Yuv, Jpeg = 10 }";
+        let file = parse(source).unwrap();
+
+        let info = &file.structs[0];
+        assert_eq!(info.name, "Info");
+        assert_eq!(info.fields[1].name, "format");
+        assert_eq!(info.fields[1].field_type, TypeDef::Custom("Format".to_string()));
+
+        let format = &file.enums[0];
+        let variants: Vec<(&str, Option<i64>)> = format
+            .variants
+            .iter()
+            .map(|v| (v.name.as_str(), v.value))
+            .collect();
+        assert_eq!(variants, [("Rgb", Some(1)), ("Yuv", None), ("Jpeg", Some(10))]);
+
+        assert!(matches!(file.resolve("Info"), Some(UserType::Struct(_))));
+        assert!(matches!(file.resolve("Format"), Some(UserType::Enum(_))));
+        assert!(file.resolve("Missing").is_none());
+    }
+
+    #[test]
+    fn test_parse_type_declaration_errors() {
+        let err = parse("struct string {}").unwrap_err();
+        assert_eq!(err.message, "`string` is a built-in type");
+
+        let err = parse("enum E { A = B }").unwrap_err();
+        assert_eq!(err.message, "expected number, found identifier `B`");
+
+        let err = parse("struct P { x: i32 y: i32 }").unwrap_err();
+        assert_eq!(err.message, "expected `,` or `}`, found identifier `y`");
+    }
+
+    #[test]
+    fn test_parse_spans() {
+        let source = "service S {\n    get(ids: map<string, i32>) -> Info;\n    ping();\n}";
+        let file = parse(source).unwrap();
+        let method = &file.services[0].methods[0];
+
+        assert_eq!((method.span.line, method.span.column), (2, 5));
+        let param = &method.params[0];
+        assert_eq!(&source[param.type_span.start..param.type_span.end], "map<string, i32>");
+        assert_eq!((param.type_span.line, param.type_span.column), (2, 14));
+        assert_eq!(&source[method.return_span.start..method.return_span.end], "Info");
+
+        let ping = &file.services[0].methods[1];
+        assert_eq!(ping.return_span, ping.span);
+    }
+
+    #[test]
+    fn test_parse_nested_generics() {
+        let file = parse("service S { get(ids: array<u64>,) -> map<string, array<bytes>>; ping(); }").unwrap();
+        let methods = &file.services[0].methods;
+
+        assert_eq!(methods[0].params[0].param_type, TypeDef::Array(Box::new(TypeDef::U64)));
+        assert_eq!(
+            methods[0].return_type,
+            TypeDef::Map(
+                Box::new(TypeDef::String),
+                Box::new(TypeDef::Array(Box::new(TypeDef::Bytes)))
+            )
+        );
+        assert_eq!(methods[1].return_type, TypeDef::Void);
+    }
+
+    #[test]
+    fn test_parse_error_span() {
+        let err = parse("service S {\n    open() -> bool\n}").unwrap_err();
+        assert_eq!(err.message, "expected `;`, found `}`");
+        assert_eq!((err.span.line, err.span.column), (3, 1));
+
+        let err = parse("service S {\n    open(id i32);\n}").unwrap_err();
+        assert_eq!(err.message, "expected `:`, found identifier `i32`");
+        assert_eq!((err.span.line, err.span.column), (2, 13));
+
+        let err = parse("service S { open(").unwrap_err();
+        assert_eq!(err.message, "expected parameter name, found end of file");
+
+        let err = parse("service S { get() -> array<void>; }").unwrap_err();
+        assert!(err.message.contains("void"));
+
+        let err = parse("service S { set(v: void); }").unwrap_err();
+        assert_eq!((err.span.line, err.span.column), (1, 20));
+    }
+
+    #[test]
+    fn test_parse_streams() {
+        let file = parse(
+            "service S {\n\
+             \x20   preview(fps: i32) -> stream<bytes>;\n\
+             \x20   upload(name: string, chunks: stream<bytes>) -> u64;\n\
+             \x20   chat(messages: stream<string>) -> stream<string>;\n\
+             }",
+        )
+        .unwrap();
+        let methods = &file.services[0].methods;
+
+        assert_eq!(methods[0].return_type, TypeDef::Stream(Box::new(TypeDef::Bytes)));
+        assert_eq!(methods[0].kind(), MethodKind::ServerStreaming);
+        assert_eq!(methods[1].kind(), MethodKind::ClientStreaming);
+        assert_eq!(methods[1].stream_param().unwrap().name, "chunks");
+        assert_eq!(methods[2].kind(), MethodKind::Bidirectional);
+
+        let err = parse("struct S { s: stream<i32> }").unwrap_err();
+        assert_eq!(err.message, "`stream` is only allowed as a method parameter or return type");
+
+        let err = parse("service S { get() -> array<stream<i32>>; }").unwrap_err();
+        assert_eq!((err.span.line, err.span.column), (1, 28));
+
+        let err = parse("service S { get() -> stream<stream<i32>>; }").unwrap_err();
+        assert!(err.message.contains("stream"));
+    }
+
+    #[test]
+    fn test_parse_oneway_and_events() {
+        let file = parse(
+            "service RemoteControl {\n\
+             \x20   oneway send_key(code: i32, pressed: bool);\n\
+             \x20   event disconnected(camera_id: i32, reason: string);\n\
+             \x20   event reset();\n\
+             }",
+        )
+        .unwrap();
+        let service = &file.services[0];
+
+        assert_eq!(service.methods.len(), 1);
+        assert!(service.methods[0].oneway);
+        assert_eq!(service.methods[0].kind(), MethodKind::Oneway);
+        assert_eq!(service.events.len(), 2);
+        assert_eq!(service.events[0].name, "disconnected");
+        assert_eq!(service.events[0].params[1].param_type, TypeDef::String);
+        assert!(service.events[1].params.is_empty());
+
+        let err = parse("service S { event e(frames: stream<bytes>); }").unwrap_err();
+        assert_eq!(err.message, "`stream` is only allowed as a method parameter or return type");
+
+        let err = parse("service S { event e() -> i32; }").unwrap_err();
+        assert_eq!(err.message, "expected `;`, found `->`");
+    }
+
+    #[test]
+    fn test_parse_idempotent_methods() {
+        let file = parse(
+            "service CameraService {\n\
+             \x20   idempotent get_camera_info(camera_id: i32) -> CameraInfo;\n\
+             \x20   capture() -> bytes;\n\
+             }",
+        )
+        .unwrap();
+        let methods = &file.services[0].methods;
+
+        assert!(methods[0].idempotent);
+        assert_eq!(methods[0].name, "get_camera_info");
+        assert_eq!(methods[0].kind(), MethodKind::Unary);
+        assert!(!methods[1].idempotent);
+
+        let err = parse("service S { idempotent event e(); }").unwrap_err();
+        assert_eq!(err.message, "expected method name, found `event`");
+    }
+}
@@ -0,0 +1,443 @@
+//! 语义检查
+//!
+//! 在解析之后、代码生成之前检查AST，一次报告所有问题：重复定义、未知类型引用、
+//! 不可哈希的map键、结构体循环包含、枚举值冲突和越界、流参数位置以及Rust/C#/C++
+//! 保留字。
+
+use super::ast::{IdlFile, MethodKind, TypeDef, UserType};
+use super::diagnostic::{Diagnostic, Report, Span};
+use std::collections::{HashMap, HashSet};
+
+/// Rust关键字（含保留关键字）
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe",
+    "use", "where", "while", "abstract", "become", "box", "do", "final", "macro", "override", "priv",
+    "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// C#关键字
+const CSHARP_KEYWORDS: &[&str] = &[
+    "abstract", "as", "base", "bool", "break", "byte", "case", "catch", "char", "checked", "class",
+    "const", "continue", "decimal", "default", "delegate", "do", "double", "else", "enum", "event",
+    "explicit", "extern", "false", "finally", "fixed", "float", "for", "foreach", "goto", "if",
+    "implicit", "in", "int", "interface", "internal", "is", "lock", "long", "namespace", "new", "null",
+    "object", "operator", "out", "override", "params", "private", "protected", "public", "readonly",
+    "ref", "return", "sbyte", "sealed", "short", "sizeof", "stackalloc", "static", "string", "struct",
+    "switch", "this", "throw", "true", "try", "typeof", "uint", "ulong", "unchecked", "unsafe",
+    "ushort", "using", "virtual", "void", "volatile", "while",
+];
+
+/// C++关键字
+const CPP_KEYWORDS: &[&str] = &[
+    "alignas", "alignof", "and", "and_eq", "asm", "auto", "bitand", "bitor", "bool", "break", "case",
+    "catch", "char", "char8_t", "char16_t", "char32_t", "class", "compl", "concept", "const",
+    "consteval", "constexpr", "constinit", "const_cast", "continue", "co_await", "co_return",
+    "co_yield", "decltype", "default", "delete", "do", "double", "dynamic_cast", "else", "enum",
+    "explicit", "export", "extern", "false", "float", "for", "friend", "goto", "if", "inline", "int",
+    "long", "mutable", "namespace", "new", "noexcept", "not", "not_eq", "nullptr", "operator", "or",
+    "or_eq", "private", "protected", "public", "register", "reinterpret_cast", "requires", "return",
+    "short", "signed", "sizeof", "static", "static_assert", "static_cast", "struct", "switch",
+    "template", "this", "thread_local", "throw", "true", "try", "typedef", "typeid", "typename",
+    "union", "unsigned", "using", "virtual", "void", "volatile", "wchar_t", "while", "xor", "xor_eq",
+];
+
+/// 检查IDL文件，返回发现的所有问题
+pub fn validate(file: &IdlFile) -> Result<(), Report> {
+    let mut validator = Validator {
+        file,
+        report: Report::default(),
+    };
+    validator.check_file();
+    validator.report.into_result()
+}
+
+struct Validator<'a> {
+    file: &'a IdlFile,
+    report: Report,
+}
+
+impl<'a> Validator<'a> {
+    fn check_file(&mut self) {
+        let file = self.file;
+
+        let type_names = file
+            .structs
+            .iter()
+            .map(|s| (s.name.as_str(), s.span))
+            .chain(file.enums.iter().map(|e| (e.name.as_str(), e.span)));
+        self.check_unique("type", type_names);
+        self.check_unique("service", file.services.iter().map(|s| (s.name.as_str(), s.span)));
+
+        for def in &file.structs {
+            self.check_name(&def.name, def.span);
+            self.check_unique("field", def.fields.iter().map(|f| (f.name.as_str(), f.span)));
+            for field in &def.fields {
+                self.check_name(&field.name, field.span);
+                self.check_type(&field.field_type, field.type_span);
+            }
+
+            let mut path = Vec::new();
+            if self.find_cycle(&def.name, &def.name, &mut path, &mut HashSet::new()) {
+                self.error(
+                    format!("struct `{}` contains itself through `{}`", def.name, path.join(" -> ")),
+                    def.span,
+                );
+            }
+        }
+
+        for def in &file.enums {
+            self.check_name(&def.name, def.span);
+            self.check_unique("variant", def.variants.iter().map(|v| (v.name.as_str(), v.span)));
+
+            // 未指定值的成员取前一个成员的值加一，与Rust/C#/C++规则一致
+            let mut used: HashMap<i64, &str> = HashMap::new();
+            let mut next = 0;
+            for variant in &def.variants {
+                self.check_name(&variant.name, variant.span);
+                let value = variant.value.unwrap_or(next);
+                // 生成的枚举在三种语言中都以int32为底层类型
+                if i32::try_from(value).is_err() {
+                    self.error(
+                        format!("value `{}` of `{}` is out of range for i32", value, variant.name),
+                        variant.span,
+                    );
+                } else if let Some(previous) = used.get(&value) {
+                    self.error(
+                        format!("value `{}` of `{}` is already used by `{}`", value, variant.name, previous),
+                        variant.span,
+                    );
+                } else {
+                    used.insert(value, &variant.name);
+                }
+                next = value.saturating_add(1);
+            }
+        }
+
+        for service in &file.services {
+            self.check_name(&service.name, service.span);
+            self.check_unique("method", service.methods.iter().map(|m| (m.name.as_str(), m.span)));
+
+            for method in &service.methods {
+                self.check_name(&method.name, method.span);
+                self.check_unique("parameter", method.params.iter().map(|p| (p.name.as_str(), p.span)));
+                for (index, param) in method.params.iter().enumerate() {
+                    self.check_name(&param.name, param.span);
+                    self.check_type(&param.param_type, param.type_span);
+                    // 非流参数随打开流的帧一起发送，流参数只能有一个且位于最后
+                    if param.param_type.is_stream() && index + 1 != method.params.len() {
+                        self.error(
+                            format!("stream parameter `{}` must be the last parameter", param.name),
+                            param.type_span,
+                        );
+                    }
+                }
+                self.check_type(&method.return_type, method.return_span);
+
+                if method.oneway {
+                    if method.return_type != TypeDef::Void {
+                        self.error(
+                            format!("oneway method `{}` cannot return a value", method.name),
+                            method.return_span,
+                        );
+                    }
+                    if let Some(param) = method.params.iter().find(|p| p.param_type.is_stream()) {
+                        self.error(
+                            format!("oneway method `{}` cannot take a stream parameter", method.name),
+                            param.type_span,
+                        );
+                    }
+                }
+                // 只有等待响应的普通调用才能重试
+                if method.idempotent && method.kind() != MethodKind::Unary {
+                    self.error(
+                        format!("idempotent method `{}` cannot be oneway or streaming", method.name),
+                        method.span,
+                    );
+                }
+            }
+
+            self.check_unique("event", service.events.iter().map(|e| (e.name.as_str(), e.span)));
+            for event in &service.events {
+                self.check_name(&event.name, event.span);
+                self.check_unique("parameter", event.params.iter().map(|p| (p.name.as_str(), p.span)));
+                for param in &event.params {
+                    self.check_name(&param.name, param.span);
+                    self.check_type(&param.param_type, param.type_span);
+                }
+            }
+        }
+    }
+
+    /// 同一作用域内的名称不能重复，在重复出现的位置报告
+    fn check_unique<'n>(&mut self, what: &str, names: impl Iterator<Item = (&'n str, Span)>) {
+        let mut seen: Vec<&str> = Vec::new();
+        for (name, span) in names {
+            if seen.contains(&name) {
+                self.error(format!("{} `{}` is defined multiple times", what, name), span);
+            } else {
+                seen.push(name);
+            }
+        }
+    }
+
+    /// 查找从`current`出发、经直接字段回到`start`的路径
+    ///
+    /// 这样的结构体在Rust中大小无限、在C++中类型不完整；`array`和`map`在堆上
+    /// 存放元素，可以打断循环。
+    fn find_cycle(&self, start: &str, current: &str, path: &mut Vec<String>, visited: &mut HashSet<String>) -> bool {
+        let Some(UserType::Struct(def)) = self.file.resolve(current) else {
+            return false;
+        };
+        for field in &def.fields {
+            let TypeDef::Custom(name) = &field.field_type else {
+                continue;
+            };
+            path.push(format!("{}.{}", current, field.name));
+            if name == start || (visited.insert(name.clone()) && self.find_cycle(start, name, path, visited)) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    fn check_name(&mut self, name: &str, span: Span) {
+        let languages: Vec<&str> = [("Rust", RUST_KEYWORDS), ("C#", CSHARP_KEYWORDS), ("C++", CPP_KEYWORDS)]
+            .iter()
+            .filter(|(_, keywords)| keywords.contains(&name))
+            .map(|(language, _)| *language)
+            .collect();
+
+        if !languages.is_empty() {
+            self.error(
+                format!("`{}` is a reserved word in {}", name, languages.join(", ")),
+                span,
+            );
+        }
+    }
+
+    fn check_type(&mut self, type_def: &TypeDef, span: Span) {
+        match type_def {
+            TypeDef::Custom(name) if self.file.resolve(name).is_none() => {
+                self.error(format!("unknown type `{}`", name), span);
+            }
+            TypeDef::Array(inner) | TypeDef::Stream(inner) => self.check_type(inner, span),
+            TypeDef::Map(key, value) => {
+                self.check_type(key, span);
+                if !self.is_hashable(key) {
+                    self.error(format!("`{}` cannot be used as a map key", key), span);
+                }
+                self.check_type(value, span);
+            }
+            _ => {}
+        }
+    }
+
+    /// map键需要在三种语言中都能比较和哈希：整数、布尔、字符串和枚举
+    fn is_hashable(&self, type_def: &TypeDef) -> bool {
+        match type_def {
+            TypeDef::Bool
+            | TypeDef::I32
+            | TypeDef::I64
+            | TypeDef::U32
+            | TypeDef::U64
+            | TypeDef::String => true,
+            // 未知类型已单独报告
+            TypeDef::Custom(name) => !matches!(self.file.resolve(name), Some(UserType::Struct(_))),
+            _ => false,
+        }
+    }
+
+    fn error(&mut self, message: String, span: Span) {
+        self.report.push(Diagnostic::new(message, span));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn check(source: &str) -> Vec<String> {
+        let file = parse(source).unwrap();
+        match validate(&file) {
+            Ok(()) => Vec::new(),
+            Err(report) => report.diagnostics.into_iter().map(|d| d.message).collect(),
+        }
+    }
+
+    #[test]
+    fn test_validate_camera_service() {
+        let source = include_str!("../../../examples/camera-service/camera_service.idl");
+        assert!(validate(&parse(source).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_validate_duplicates() {
+        let messages = check(
+            "service S {\n    get(id: i32, id: i32) -> bool;\n    get() -> bool;\n}\n\
+             struct A { x: i32, x: i64 }\n\
+             enum A { B, C, C }",
+        );
+        assert_eq!(
+            messages,
+            [
+                "type `A` is defined multiple times",
+                "field `x` is defined multiple times",
+                "variant `C` is defined multiple times",
+                "method `get` is defined multiple times",
+                "parameter `id` is defined multiple times",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_types() {
+        let messages = check(
+            "struct Key { id: i32 }\nenum Kind { A }\n\
+             service S {\n\
+             \x20   a(m: map<f32, i32>) -> map<bytes, Missing>;\n\
+             \x20   b(m: map<Kind, array<map<Key, i32>>>);\n\
+             }",
+        );
+        assert_eq!(
+            messages,
+            [
+                "`f32` cannot be used as a map key",
+                "`bytes` cannot be used as a map key",
+                "unknown type `Missing`",
+                "`Key` cannot be used as a map key",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_stream_params() {
+        let messages = check(
+            "service S {\n\
+             \x20   a(x: stream<i32>, y: stream<Missing>);\n\
+             \x20   b(name: string, chunks: stream<bytes>) -> stream<bool>;\n\
+             }",
+        );
+        assert_eq!(
+            messages,
+            ["stream parameter `x` must be the last parameter", "unknown type `Missing`"]
+        );
+    }
+
+    #[test]
+    fn test_validate_oneway_and_events() {
+        let messages = check(
+            "service S {\n\
+             \x20   oneway key(code: i32) -> bool;\n\
+             \x20   oneway upload(chunks: stream<bytes>);\n\
+             \x20   oneway press(code: i32);\n\
+             \x20   event moved(x: i32, x: i32);\n\
+             \x20   event moved(info: Missing);\n\
+             }",
+        );
+        assert_eq!(
+            messages,
+            [
+                "oneway method `key` cannot return a value",
+                "oneway method `upload` cannot take a stream parameter",
+                "event `moved` is defined multiple times",
+                "parameter `x` is defined multiple times",
+                "unknown type `Missing`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_idempotent_methods() {
+        let messages = check(
+            "service S {\n\
+             \x20   idempotent get(id: i32) -> bool;\n\
+             \x20   idempotent oneway press(code: i32);\n\
+             \x20   idempotent preview() -> stream<bytes>;\n\
+             }",
+        );
+        assert_eq!(
+            messages,
+            [
+                "idempotent method `press` cannot be oneway or streaming",
+                "idempotent method `preview` cannot be oneway or streaming",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_reserved_words() {
+        let messages = check("struct Info { type: i32, object: string, delete: bool }\nservice S { match(); }");
+        assert_eq!(
+            messages,
+            [
+                "`type` is a reserved word in Rust",
+                "`object` is a reserved word in C#",
+                "`delete` is a reserved word in C++",
+                "`match` is a reserved word in Rust",
+            ]
+        );
+        assert_eq!(
+            check("enum E { virtual }"),
+            ["`virtual` is a reserved word in Rust, C#, C++"]
+        );
+    }
+
+    #[test]
+    fn test_validate_enum_values() {
+        let messages = check("enum E { A = 1, B, C = 2, D = 0, E = 1 }");
+        assert_eq!(
+            messages,
+            [
+                "value `2` of `C` is already used by `B`",
+                "value `1` of `E` is already used by `A`",
+            ]
+        );
+
+        // 显式和隐式的值都必须在i32范围内
+        assert_eq!(
+            check("enum E { A = 3000000000, B = 1 }"),
+            ["value `3000000000` of `A` is out of range for i32"]
+        );
+        assert_eq!(
+            check("enum E { A = 2147483647, B }"),
+            ["value `2147483648` of `B` is out of range for i32"]
+        );
+        assert!(check("enum E { A, B = 2147483647 }").is_empty());
+    }
+
+    #[test]
+    fn test_validate_struct_cycles() {
+        assert_eq!(
+            check("struct Node { next: Node }"),
+            ["struct `Node` contains itself through `Node.next`"]
+        );
+        assert_eq!(
+            check("struct A { id: i32, b: B }\nstruct B { c: C }\nstruct C { a: A }\nstruct D { a: A }"),
+            [
+                "struct `A` contains itself through `A.b -> B.c -> C.a`",
+                "struct `B` contains itself through `B.c -> C.a -> A.b`",
+                "struct `C` contains itself through `C.a -> A.b -> B.c`",
+            ]
+        );
+
+        // array和map打断循环
+        assert!(check("struct Tree { children: array<Tree>, index: map<string, Tree> }").is_empty());
+    }
+
+    #[test]
+    fn test_validate_spans() {
+        let source = "service S {\n    get(a: i32) -> Missing;\n    get(a: i32, a: i32);\n}";
+        let report = validate(&parse(source).unwrap()).unwrap_err();
+
+        let positions: Vec<(usize, usize)> = report
+            .diagnostics
+            .iter()
+            .map(|d| (d.span.line, d.span.column))
+            .collect();
+        assert_eq!(positions, [(3, 5), (2, 20), (3, 17)]);
+    }
+}
@@ -0,0 +1,79 @@
+//! 命令行测试
+
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+const CAMERA_IDL: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../examples/camera-service/camera_service.idl");
+
+fn compiler(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_softbus-idl-compiler"))
+        .args(args)
+        .output()
+        .expect("failed to run softbus-idl-compiler")
+}
+
+fn write_idl(dir: &Path, source: &str) -> String {
+    let path = dir.join("broken.idl");
+    std::fs::write(&path, source).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_check_valid_idl() {
+    let output = compiler(&["--check", "--input", CAMERA_IDL]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_check_reports_diagnostic() {
+    let temp_dir = TempDir::new().unwrap();
+    let input = write_idl(temp_dir.path(), "service S {\n    open() -> bool\n}\n");
+
+    let output = compiler(&["--check", "--input", &input]);
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("expected `;`, found `}`"));
+    assert!(stderr.contains("broken.idl:3:1"));
+}
+
+#[test]
+fn test_generate_rust() {
+    let temp_dir = TempDir::new().unwrap();
+    let out_dir = temp_dir.path().join("out");
+
+    let output = compiler(&[
+        "--input", CAMERA_IDL,
+        "--output", out_dir.to_str().unwrap(),
+        "--lang", "rust",
+    ]);
+    assert!(output.status.success());
+
+    let code = std::fs::read_to_string(out_dir.join("camera_service.rs")).unwrap();
+    assert!(code.contains("pub trait CameraService"));
+}
+
+#[test]
+fn test_output_required_without_check() {
+    let output = compiler(&["--input", CAMERA_IDL]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_check_reports_all_semantic_errors() {
+    let temp_dir = TempDir::new().unwrap();
+    let input = write_idl(
+        temp_dir.path(),
+        "service S {\n    get(m: map<f64, i32>) -> Missing;\n    get();\n}\n",
+    );
+
+    let output = compiler(&["--check", "--input", &input]);
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("method `get` is defined multiple times"));
+    assert!(stderr.contains("`f64` cannot be used as a map key"));
+    assert!(stderr.contains("unknown type `Missing`"));
+    assert!(stderr.contains("aborting due to 3 previous errors"));
+}
@@ -9,6 +9,8 @@ fn test_rust_codegen() {
     let service = ServiceDef {
         name: "TestService".to_string(),
         methods: vec![],
+        events: vec![],
+        span: Span::default(),
     };
 
     let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,56 @@
+//! 库编译API测试
+
+use softbus_idl_compiler::{compile_idls, Language};
+use tempfile::TempDir;
+
+const CAMERA_IDL: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../examples/camera-service/camera_service.idl");
+
+#[test]
+fn test_compile_idls_to_out_dir() {
+    let temp_dir = TempDir::new().unwrap();
+
+    compile_idls(&[CAMERA_IDL])
+        .out_dir(temp_dir.path())
+        .languages(&[Language::Rust])
+        .emit_rerun_if_changed(false)
+        .compile()
+        .unwrap();
+
+    let code = std::fs::read_to_string(temp_dir.path().join("camera_service.rs")).unwrap();
+    assert!(code.contains("pub struct CameraServiceProxy"));
+}
+
+#[test]
+fn test_compile_idls_reports_diagnostic() {
+    let temp_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("broken.idl");
+    std::fs::write(&input, "service S {\n    open(id i32);\n}\n").unwrap();
+
+    let err = compile_idls(&[&input])
+        .out_dir(temp_dir.path())
+        .emit_rerun_if_changed(false)
+        .compile()
+        .unwrap_err();
+
+    let message = err.to_string();
+    assert!(message.contains("expected `:`, found identifier `i32`"));
+    assert!(message.contains("broken.idl:2:13"));
+    assert!(!temp_dir.path().join("s.rs").exists());
+}
+
+#[test]
+fn test_compile_idls_writes_types_for_all_languages() {
+    let temp_dir = TempDir::new().unwrap();
+
+    compile_idls(&[CAMERA_IDL])
+        .out_dir(temp_dir.path())
+        .languages(&Language::ALL)
+        .emit_rerun_if_changed(false)
+        .compile()
+        .unwrap();
+
+    for name in ["camera_service_types.rs", "CameraServiceTypes.cs", "camera_service_types.h"] {
+        let code = std::fs::read_to_string(temp_dir.path().join(name)).unwrap();
+        assert!(code.contains("CameraInfo"), "{} is missing CameraInfo", name);
+    }
+}
@@ -0,0 +1,168 @@
+//! 用系统g++编译生成的C++代码
+//!
+//! 未安装g++时跳过。测试程序通过内存通道扮演服务端，验证代理发出的
+//! `RpcRequest`帧和对返回值的解码。
+
+use softbus_idl_compiler::{compile_idls, Language};
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+const CAMERA_IDL: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../examples/camera-service/camera_service.idl");
+
+const ROUNDTRIP_MAIN: &str = r#"
+#include "camera_service.h"
+
+#include <cstdio>
+#include <deque>
+
+using namespace softbus::generated;
+
+// 按方法名回放应答的服务端
+class FakeServer : public RpcChannel {
+public:
+    void send(const std::vector<uint8_t>& frame) override {
+        RpcRequest request = RpcRequest::decode(frame);
+        if (request.frame_type == FrameType::Notification) {
+            // 单向调用没有请求ID，也不回复
+            BincodeReader params(request.payload);
+            notified = request.request_id.empty() && request.method_name == "set_flash" && params.read_bool();
+            return;
+        }
+
+        RpcResponse response;
+        response.request_id = request.request_id;
+        response.success = true;
+
+        BincodeReader params(request.payload);
+        BincodeWriter result;
+        if (request.service_name != "CameraService") {
+            response.success = false;
+            response.error_code = RpcErrorCode::ServiceNotFound;
+        } else if (request.method_name == "open") {
+            result.write_bool(params.read_i32() == 7);
+        } else if (request.method_name == "get_camera_info") {
+            CameraInfo info;
+            info.id = params.read_i32();
+            info.name = "front";
+            info.resolution.width = 1920;
+            info.resolution.height = 1080;
+            info.formats = {PixelFormat::Yuv420, PixelFormat::Jpeg};
+            CameraInfoCodec::encode(result, info);
+        } else if (request.method_name == "set_pixel_format") {
+            params.read_i32();
+            result.write_bool(PixelFormatCodec::decode(params) == PixelFormat::Nv21);
+        } else {
+            response.success = false;
+            response.error_code = RpcErrorCode::MethodNotFound;
+            response.error_message = "no such method";
+        }
+        response.payload = result.take();
+
+        // 先放一个过期响应，客户端应丢弃
+        RpcResponse stale;
+        stale.request_id = "stale";
+        stale.success = true;
+        frames_.push_back(stale.encode());
+        frames_.push_back(response.encode());
+    }
+
+    std::vector<uint8_t> receive() override {
+        std::vector<uint8_t> frame = frames_.front();
+        frames_.pop_front();
+        return frame;
+    }
+
+    bool notified = false;
+
+private:
+    std::deque<std::vector<uint8_t>> frames_;
+};
+
+#define CHECK(cond) \
+    do { \
+        if (!(cond)) { \
+            std::fprintf(stderr, "check failed: %s\n", #cond); \
+            return 1; \
+        } \
+    } while (0)
+
+int main() {
+    auto server = std::make_shared<FakeServer>();
+    auto client = std::make_shared<RpcClient>(server);
+    CameraServiceProxy proxy(client);
+    CameraService& service = proxy;
+
+    CHECK(service.open(7));
+    CHECK(!service.open(8));
+
+    CameraInfo info = service.get_camera_info(3);
+    CHECK(info.id == 3);
+    CHECK(info.name == "front");
+    CHECK(info.resolution.width == 1920 && info.resolution.height == 1080);
+    CHECK(info.formats.size() == 2 && info.formats[1] == PixelFormat::Jpeg);
+
+    CHECK(service.set_pixel_format(0, PixelFormat::Nv21));
+    CHECK(!service.set_pixel_format(0, PixelFormat::Rgb888));
+
+    service.set_flash(true);
+    CHECK(server->notified);
+    CHECK(service.open(7));
+
+    try {
+        service.close();
+        return 1;
+    } catch (const RpcError& error) {
+        CHECK(error.code() == RpcErrorCode::MethodNotFound);
+    }
+    return 0;
+}
+"#;
+
+fn gxx_available() -> bool {
+    Command::new("g++").arg("--version").output().is_ok_and(|output| output.status.success())
+}
+
+fn run(command: &mut Command) {
+    let output = command.output().unwrap();
+    assert!(
+        output.status.success(),
+        "{:?} failed:\n{}{}",
+        command,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+fn generate(dir: &Path) {
+    compile_idls(&[CAMERA_IDL])
+        .out_dir(dir)
+        .languages(&[Language::Cpp])
+        .emit_rerun_if_changed(false)
+        .compile()
+        .unwrap();
+}
+
+#[test]
+fn test_generated_cpp_compiles_and_roundtrips() {
+    if !gxx_available() {
+        eprintln!("g++ not found, skipping");
+        return;
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    generate(dir);
+    std::fs::write(dir.join("main.cpp"), ROUNDTRIP_MAIN).unwrap();
+
+    let binary = dir.join("roundtrip");
+    run(Command::new("g++")
+        .args(["-std=c++17", "-Wall", "-Wextra", "-Werror", "-I"])
+        .arg(dir)
+        .arg(dir.join("softbus_rpc.cpp"))
+        .arg(dir.join("camera_service.cpp"))
+        .arg(dir.join("main.cpp"))
+        .arg("-o")
+        .arg(&binary));
+    run(&mut Command::new(&binary));
+}
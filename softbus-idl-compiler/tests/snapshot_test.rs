@@ -0,0 +1,78 @@
+//! 生成代码快照测试
+//!
+//! 快照保存在`tests/snapshots/<语言>/`下。修改生成器后用
+//! `UPDATE_SNAPSHOTS=1 cargo test -p softbus-idl-compiler --test snapshot_test`
+//! 重新生成，并在提交前检查差异。
+
+use softbus_idl_compiler::{compile_idls, Language};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+const CAMERA_IDL: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../examples/camera-service/camera_service.idl");
+
+fn read_dir(dir: &Path) -> BTreeMap<String, String> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .map(|entry| {
+                    let path = entry.unwrap().path();
+                    let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                    (name, std::fs::read_to_string(&path).unwrap())
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn assert_snapshots(language: Language, snapshot_dir: &str) {
+    let temp_dir = TempDir::new().unwrap();
+    compile_idls(&[CAMERA_IDL])
+        .out_dir(temp_dir.path())
+        .languages(&[language])
+        .emit_rerun_if_changed(false)
+        .compile()
+        .unwrap();
+
+    let generated = read_dir(temp_dir.path());
+    let snapshot_dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "snapshots", snapshot_dir].iter().collect();
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        let _ = std::fs::remove_dir_all(&snapshot_dir);
+        std::fs::create_dir_all(&snapshot_dir).unwrap();
+        for (name, code) in &generated {
+            std::fs::write(snapshot_dir.join(name), code).unwrap();
+        }
+        return;
+    }
+
+    let expected = read_dir(&snapshot_dir);
+    assert_eq!(
+        generated.keys().collect::<Vec<_>>(),
+        expected.keys().collect::<Vec<_>>(),
+        "generated files differ from {}",
+        snapshot_dir.display()
+    );
+    for (name, code) in &generated {
+        assert!(
+            *code == expected[name],
+            "{} differs from its snapshot, rerun with UPDATE_SNAPSHOTS=1 and review the diff",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_rust_snapshot() {
+    assert_snapshots(Language::Rust, "rust");
+}
+
+#[test]
+fn test_csharp_snapshot() {
+    assert_snapshots(Language::CSharp, "csharp");
+}
+
+#[test]
+fn test_cpp_snapshot() {
+    assert_snapshots(Language::Cpp, "cpp");
+}
@@ -0,0 +1,68 @@
+// 由softbus-idl-compiler自动生成，请勿手动修改
+// 服务: CameraService
+
+#include "camera_service.h"
+
+#include <utility>
+
+namespace softbus {
+namespace generated {
+
+CameraServiceProxy::CameraServiceProxy(std::shared_ptr<RpcClient> client) : client_(std::move(client)) {}
+
+bool CameraServiceProxy::open(int32_t camera_id) {
+    BincodeWriter writer;
+    writer.write_i32(camera_id);
+    BincodeReader reader(client_->call(kServiceName, "open", writer.take()));
+    return reader.read_bool();
+}
+
+bool CameraServiceProxy::close() {
+    BincodeWriter writer;
+    BincodeReader reader(client_->call(kServiceName, "close", writer.take()));
+    return reader.read_bool();
+}
+
+std::vector<uint8_t> CameraServiceProxy::capture() {
+    BincodeWriter writer;
+    BincodeReader reader(client_->call(kServiceName, "capture", writer.take()));
+    return reader.read_bytes();
+}
+
+std::vector<std::string> CameraServiceProxy::list_cameras() {
+    BincodeWriter writer;
+    BincodeReader reader(client_->call(kServiceName, "list_cameras", writer.take()));
+    return reader.read_list([](BincodeReader& r1) { return r1.read_string(); });
+}
+
+bool CameraServiceProxy::set_parameter(const std::string& key, const std::string& value) {
+    BincodeWriter writer;
+    writer.write_string(key);
+    writer.write_string(value);
+    BincodeReader reader(client_->call(kServiceName, "set_parameter", writer.take()));
+    return reader.read_bool();
+}
+
+CameraInfo CameraServiceProxy::get_camera_info(int32_t camera_id) {
+    BincodeWriter writer;
+    writer.write_i32(camera_id);
+    BincodeReader reader(client_->call(kServiceName, "get_camera_info", writer.take()));
+    return CameraInfoCodec::decode(reader);
+}
+
+bool CameraServiceProxy::set_pixel_format(int32_t camera_id, const PixelFormat& format) {
+    BincodeWriter writer;
+    writer.write_i32(camera_id);
+    PixelFormatCodec::encode(writer, format);
+    BincodeReader reader(client_->call(kServiceName, "set_pixel_format", writer.take()));
+    return reader.read_bool();
+}
+
+void CameraServiceProxy::set_flash(bool enabled) {
+    BincodeWriter writer;
+    writer.write_bool(enabled);
+    client_->notify(kServiceName, "set_flash", writer.take());
+}
+
+}  // namespace generated
+}  // namespace softbus
@@ -0,0 +1,76 @@
+// 由softbus-idl-compiler自动生成，请勿手动修改
+// 服务: CameraService
+
+#ifndef SOFTBUS_GENERATED_CAMERA_SERVICE_H
+#define SOFTBUS_GENERATED_CAMERA_SERVICE_H
+
+#include <cstdint>
+#include <memory>
+#include <string>
+#include <unordered_map>
+#include <vector>
+
+#include "softbus_rpc.h"
+#include "camera_service_types.h"
+
+namespace softbus {
+namespace generated {
+
+/**
+ * @brief CameraService服务接口
+ */
+class CameraService {
+public:
+    virtual ~CameraService() = default;
+
+    virtual bool open(int32_t camera_id) = 0;
+
+    virtual bool close() = 0;
+
+    virtual std::vector<uint8_t> capture() = 0;
+
+    virtual std::vector<std::string> list_cameras() = 0;
+
+    virtual bool set_parameter(const std::string& key, const std::string& value) = 0;
+
+    virtual CameraInfo get_camera_info(int32_t camera_id) = 0;
+
+    virtual bool set_pixel_format(int32_t camera_id, const PixelFormat& format) = 0;
+
+    virtual void set_flash(bool enabled) = 0;
+};
+
+/**
+ * @brief CameraService客户端代理，通过RpcClient调用远端服务
+ */
+class CameraServiceProxy : public CameraService {
+public:
+    /** @brief 服务名称 */
+    static constexpr const char* kServiceName = "CameraService";
+
+    explicit CameraServiceProxy(std::shared_ptr<RpcClient> client);
+
+    bool open(int32_t camera_id) override;
+
+    bool close() override;
+
+    std::vector<uint8_t> capture() override;
+
+    std::vector<std::string> list_cameras() override;
+
+    bool set_parameter(const std::string& key, const std::string& value) override;
+
+    CameraInfo get_camera_info(int32_t camera_id) override;
+
+    bool set_pixel_format(int32_t camera_id, const PixelFormat& format) override;
+
+    void set_flash(bool enabled) override;
+
+private:
+    std::shared_ptr<RpcClient> client_;
+};
+
+}  // namespace generated
+}  // namespace softbus
+
+#endif // SOFTBUS_GENERATED_CAMERA_SERVICE_H
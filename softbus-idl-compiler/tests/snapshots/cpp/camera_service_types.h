@@ -0,0 +1,115 @@
+// 由softbus-idl-compiler自动生成，请勿手动修改
+// 类型声明: camera_service
+
+#ifndef SOFTBUS_GENERATED_CAMERA_SERVICE_TYPES_H
+#define SOFTBUS_GENERATED_CAMERA_SERVICE_TYPES_H
+
+#include <cstdint>
+#include <string>
+#include <unordered_map>
+#include <vector>
+
+#include "softbus_rpc.h"
+
+namespace softbus {
+namespace generated {
+
+enum class PixelFormat : int32_t {
+    Rgb888 = 0,
+    Yuv420,
+    Nv21,
+    Jpeg,
+};
+
+/**
+ * @brief PixelFormat的bincode编解码，按成员序号传输
+ */
+struct PixelFormatCodec {
+    static void encode(BincodeWriter& writer, PixelFormat value) {
+        switch (value) {
+            case PixelFormat::Rgb888:
+                writer.write_u32(0);
+                return;
+            case PixelFormat::Yuv420:
+                writer.write_u32(1);
+                return;
+            case PixelFormat::Nv21:
+                writer.write_u32(2);
+                return;
+            case PixelFormat::Jpeg:
+                writer.write_u32(3);
+                return;
+        }
+        throw RpcError(RpcErrorCode::SerializationError, "invalid PixelFormat value");
+    }
+
+    static PixelFormat decode(BincodeReader& reader) {
+        switch (reader.read_u32()) {
+            case 0:
+                return PixelFormat::Rgb888;
+            case 1:
+                return PixelFormat::Yuv420;
+            case 2:
+                return PixelFormat::Nv21;
+            case 3:
+                return PixelFormat::Jpeg;
+            default:
+                throw RpcError(RpcErrorCode::SerializationError, "invalid PixelFormat index");
+        }
+    }
+};
+
+struct Resolution {
+    uint32_t width{};
+    uint32_t height{};
+};
+
+/**
+ * @brief Resolution的bincode编解码，按字段声明顺序传输
+ */
+struct ResolutionCodec {
+    static void encode(BincodeWriter& writer, const Resolution& value) {
+        writer.write_u32(value.width);
+        writer.write_u32(value.height);
+    }
+
+    static Resolution decode(BincodeReader& reader) {
+        Resolution value;
+        value.width = reader.read_u32();
+        value.height = reader.read_u32();
+        return value;
+    }
+};
+
+struct CameraInfo {
+    int32_t id{};
+    std::string name;
+    Resolution resolution;
+    std::vector<PixelFormat> formats;
+};
+
+/**
+ * @brief CameraInfo的bincode编解码，按字段声明顺序传输
+ */
+struct CameraInfoCodec {
+    static void encode(BincodeWriter& writer, const CameraInfo& value) {
+        writer.write_i32(value.id);
+        writer.write_string(value.name);
+        ResolutionCodec::encode(writer, value.resolution);
+        writer.write_list(value.formats, [](BincodeWriter& w1, const auto& v1) { PixelFormatCodec::encode(w1, v1); });
+    }
+
+    static CameraInfo decode(BincodeReader& reader) {
+        CameraInfo value;
+        value.id = reader.read_i32();
+        value.name = reader.read_string();
+        value.resolution = ResolutionCodec::decode(reader);
+        value.formats = reader.read_list([](BincodeReader& r1) { return PixelFormatCodec::decode(r1); });
+        return value;
+    }
+};
+
+}  // namespace generated
+}  // namespace softbus
+
+#endif // SOFTBUS_GENERATED_CAMERA_SERVICE_TYPES_H
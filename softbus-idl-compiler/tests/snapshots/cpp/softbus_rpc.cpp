@@ -0,0 +1,402 @@
+// 由softbus-idl-compiler自动生成，请勿手动修改
+// SoftBus RPC运行时实现
+
+#include "softbus_rpc.h"
+
+#include <cstring>
+#include <random>
+
+namespace softbus {
+namespace generated {
+
+namespace {
+
+[[noreturn]] void malformed(const char* what) {
+    throw RpcError(RpcErrorCode::SerializationError, what);
+}
+
+/**
+ * @brief protobuf线格式写入器，省略取默认值的标量字段
+ */
+class ProtoWriter {
+public:
+    void write_varint_field(int field, uint64_t value) {
+        if (value != 0) {
+            write_tag(field, 0);
+            write_varint(value);
+        }
+    }
+
+    void write_string(int field, const std::string& value) {
+        if (!value.empty()) {
+            write_message(field, reinterpret_cast<const uint8_t*>(value.data()), value.size());
+        }
+    }
+
+    void write_bytes(int field, const std::vector<uint8_t>& value) {
+        if (!value.empty()) {
+            write_message(field, value.data(), value.size());
+        }
+    }
+
+    void write_metadata(int field, const std::map<std::string, std::string>& metadata) {
+        for (const auto& entry : metadata) {
+            ProtoWriter item;
+            item.write_string(1, entry.first);
+            item.write_string(2, entry.second);
+            std::vector<uint8_t> bytes = item.take();
+            write_message(field, bytes.data(), bytes.size());
+        }
+    }
+
+    std::vector<uint8_t> take() { return std::move(buffer_); }
+
+private:
+    void write_message(int field, const uint8_t* data, size_t size) {
+        write_tag(field, 2);
+        write_varint(size);
+        buffer_.insert(buffer_.end(), data, data + size);
+    }
+
+    void write_tag(int field, int wire_type) {
+        write_varint((static_cast<uint64_t>(field) << 3) | static_cast<uint64_t>(wire_type));
+    }
+
+    void write_varint(uint64_t value) {
+        while (value >= 0x80) {
+            buffer_.push_back(static_cast<uint8_t>(value | 0x80));
+            value >>= 7;
+        }
+        buffer_.push_back(static_cast<uint8_t>(value));
+    }
+
+    std::vector<uint8_t> buffer_;
+};
+
+/**
+ * @brief protobuf线格式读取器
+ */
+class ProtoReader {
+public:
+    ProtoReader(const uint8_t* data, size_t size) : data_(data), size_(size) {}
+
+    bool next(int& field, int& wire_type) {
+        if (position_ >= size_) {
+            return false;
+        }
+        uint64_t tag = read_varint();
+        field = static_cast<int>(tag >> 3);
+        wire_type = static_cast<int>(tag & 7);
+        return true;
+    }
+
+    uint64_t read_varint() {
+        uint64_t result = 0;
+        for (int shift = 0; shift < 64; shift += 7) {
+            uint8_t byte = *take(1);
+            result |= static_cast<uint64_t>(byte & 0x7F) << shift;
+            if ((byte & 0x80) == 0) {
+                return result;
+            }
+        }
+        malformed("malformed varint");
+    }
+
+    std::string read_string() {
+        size_t length = read_length();
+        const uint8_t* data = take(length);
+        return std::string(reinterpret_cast<const char*>(data), length);
+    }
+
+    std::vector<uint8_t> read_bytes() {
+        size_t length = read_length();
+        const uint8_t* data = take(length);
+        return std::vector<uint8_t>(data, data + length);
+    }
+
+    void skip(int wire_type) {
+        switch (wire_type) {
+            case 0:
+                read_varint();
+                break;
+            case 1:
+                take(8);
+                break;
+            case 2:
+                take(read_length());
+                break;
+            case 5:
+                take(4);
+                break;
+            default:
+                malformed("unsupported wire type");
+        }
+    }
+
+private:
+    size_t read_length() {
+        uint64_t length = read_varint();
+        if (length > size_) {
+            malformed("length out of range");
+        }
+        return static_cast<size_t>(length);
+    }
+
+    const uint8_t* take(size_t count) {
+        if (size_ - position_ < count) {
+            malformed("unexpected end of protobuf message");
+        }
+        const uint8_t* data = data_ + position_;
+        position_ += count;
+        return data;
+    }
+
+    const uint8_t* data_;
+    size_t size_;
+    size_t position_ = 0;
+};
+
+void read_metadata_entry(ProtoReader& reader, std::map<std::string, std::string>& metadata) {
+    std::vector<uint8_t> bytes = reader.read_bytes();
+    ProtoReader entry(bytes.data(), bytes.size());
+    std::string key;
+    std::string value;
+    int field = 0;
+    int wire_type = 0;
+    while (entry.next(field, wire_type)) {
+        if (field == 1 && wire_type == 2) {
+            key = entry.read_string();
+        } else if (field == 2 && wire_type == 2) {
+            value = entry.read_string();
+        } else {
+            entry.skip(wire_type);
+        }
+    }
+    metadata[key] = value;
+}
+
+}  // namespace
+
+std::vector<uint8_t> RpcRequest::encode() const {
+    ProtoWriter writer;
+    writer.write_string(1, request_id);
+    writer.write_string(2, service_name);
+    writer.write_string(3, method_name);
+    writer.write_bytes(4, payload);
+    writer.write_metadata(5, metadata);
+    writer.write_varint_field(6, static_cast<uint64_t>(frame_type));
+    return writer.take();
+}
+
+RpcRequest RpcRequest::decode(const std::vector<uint8_t>& data) {
+    RpcRequest request;
+    ProtoReader reader(data.data(), data.size());
+    int field = 0;
+    int wire_type = 0;
+    while (reader.next(field, wire_type)) {
+        if (field == 6 && wire_type == 0) {
+            request.frame_type = static_cast<FrameType>(reader.read_varint());
+            continue;
+        }
+        if (wire_type != 2) {
+            reader.skip(wire_type);
+            continue;
+        }
+        switch (field) {
+            case 1:
+                request.request_id = reader.read_string();
+                break;
+            case 2:
+                request.service_name = reader.read_string();
+                break;
+            case 3:
+                request.method_name = reader.read_string();
+                break;
+            case 4:
+                request.payload = reader.read_bytes();
+                break;
+            case 5:
+                read_metadata_entry(reader, request.metadata);
+                break;
+            default:
+                reader.skip(wire_type);
+        }
+    }
+    return request;
+}
+
+std::vector<uint8_t> RpcResponse::encode() const {
+    ProtoWriter writer;
+    writer.write_string(1, request_id);
+    writer.write_varint_field(2, success ? 1 : 0);
+    writer.write_bytes(3, payload);
+    writer.write_string(4, error_message);
+    writer.write_metadata(5, metadata);
+    writer.write_varint_field(6, static_cast<uint64_t>(error_code));
+    return writer.take();
+}
+
+RpcResponse RpcResponse::decode(const std::vector<uint8_t>& data) {
+    RpcResponse response;
+    ProtoReader reader(data.data(), data.size());
+    int field = 0;
+    int wire_type = 0;
+    while (reader.next(field, wire_type)) {
+        if (field == 1 && wire_type == 2) {
+            response.request_id = reader.read_string();
+        } else if (field == 2 && wire_type == 0) {
+            response.success = reader.read_varint() != 0;
+        } else if (field == 3 && wire_type == 2) {
+            response.payload = reader.read_bytes();
+        } else if (field == 4 && wire_type == 2) {
+            response.error_message = reader.read_string();
+        } else if (field == 5 && wire_type == 2) {
+            read_metadata_entry(reader, response.metadata);
+        } else if (field == 6 && wire_type == 0) {
+            response.error_code = static_cast<RpcErrorCode>(reader.read_varint());
+        } else {
+            reader.skip(wire_type);
+        }
+    }
+    return response;
+}
+
+void BincodeWriter::write_f32(float value) {
+    uint32_t bits = 0;
+    std::memcpy(&bits, &value, sizeof(bits));
+    write_le(bits, 4);
+}
+
+void BincodeWriter::write_f64(double value) {
+    uint64_t bits = 0;
+    std::memcpy(&bits, &value, sizeof(bits));
+    write_le(bits, 8);
+}
+
+void BincodeWriter::write_string(const std::string& value) {
+    write_u64(value.size());
+    buffer_.insert(buffer_.end(), value.begin(), value.end());
+}
+
+void BincodeWriter::write_bytes(const std::vector<uint8_t>& value) {
+    write_u64(value.size());
+    buffer_.insert(buffer_.end(), value.begin(), value.end());
+}
+
+void BincodeWriter::write_le(uint64_t value, size_t size) {
+    for (size_t i = 0; i < size; ++i) {
+        buffer_.push_back(static_cast<uint8_t>(value >> (8 * i)));
+    }
+}
+
+bool BincodeReader::read_bool() {
+    switch (*take(1)) {
+        case 0:
+            return false;
+        case 1:
+            return true;
+        default:
+            malformed("invalid bool value");
+    }
+}
+
+float BincodeReader::read_f32() {
+    uint32_t bits = static_cast<uint32_t>(read_le(4));
+    float value = 0;
+    std::memcpy(&value, &bits, sizeof(value));
+    return value;
+}
+
+double BincodeReader::read_f64() {
+    uint64_t bits = read_le(8);
+    double value = 0;
+    std::memcpy(&value, &bits, sizeof(value));
+    return value;
+}
+
+std::string BincodeReader::read_string() {
+    size_t length = read_length();
+    const uint8_t* data = take(length);
+    return std::string(reinterpret_cast<const char*>(data), length);
+}
+
+std::vector<uint8_t> BincodeReader::read_bytes() {
+    size_t length = read_length();
+    const uint8_t* data = take(length);
+    return std::vector<uint8_t>(data, data + length);
+}
+
+uint64_t BincodeReader::read_le(size_t size) {
+    const uint8_t* data = take(size);
+    uint64_t value = 0;
+    for (size_t i = 0; i < size; ++i) {
+        value |= static_cast<uint64_t>(data[i]) << (8 * i);
+    }
+    return value;
+}
+
+size_t BincodeReader::read_length() {
+    uint64_t length = read_u64();
+    if (length > data_.size()) {
+        malformed("length out of range");
+    }
+    return static_cast<size_t>(length);
+}
+
+const uint8_t* BincodeReader::take(size_t count) {
+    if (data_.size() - position_ < count) {
+        malformed("unexpected end of bincode payload");
+    }
+    const uint8_t* data = data_.data() + position_;
+    position_ += count;
+    return data;
+}
+
+RpcClient::RpcClient(std::shared_ptr<RpcChannel> channel) : channel_(std::move(channel)) {
+    std::random_device random;
+    id_prefix_ = "cpp-" + std::to_string(random()) + "-";
+}
+
+std::vector<uint8_t> RpcClient::call(const std::string& service, const std::string& method, std::vector<uint8_t> payload) {
+    std::lock_guard<std::mutex> lock(mutex_);
+
+    RpcRequest request;
+    request.request_id = id_prefix_ + std::to_string(next_id_++);
+    request.service_name = service;
+    request.method_name = method;
+    request.payload = std::move(payload);
+    channel_->send(request.encode());
+
+    while (true) {
+        std::vector<uint8_t> frame = channel_->receive();
+        RpcResponse response;
+        try {
+            response = RpcResponse::decode(frame);
+        } catch (const RpcError&) {
+            // 无法解析的帧直接丢弃
+            continue;
+        }
+
+        if (response.request_id != request.request_id) {
+            continue;
+        }
+        if (!response.success) {
+            throw RpcError(response.error_code, response.error_message);
+        }
+        return std::move(response.payload);
+    }
+}
+
+void RpcClient::notify(const std::string& service, const std::string& method, std::vector<uint8_t> payload) {
+    std::lock_guard<std::mutex> lock(mutex_);
+
+    RpcRequest request;
+    request.service_name = service;
+    request.method_name = method;
+    request.payload = std::move(payload);
+    request.frame_type = FrameType::Notification;
+    channel_->send(request.encode());
+}
+
+}  // namespace generated
+}  // namespace softbus
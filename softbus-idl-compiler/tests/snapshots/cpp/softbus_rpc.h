@@ -0,0 +1,253 @@
+// 由softbus-idl-compiler自动生成，请勿手动修改
+// SoftBus RPC运行时：RpcRequest/RpcResponse帧编解码、bincode序列化与同步调用
+
+#ifndef SOFTBUS_GENERATED_SOFTBUS_RPC_H
+#define SOFTBUS_GENERATED_SOFTBUS_RPC_H
+
+#include <algorithm>
+#include <cstddef>
+#include <cstdint>
+#include <map>
+#include <memory>
+#include <mutex>
+#include <stdexcept>
+#include <string>
+#include <unordered_map>
+#include <utility>
+#include <vector>
+
+namespace softbus {
+namespace generated {
+
+/**
+ * @brief RPC错误码，与rpc.proto中的RpcErrorCode一致
+ */
+enum class RpcErrorCode : int32_t {
+    Ok = 0,
+    Timeout = 1,
+    ServiceNotFound = 2,
+    MethodNotFound = 3,
+    SerializationError = 4,
+    NetworkError = 5,
+    InternalError = 6,
+    Cancelled = 7,
+    Unauthenticated = 8,
+};
+
+/**
+ * @brief 帧类型，与rpc.proto中的FrameType一致
+ */
+enum class FrameType : int32_t {
+    Unary = 0,
+    StreamOpen = 1,
+    StreamData = 2,
+    StreamEnd = 3,
+    StreamCancel = 4,
+    StreamCredit = 5,
+    Notification = 6,
+    Cancel = 7,
+};
+
+/**
+ * @brief RPC调用失败
+ */
+class RpcError : public std::runtime_error {
+public:
+    RpcError(RpcErrorCode code, const std::string& message)
+        : std::runtime_error(message), code_(code) {}
+
+    RpcErrorCode code() const noexcept { return code_; }
+
+private:
+    RpcErrorCode code_;
+};
+
+/**
+ * @brief RPC请求帧
+ */
+struct RpcRequest {
+    std::string request_id;
+    std::string service_name;
+    std::string method_name;
+    std::vector<uint8_t> payload;
+    std::map<std::string, std::string> metadata;
+    FrameType frame_type = FrameType::Unary;
+
+    /**
+     * @brief 按protobuf线格式编码
+     */
+    std::vector<uint8_t> encode() const;
+
+    /**
+     * @brief 从protobuf线格式解码，未知字段会被跳过
+     * @throw RpcError 数据格式错误
+     */
+    static RpcRequest decode(const std::vector<uint8_t>& data);
+};
+
+/**
+ * @brief RPC响应帧
+ */
+struct RpcResponse {
+    std::string request_id;
+    bool success = false;
+    std::vector<uint8_t> payload;
+    std::string error_message;
+    std::map<std::string, std::string> metadata;
+    RpcErrorCode error_code = RpcErrorCode::Ok;
+
+    /**
+     * @brief 按protobuf线格式编码
+     */
+    std::vector<uint8_t> encode() const;
+
+    /**
+     * @brief 从protobuf线格式解码，未知字段会被跳过
+     * @throw RpcError 数据格式错误
+     */
+    static RpcResponse decode(const std::vector<uint8_t>& data);
+};
+
+/**
+ * @brief 与Rust端bincode 1.x默认配置兼容的写入器
+ */
+class BincodeWriter {
+public:
+    void write_bool(bool value) { buffer_.push_back(value ? 1 : 0); }
+    void write_i32(int32_t value) { write_le(static_cast<uint32_t>(value), 4); }
+    void write_i64(int64_t value) { write_le(static_cast<uint64_t>(value), 8); }
+    void write_u32(uint32_t value) { write_le(value, 4); }
+    void write_u64(uint64_t value) { write_le(value, 8); }
+    void write_f32(float value);
+    void write_f64(double value);
+    void write_string(const std::string& value);
+    void write_bytes(const std::vector<uint8_t>& value);
+
+    template <typename T, typename F>
+    void write_list(const std::vector<T>& list, F write_item) {
+        write_u64(list.size());
+        for (const auto& item : list) {
+            write_item(*this, item);
+        }
+    }
+
+    template <typename K, typename V, typename FK, typename FV>
+    void write_map(const std::unordered_map<K, V>& map, FK write_key, FV write_value) {
+        write_u64(map.size());
+        for (const auto& entry : map) {
+            write_key(*this, entry.first);
+            write_value(*this, entry.second);
+        }
+    }
+
+    /**
+     * @brief 取出已写入的数据
+     */
+    std::vector<uint8_t> take() { return std::move(buffer_); }
+
+private:
+    void write_le(uint64_t value, size_t size);
+
+    std::vector<uint8_t> buffer_;
+};
+
+/**
+ * @brief 与Rust端bincode 1.x默认配置兼容的读取器
+ * @throw RpcError 数据不足或格式错误
+ */
+class BincodeReader {
+public:
+    explicit BincodeReader(std::vector<uint8_t> data) : data_(std::move(data)) {}
+
+    bool read_bool();
+    int32_t read_i32() { return static_cast<int32_t>(read_le(4)); }
+    int64_t read_i64() { return static_cast<int64_t>(read_le(8)); }
+    uint32_t read_u32() { return static_cast<uint32_t>(read_le(4)); }
+    uint64_t read_u64() { return read_le(8); }
+    float read_f32();
+    double read_f64();
+    std::string read_string();
+    std::vector<uint8_t> read_bytes();
+
+    template <typename F>
+    auto read_list(F read_item) -> std::vector<decltype(read_item(std::declval<BincodeReader&>()))> {
+        using T = decltype(read_item(std::declval<BincodeReader&>()));
+        size_t count = read_length();
+        std::vector<T> list;
+        list.reserve(std::min<size_t>(count, 1024));
+        for (size_t i = 0; i < count; ++i) {
+            list.push_back(read_item(*this));
+        }
+        return list;
+    }
+
+    template <typename FK, typename FV>
+    auto read_map(FK read_key, FV read_value)
+        -> std::unordered_map<decltype(read_key(std::declval<BincodeReader&>())),
+                              decltype(read_value(std::declval<BincodeReader&>()))> {
+        std::unordered_map<decltype(read_key(std::declval<BincodeReader&>())),
+                           decltype(read_value(std::declval<BincodeReader&>()))> map;
+        size_t count = read_length();
+        for (size_t i = 0; i < count; ++i) {
+            auto key = read_key(*this);
+            map.insert_or_assign(std::move(key), read_value(*this));
+        }
+        return map;
+    }
+
+private:
+    uint64_t read_le(size_t size);
+    size_t read_length();
+    const uint8_t* take(size_t count);
+
+    std::vector<uint8_t> data_;
+    size_t position_ = 0;
+};
+
+/**
+ * @brief 承载RPC帧的双向通道，每次收发一个完整帧
+ */
+class RpcChannel {
+public:
+    virtual ~RpcChannel() = default;
+
+    virtual void send(const std::vector<uint8_t>& frame) = 0;
+
+    /**
+     * @brief 接收下一帧，通道关闭或超时时应抛出异常
+     */
+    virtual std::vector<uint8_t> receive() = 0;
+};
+
+/**
+ * @brief 同步RPC客户端
+ *
+ * 同一时间只有一个调用在途，收到的响应按请求ID匹配，过期响应被丢弃。
+ */
+class RpcClient {
+public:
+    explicit RpcClient(std::shared_ptr<RpcChannel> channel);
+
+    /**
+     * @brief 发送请求并等待对应的响应
+     * @return 响应载荷
+     * @throw RpcError 远端返回错误
+     */
+    std::vector<uint8_t> call(const std::string& service, const std::string& method, std::vector<uint8_t> payload);
+
+    /**
+     * @brief 调用单向方法：只发送请求，不等待响应
+     */
+    void notify(const std::string& service, const std::string& method, std::vector<uint8_t> payload);
+
+private:
+    std::shared_ptr<RpcChannel> channel_;
+    std::mutex mutex_;
+    std::string id_prefix_;
+    uint64_t next_id_ = 0;
+};
+
+}  // namespace generated
+}  // namespace softbus
+
+#endif // SOFTBUS_GENERATED_SOFTBUS_RPC_H
@@ -0,0 +1,108 @@
+// 由softbus-idl-compiler自动生成，请勿手动修改
+// 服务: CameraService
+
+#nullable enable
+
+using System;
+using System.Collections.Generic;
+using System.Threading;
+using System.Threading.Tasks;
+
+namespace SoftBus.Generated
+{
+    /// <summary>CameraService服务接口</summary>
+    public interface ICameraService
+    {
+        Task<bool> OpenAsync(int cameraId, CancellationToken cancellationToken = default);
+
+        Task<bool> CloseAsync(CancellationToken cancellationToken = default);
+
+        Task<byte[]> CaptureAsync(CancellationToken cancellationToken = default);
+
+        Task<List<string>> ListCamerasAsync(CancellationToken cancellationToken = default);
+
+        Task<bool> SetParameterAsync(string key, string value, CancellationToken cancellationToken = default);
+
+        Task<CameraInfo> GetCameraInfoAsync(int cameraId, CancellationToken cancellationToken = default);
+
+        Task<bool> SetPixelFormatAsync(int cameraId, PixelFormat format, CancellationToken cancellationToken = default);
+
+        Task SetFlashAsync(bool enabled, CancellationToken cancellationToken = default);
+    }
+
+    /// <summary>CameraService客户端代理，通过RpcClient调用远端服务</summary>
+    public sealed class CameraServiceProxy : ICameraService
+    {
+        /// <summary>服务名称</summary>
+        public const string ServiceName = "CameraService";
+
+        private readonly RpcClient _client;
+
+        public CameraServiceProxy(RpcClient client)
+        {
+            _client = client;
+        }
+
+        public async Task<bool> OpenAsync(int cameraId, CancellationToken cancellationToken = default)
+        {
+            var writer = new BincodeWriter();
+            writer.WriteI32(cameraId);
+            var reader = await _client.CallAsync(ServiceName, "open", writer.ToArray(), cancellationToken).ConfigureAwait(false);
+            return reader.ReadBool();
+        }
+
+        public async Task<bool> CloseAsync(CancellationToken cancellationToken = default)
+        {
+            var writer = new BincodeWriter();
+            var reader = await _client.CallAsync(ServiceName, "close", writer.ToArray(), cancellationToken).ConfigureAwait(false);
+            return reader.ReadBool();
+        }
+
+        public async Task<byte[]> CaptureAsync(CancellationToken cancellationToken = default)
+        {
+            var writer = new BincodeWriter();
+            var reader = await _client.CallAsync(ServiceName, "capture", writer.ToArray(), cancellationToken).ConfigureAwait(false);
+            return reader.ReadBytes();
+        }
+
+        public async Task<List<string>> ListCamerasAsync(CancellationToken cancellationToken = default)
+        {
+            var writer = new BincodeWriter();
+            var reader = await _client.CallAsync(ServiceName, "list_cameras", writer.ToArray(), cancellationToken).ConfigureAwait(false);
+            return reader.ReadList(r1 => r1.ReadString());
+        }
+
+        public async Task<bool> SetParameterAsync(string key, string value, CancellationToken cancellationToken = default)
+        {
+            var writer = new BincodeWriter();
+            writer.WriteString(key);
+            writer.WriteString(value);
+            var reader = await _client.CallAsync(ServiceName, "set_parameter", writer.ToArray(), cancellationToken).ConfigureAwait(false);
+            return reader.ReadBool();
+        }
+
+        public async Task<CameraInfo> GetCameraInfoAsync(int cameraId, CancellationToken cancellationToken = default)
+        {
+            var writer = new BincodeWriter();
+            writer.WriteI32(cameraId);
+            var reader = await _client.CallAsync(ServiceName, "get_camera_info", writer.ToArray(), cancellationToken).ConfigureAwait(false);
+            return CameraInfoCodec.Decode(reader);
+        }
+
+        public async Task<bool> SetPixelFormatAsync(int cameraId, PixelFormat format, CancellationToken cancellationToken = default)
+        {
+            var writer = new BincodeWriter();
+            writer.WriteI32(cameraId);
+            PixelFormatCodec.Encode(writer, format);
+            var reader = await _client.CallAsync(ServiceName, "set_pixel_format", writer.ToArray(), cancellationToken).ConfigureAwait(false);
+            return reader.ReadBool();
+        }
+
+        public async Task SetFlashAsync(bool enabled, CancellationToken cancellationToken = default)
+        {
+            var writer = new BincodeWriter();
+            writer.WriteBool(enabled);
+            await _client.NotifyAsync(ServiceName, "set_flash", writer.ToArray(), cancellationToken).ConfigureAwait(false);
+        }
+    }
+}
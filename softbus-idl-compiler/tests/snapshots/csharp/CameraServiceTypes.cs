@@ -0,0 +1,97 @@
+// 由softbus-idl-compiler自动生成，请勿手动修改
+// 类型声明: camera_service
+
+#nullable enable
+
+using System;
+using System.Collections.Generic;
+using System.IO;
+
+namespace SoftBus.Generated
+{
+    /// <summary>PixelFormat枚举</summary>
+    public enum PixelFormat
+    {
+        Rgb888 = 0,
+        Yuv420,
+        Nv21,
+        Jpeg,
+    }
+
+    /// <summary>PixelFormat的bincode编解码，按成员序号传输</summary>
+    public static class PixelFormatCodec
+    {
+        public static void Encode(BincodeWriter writer, PixelFormat value)
+        {
+            writer.WriteU32(value switch
+            {
+                PixelFormat.Rgb888 => 0u,
+                PixelFormat.Yuv420 => 1u,
+                PixelFormat.Nv21 => 2u,
+                PixelFormat.Jpeg => 3u,
+                _ => throw new ArgumentOutOfRangeException(nameof(value)),
+            });
+        }
+
+        public static PixelFormat Decode(BincodeReader reader) => reader.ReadU32() switch
+        {
+            0u => PixelFormat.Rgb888,
+            1u => PixelFormat.Yuv420,
+            2u => PixelFormat.Nv21,
+            3u => PixelFormat.Jpeg,
+            var index => throw new InvalidDataException($"invalid PixelFormat index {index}"),
+        };
+    }
+
+    /// <summary>Resolution结构体</summary>
+    public class Resolution
+    {
+        public uint Width { get; set; }
+        public uint Height { get; set; }
+    }
+
+    /// <summary>Resolution的bincode编解码，按字段声明顺序传输</summary>
+    public static class ResolutionCodec
+    {
+        public static void Encode(BincodeWriter writer, Resolution value)
+        {
+            writer.WriteU32(value.Width);
+            writer.WriteU32(value.Height);
+        }
+
+        public static Resolution Decode(BincodeReader reader) => new Resolution
+        {
+            Width = reader.ReadU32(),
+            Height = reader.ReadU32(),
+        };
+    }
+
+    /// <summary>CameraInfo结构体</summary>
+    public class CameraInfo
+    {
+        public int Id { get; set; }
+        public string Name { get; set; } = "";
+        public Resolution Resolution { get; set; } = new();
+        public List<PixelFormat> Formats { get; set; } = new();
+    }
+
+    /// <summary>CameraInfo的bincode编解码，按字段声明顺序传输</summary>
+    public static class CameraInfoCodec
+    {
+        public static void Encode(BincodeWriter writer, CameraInfo value)
+        {
+            writer.WriteI32(value.Id);
+            writer.WriteString(value.Name);
+            ResolutionCodec.Encode(writer, value.Resolution);
+            writer.WriteList(value.Formats, (w1, v1) => PixelFormatCodec.Encode(w1, v1));
+        }
+
+        public static CameraInfo Decode(BincodeReader reader) => new CameraInfo
+        {
+            Id = reader.ReadI32(),
+            Name = reader.ReadString(),
+            Resolution = ResolutionCodec.Decode(reader),
+            Formats = reader.ReadList(r1 => PixelFormatCodec.Decode(r1)),
+        };
+    }
+}
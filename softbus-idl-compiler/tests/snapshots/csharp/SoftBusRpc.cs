@@ -0,0 +1,618 @@
+// 由softbus-idl-compiler自动生成，请勿手动修改
+// SoftBus RPC运行时：RpcRequest/RpcResponse帧编解码、bincode序列化与调用复用
+
+#nullable enable
+
+using System;
+using System.Buffers.Binary;
+using System.Collections.Concurrent;
+using System.Collections.Generic;
+using System.IO;
+using System.Text;
+using System.Threading;
+using System.Threading.Tasks;
+
+namespace SoftBus.Generated
+{
+    /// <summary>RPC错误码，与rpc.proto中的RpcErrorCode一致</summary>
+    public enum RpcErrorCode
+    {
+        Ok = 0,
+        Timeout = 1,
+        ServiceNotFound = 2,
+        MethodNotFound = 3,
+        SerializationError = 4,
+        NetworkError = 5,
+        InternalError = 6,
+        Cancelled = 7,
+        Unauthenticated = 8,
+    }
+
+    /// <summary>RPC调用失败</summary>
+    public class RpcException : Exception
+    {
+        public RpcErrorCode Code { get; }
+
+        public RpcException(RpcErrorCode code, string message) : base(message)
+        {
+            Code = code;
+        }
+    }
+
+    /// <summary>承载RPC帧的双向通道，每次收发一个完整帧</summary>
+    public interface IRpcChannel
+    {
+        Task SendAsync(byte[] frame, CancellationToken cancellationToken);
+
+        Task<byte[]> ReceiveAsync(CancellationToken cancellationToken);
+    }
+
+    /// <summary>帧类型，与rpc.proto中的FrameType一致</summary>
+    public enum FrameType
+    {
+        Unary = 0,
+        StreamOpen = 1,
+        StreamData = 2,
+        StreamEnd = 3,
+        StreamCancel = 4,
+        StreamCredit = 5,
+        Notification = 6,
+        Cancel = 7,
+    }
+
+    /// <summary>RPC请求帧</summary>
+    public sealed class RpcRequest
+    {
+        public string RequestId { get; set; } = "";
+        public string ServiceName { get; set; } = "";
+        public string MethodName { get; set; } = "";
+        public byte[] Payload { get; set; } = Array.Empty<byte>();
+        public Dictionary<string, string> Metadata { get; set; } = new();
+        public FrameType FrameType { get; set; } = FrameType.Unary;
+
+        /// <summary>按protobuf线格式编码</summary>
+        public byte[] Encode()
+        {
+            var writer = new ProtoWriter();
+            writer.WriteString(1, RequestId);
+            writer.WriteString(2, ServiceName);
+            writer.WriteString(3, MethodName);
+            writer.WriteBytes(4, Payload);
+            foreach (var entry in Metadata)
+            {
+                var item = new ProtoWriter();
+                item.WriteString(1, entry.Key);
+                item.WriteString(2, entry.Value);
+                writer.WriteMessage(5, item.ToArray());
+            }
+            writer.WriteVarintField(6, (ulong)FrameType);
+            return writer.ToArray();
+        }
+    }
+
+    /// <summary>RPC响应帧</summary>
+    public sealed class RpcResponse
+    {
+        public string RequestId { get; set; } = "";
+        public bool Success { get; set; }
+        public byte[] Payload { get; set; } = Array.Empty<byte>();
+        public string ErrorMessage { get; set; } = "";
+        public Dictionary<string, string> Metadata { get; set; } = new();
+        public RpcErrorCode ErrorCode { get; set; }
+
+        /// <summary>从protobuf线格式解码，未知字段会被跳过</summary>
+        public static RpcResponse Decode(byte[] data)
+        {
+            var response = new RpcResponse();
+            var reader = new ProtoReader(data);
+            while (reader.TryReadTag(out var field, out var wireType))
+            {
+                switch (field, wireType)
+                {
+                    case (1, 2):
+                        response.RequestId = reader.ReadString();
+                        break;
+                    case (2, 0):
+                        response.Success = reader.ReadVarint() != 0;
+                        break;
+                    case (3, 2):
+                        response.Payload = reader.ReadBytes();
+                        break;
+                    case (4, 2):
+                        response.ErrorMessage = reader.ReadString();
+                        break;
+                    case (5, 2):
+                        DecodeMetadataEntry(new ProtoReader(reader.ReadBytes()), response.Metadata);
+                        break;
+                    case (6, 0):
+                        response.ErrorCode = (RpcErrorCode)(int)reader.ReadVarint();
+                        break;
+                    default:
+                        reader.Skip(wireType);
+                        break;
+                }
+            }
+            return response;
+        }
+
+        private static void DecodeMetadataEntry(ProtoReader reader, Dictionary<string, string> metadata)
+        {
+            var key = "";
+            var value = "";
+            while (reader.TryReadTag(out var field, out var wireType))
+            {
+                switch (field, wireType)
+                {
+                    case (1, 2):
+                        key = reader.ReadString();
+                        break;
+                    case (2, 2):
+                        value = reader.ReadString();
+                        break;
+                    default:
+                        reader.Skip(wireType);
+                        break;
+                }
+            }
+            metadata[key] = value;
+        }
+    }
+
+    /// <summary>protobuf线格式写入器，省略取默认值的标量字段</summary>
+    internal sealed class ProtoWriter
+    {
+        private readonly MemoryStream _stream = new();
+
+        public byte[] ToArray() => _stream.ToArray();
+
+        public void WriteString(int field, string value)
+        {
+            if (value.Length > 0)
+            {
+                WriteMessage(field, Encoding.UTF8.GetBytes(value));
+            }
+        }
+
+        public void WriteBytes(int field, byte[] value)
+        {
+            if (value.Length > 0)
+            {
+                WriteMessage(field, value);
+            }
+        }
+
+        public void WriteVarintField(int field, ulong value)
+        {
+            if (value != 0)
+            {
+                WriteVarint((ulong)(field << 3));
+                WriteVarint(value);
+            }
+        }
+
+        /// <summary>写入长度前缀字段，即使内容为空</summary>
+        public void WriteMessage(int field, byte[] value)
+        {
+            WriteVarint((ulong)((field << 3) | 2));
+            WriteVarint((ulong)value.Length);
+            _stream.Write(value, 0, value.Length);
+        }
+
+        private void WriteVarint(ulong value)
+        {
+            while (value >= 0x80)
+            {
+                _stream.WriteByte((byte)(value | 0x80));
+                value >>= 7;
+            }
+            _stream.WriteByte((byte)value);
+        }
+    }
+
+    /// <summary>protobuf线格式读取器</summary>
+    internal sealed class ProtoReader
+    {
+        private readonly byte[] _data;
+        private int _position;
+
+        public ProtoReader(byte[] data)
+        {
+            _data = data;
+        }
+
+        public bool TryReadTag(out int field, out int wireType)
+        {
+            if (_position >= _data.Length)
+            {
+                field = 0;
+                wireType = 0;
+                return false;
+            }
+
+            var tag = ReadVarint();
+            field = (int)(tag >> 3);
+            wireType = (int)(tag & 7);
+            return true;
+        }
+
+        public ulong ReadVarint()
+        {
+            ulong result = 0;
+            for (var shift = 0; shift < 64; shift += 7)
+            {
+                var b = Take(1)[0];
+                result |= (ulong)(b & 0x7F) << shift;
+                if ((b & 0x80) == 0)
+                {
+                    return result;
+                }
+            }
+            throw new InvalidDataException("malformed varint");
+        }
+
+        public byte[] ReadBytes() => Take(ReadLength()).ToArray();
+
+        public string ReadString() => Encoding.UTF8.GetString(Take(ReadLength()));
+
+        public void Skip(int wireType)
+        {
+            switch (wireType)
+            {
+                case 0:
+                    ReadVarint();
+                    break;
+                case 1:
+                    Take(8);
+                    break;
+                case 2:
+                    Take(ReadLength());
+                    break;
+                case 5:
+                    Take(4);
+                    break;
+                default:
+                    throw new InvalidDataException($"unsupported wire type {wireType}");
+            }
+        }
+
+        private int ReadLength()
+        {
+            var length = ReadVarint();
+            if (length > int.MaxValue)
+            {
+                throw new InvalidDataException("length out of range");
+            }
+            return (int)length;
+        }
+
+        private ReadOnlySpan<byte> Take(int count)
+        {
+            if (_data.Length - _position < count)
+            {
+                throw new InvalidDataException("unexpected end of protobuf message");
+            }
+            var span = new ReadOnlySpan<byte>(_data, _position, count);
+            _position += count;
+            return span;
+        }
+    }
+
+    /// <summary>与Rust端bincode 1.x默认配置兼容的写入器</summary>
+    public sealed class BincodeWriter
+    {
+        private readonly MemoryStream _stream = new();
+
+        public byte[] ToArray() => _stream.ToArray();
+
+        public void WriteBool(bool value) => _stream.WriteByte(value ? (byte)1 : (byte)0);
+
+        public void WriteI32(int value)
+        {
+            Span<byte> buffer = stackalloc byte[4];
+            BinaryPrimitives.WriteInt32LittleEndian(buffer, value);
+            _stream.Write(buffer);
+        }
+
+        public void WriteI64(long value)
+        {
+            Span<byte> buffer = stackalloc byte[8];
+            BinaryPrimitives.WriteInt64LittleEndian(buffer, value);
+            _stream.Write(buffer);
+        }
+
+        public void WriteU32(uint value)
+        {
+            Span<byte> buffer = stackalloc byte[4];
+            BinaryPrimitives.WriteUInt32LittleEndian(buffer, value);
+            _stream.Write(buffer);
+        }
+
+        public void WriteU64(ulong value)
+        {
+            Span<byte> buffer = stackalloc byte[8];
+            BinaryPrimitives.WriteUInt64LittleEndian(buffer, value);
+            _stream.Write(buffer);
+        }
+
+        public void WriteF32(float value)
+        {
+            Span<byte> buffer = stackalloc byte[4];
+            BinaryPrimitives.WriteSingleLittleEndian(buffer, value);
+            _stream.Write(buffer);
+        }
+
+        public void WriteF64(double value)
+        {
+            Span<byte> buffer = stackalloc byte[8];
+            BinaryPrimitives.WriteDoubleLittleEndian(buffer, value);
+            _stream.Write(buffer);
+        }
+
+        public void WriteString(string value) => WriteBytes(Encoding.UTF8.GetBytes(value));
+
+        public void WriteBytes(byte[] value)
+        {
+            WriteU64((ulong)value.Length);
+            _stream.Write(value, 0, value.Length);
+        }
+
+        public void WriteList<T>(List<T> list, Action<BincodeWriter, T> writeItem)
+        {
+            WriteU64((ulong)list.Count);
+            foreach (var item in list)
+            {
+                writeItem(this, item);
+            }
+        }
+
+        public void WriteMap<TKey, TValue>(
+            Dictionary<TKey, TValue> map,
+            Action<BincodeWriter, TKey> writeKey,
+            Action<BincodeWriter, TValue> writeValue)
+            where TKey : notnull
+        {
+            WriteU64((ulong)map.Count);
+            foreach (var entry in map)
+            {
+                writeKey(this, entry.Key);
+                writeValue(this, entry.Value);
+            }
+        }
+    }
+
+    /// <summary>与Rust端bincode 1.x默认配置兼容的读取器</summary>
+    public sealed class BincodeReader
+    {
+        private readonly byte[] _data;
+        private int _position;
+
+        public BincodeReader(byte[] data)
+        {
+            _data = data;
+        }
+
+        public bool ReadBool() => Take(1)[0] switch
+        {
+            0 => false,
+            1 => true,
+            var value => throw new InvalidDataException($"invalid bool value {value}"),
+        };
+
+        public int ReadI32() => BinaryPrimitives.ReadInt32LittleEndian(Take(4));
+
+        public long ReadI64() => BinaryPrimitives.ReadInt64LittleEndian(Take(8));
+
+        public uint ReadU32() => BinaryPrimitives.ReadUInt32LittleEndian(Take(4));
+
+        public ulong ReadU64() => BinaryPrimitives.ReadUInt64LittleEndian(Take(8));
+
+        public float ReadF32() => BinaryPrimitives.ReadSingleLittleEndian(Take(4));
+
+        public double ReadF64() => BinaryPrimitives.ReadDoubleLittleEndian(Take(8));
+
+        public string ReadString() => Encoding.UTF8.GetString(Take(ReadLength()));
+
+        public byte[] ReadBytes() => Take(ReadLength()).ToArray();
+
+        public List<T> ReadList<T>(Func<BincodeReader, T> readItem)
+        {
+            var count = ReadLength();
+            var list = new List<T>(Math.Min(count, 1024));
+            for (var i = 0; i < count; i++)
+            {
+                list.Add(readItem(this));
+            }
+            return list;
+        }
+
+        public Dictionary<TKey, TValue> ReadMap<TKey, TValue>(
+            Func<BincodeReader, TKey> readKey,
+            Func<BincodeReader, TValue> readValue)
+            where TKey : notnull
+        {
+            var count = ReadLength();
+            var map = new Dictionary<TKey, TValue>(Math.Min(count, 1024));
+            for (var i = 0; i < count; i++)
+            {
+                var key = readKey(this);
+                map[key] = readValue(this);
+            }
+            return map;
+        }
+
+        private int ReadLength()
+        {
+            var length = ReadU64();
+            if (length > int.MaxValue)
+            {
+                throw new InvalidDataException("length out of range");
+            }
+            return (int)length;
+        }
+
+        private ReadOnlySpan<byte> Take(int count)
+        {
+            if (_data.Length - _position < count)
+            {
+                throw new InvalidDataException("unexpected end of bincode payload");
+            }
+            var span = new ReadOnlySpan<byte>(_data, _position, count);
+            _position += count;
+            return span;
+        }
+    }
+
+    /// <summary>RPC客户端，按请求ID复用同一通道上的并发调用</summary>
+    public sealed class RpcClient : IAsyncDisposable
+    {
+        private readonly IRpcChannel _channel;
+        private readonly ConcurrentDictionary<string, TaskCompletionSource<RpcResponse>> _pending = new();
+        private readonly SemaphoreSlim _sendLock = new(1, 1);
+        private readonly CancellationTokenSource _shutdown = new();
+        private readonly Task _reader;
+        private volatile bool _closed;
+
+        /// <summary>单次调用超时时间</summary>
+        public TimeSpan Timeout { get; set; } = TimeSpan.FromSeconds(30);
+
+        public RpcClient(IRpcChannel channel)
+        {
+            _channel = channel;
+            _reader = Task.Run(ReadLoopAsync);
+        }
+
+        /// <summary>发送请求并等待对应的响应，返回响应载荷的读取器</summary>
+        public async Task<BincodeReader> CallAsync(
+            string service,
+            string method,
+            byte[] payload,
+            CancellationToken cancellationToken = default)
+        {
+            var request = new RpcRequest
+            {
+                RequestId = Guid.NewGuid().ToString(),
+                ServiceName = service,
+                MethodName = method,
+                Payload = payload,
+            };
+            var completion = new TaskCompletionSource<RpcResponse>(TaskCreationOptions.RunContinuationsAsynchronously);
+            _pending[request.RequestId] = completion;
+
+            try
+            {
+                if (_closed)
+                {
+                    throw new RpcException(RpcErrorCode.NetworkError, "channel closed");
+                }
+
+                using var timeout = CancellationTokenSource.CreateLinkedTokenSource(cancellationToken);
+                timeout.CancelAfter(Timeout);
+
+                RpcResponse response;
+                try
+                {
+                    await _sendLock.WaitAsync(timeout.Token).ConfigureAwait(false);
+                    try
+                    {
+                        await _channel.SendAsync(request.Encode(), timeout.Token).ConfigureAwait(false);
+                    }
+                    finally
+                    {
+                        _sendLock.Release();
+                    }
+                    response = await completion.Task.WaitAsync(timeout.Token).ConfigureAwait(false);
+                }
+                catch (OperationCanceledException) when (!cancellationToken.IsCancellationRequested)
+                {
+                    throw new RpcException(RpcErrorCode.Timeout, $"{service}.{method} timed out");
+                }
+
+                if (!response.Success)
+                {
+                    throw new RpcException(response.ErrorCode, response.ErrorMessage);
+                }
+                return new BincodeReader(response.Payload);
+            }
+            finally
+            {
+                _pending.TryRemove(request.RequestId, out _);
+            }
+        }
+
+        /// <summary>调用单向方法：只发送请求，不等待响应</summary>
+        public async Task NotifyAsync(
+            string service,
+            string method,
+            byte[] payload,
+            CancellationToken cancellationToken = default)
+        {
+            if (_closed)
+            {
+                throw new RpcException(RpcErrorCode.NetworkError, "channel closed");
+            }
+
+            var request = new RpcRequest
+            {
+                ServiceName = service,
+                MethodName = method,
+                Payload = payload,
+                FrameType = FrameType.Notification,
+            };
+            await _sendLock.WaitAsync(cancellationToken).ConfigureAwait(false);
+            try
+            {
+                await _channel.SendAsync(request.Encode(), cancellationToken).ConfigureAwait(false);
+            }
+            finally
+            {
+                _sendLock.Release();
+            }
+        }
+
+        private async Task ReadLoopAsync()
+        {
+            try
+            {
+                while (true)
+                {
+                    var frame = await _channel.ReceiveAsync(_shutdown.Token).ConfigureAwait(false);
+                    RpcResponse response;
+                    try
+                    {
+                        response = RpcResponse.Decode(frame);
+                    }
+                    catch (InvalidDataException)
+                    {
+                        // 无法解析的帧直接丢弃
+                        continue;
+                    }
+
+                    if (_pending.TryRemove(response.RequestId, out var completion))
+                    {
+                        completion.TrySetResult(response);
+                    }
+                }
+            }
+            catch (Exception e)
+            {
+                // 通道关闭后所有等待中的调用立即失败
+                _closed = true;
+                foreach (var requestId in _pending.Keys)
+                {
+                    if (_pending.TryRemove(requestId, out var completion))
+                    {
+                        completion.TrySetException(new RpcException(RpcErrorCode.NetworkError, $"channel closed: {e.Message}"));
+                    }
+                }
+            }
+        }
+
+        public async ValueTask DisposeAsync()
+        {
+            _shutdown.Cancel();
+            await _reader.ConfigureAwait(false);
+            _shutdown.Dispose();
+            _sendLock.Dispose();
+        }
+    }
+}
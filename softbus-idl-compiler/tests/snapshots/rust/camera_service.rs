@@ -0,0 +1,289 @@
+// 由softbus-idl-compiler自动生成，请勿手动修改
+// 服务: CameraService
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use softbus_core::rpc::server::{MethodHandler, RpcService};
+use softbus_core::rpc::{CallContext, RpcClient, RpcServer};
+use softbus_core::Result;
+
+/// CameraService服务名称
+pub const CAMERA_SERVICE_NAME: &str = "CameraService";
+
+/// CameraService服务接口
+#[async_trait]
+pub trait CameraService: Send + Sync + 'static {
+    async fn open(&self, ctx: &CallContext, camera_id: i32) -> Result<bool>;
+    async fn close(&self, ctx: &CallContext) -> Result<bool>;
+    async fn capture(&self, ctx: &CallContext) -> Result<Vec<u8>>;
+    async fn list_cameras(&self, ctx: &CallContext) -> Result<Vec<String>>;
+    async fn set_parameter(&self, ctx: &CallContext, key: String, value: String) -> Result<bool>;
+    async fn get_camera_info(&self, ctx: &CallContext, camera_id: i32) -> Result<CameraInfo>;
+    async fn set_pixel_format(&self, ctx: &CallContext, camera_id: i32, format: PixelFormat) -> Result<bool>;
+    async fn set_flash(&self, ctx: &CallContext, enabled: bool) -> Result<()>;
+}
+
+/// CameraService.open请求参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraServiceOpenRequest {
+    pub camera_id: i32,
+}
+
+/// CameraService.close请求参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraServiceCloseRequest {}
+
+/// CameraService.capture请求参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraServiceCaptureRequest {}
+
+/// CameraService.list_cameras请求参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraServiceListCamerasRequest {}
+
+/// CameraService.set_parameter请求参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraServiceSetParameterRequest {
+    pub key: String,
+    pub value: String,
+}
+
+/// CameraService.get_camera_info请求参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraServiceGetCameraInfoRequest {
+    pub camera_id: i32,
+}
+
+/// CameraService.set_pixel_format请求参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraServiceSetPixelFormatRequest {
+    pub camera_id: i32,
+    pub format: PixelFormat,
+}
+
+/// CameraService.set_flash请求参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraServiceSetFlashRequest {
+    pub enabled: bool,
+}
+
+/// CameraService客户端代理
+#[derive(Clone)]
+pub struct CameraServiceProxy {
+    client: Arc<RpcClient>,
+}
+
+impl CameraServiceProxy {
+    /// 基于RPC客户端创建代理
+    pub fn new(client: Arc<RpcClient>) -> Self {
+        Self { client }
+    }
+
+    pub async fn open(&self, camera_id: i32) -> Result<bool> {
+        let request = CameraServiceOpenRequest { camera_id };
+        self.client.call(CAMERA_SERVICE_NAME, "open", request).await
+    }
+
+    pub async fn close(&self) -> Result<bool> {
+        let request = CameraServiceCloseRequest {};
+        self.client.call(CAMERA_SERVICE_NAME, "close", request).await
+    }
+
+    pub async fn capture(&self) -> Result<Vec<u8>> {
+        let request = CameraServiceCaptureRequest {};
+        self.client.call(CAMERA_SERVICE_NAME, "capture", request).await
+    }
+
+    pub async fn list_cameras(&self) -> Result<Vec<String>> {
+        let request = CameraServiceListCamerasRequest {};
+        self.client.call_idempotent(CAMERA_SERVICE_NAME, "list_cameras", request).await
+    }
+
+    pub async fn set_parameter(&self, key: String, value: String) -> Result<bool> {
+        let request = CameraServiceSetParameterRequest { key, value };
+        self.client.call(CAMERA_SERVICE_NAME, "set_parameter", request).await
+    }
+
+    pub async fn get_camera_info(&self, camera_id: i32) -> Result<CameraInfo> {
+        let request = CameraServiceGetCameraInfoRequest { camera_id };
+        self.client.call_idempotent(CAMERA_SERVICE_NAME, "get_camera_info", request).await
+    }
+
+    pub async fn set_pixel_format(&self, camera_id: i32, format: PixelFormat) -> Result<bool> {
+        let request = CameraServiceSetPixelFormatRequest { camera_id, format };
+        self.client.call(CAMERA_SERVICE_NAME, "set_pixel_format", request).await
+    }
+
+    pub async fn set_flash(&self, enabled: bool) -> Result<()> {
+        let request = CameraServiceSetFlashRequest { enabled };
+        self.client.notify(CAMERA_SERVICE_NAME, "set_flash", request).await
+    }
+}
+
+/// CameraService服务端桩
+///
+/// 把`CameraService`实现适配为`RpcServer`上的方法处理器
+pub struct CameraServiceStub<T: CameraService> {
+    inner: Arc<T>,
+}
+
+impl<T: CameraService> CameraServiceStub<T> {
+    /// 包装服务实现
+    pub fn new(inner: Arc<T>) -> Self {
+        Self { inner }
+    }
+
+    /// 将服务注册到RPC服务端
+    pub fn register(&self, server: &RpcServer) {
+        server.register_service(self);
+    }
+}
+
+impl<T: CameraService> RpcService for CameraServiceStub<T> {
+    fn service_name(&self) -> &str {
+        CAMERA_SERVICE_NAME
+    }
+
+    fn method_handlers(&self) -> Vec<(String, Arc<dyn MethodHandler>)> {
+        vec![
+            (
+                "open".to_string(),
+                Arc::new(CameraServiceOpenHandler { inner: Arc::clone(&self.inner) }) as Arc<dyn MethodHandler>,
+            ),
+            (
+                "close".to_string(),
+                Arc::new(CameraServiceCloseHandler { inner: Arc::clone(&self.inner) }) as Arc<dyn MethodHandler>,
+            ),
+            (
+                "capture".to_string(),
+                Arc::new(CameraServiceCaptureHandler { inner: Arc::clone(&self.inner) }) as Arc<dyn MethodHandler>,
+            ),
+            (
+                "list_cameras".to_string(),
+                Arc::new(CameraServiceListCamerasHandler { inner: Arc::clone(&self.inner) }) as Arc<dyn MethodHandler>,
+            ),
+            (
+                "set_parameter".to_string(),
+                Arc::new(CameraServiceSetParameterHandler { inner: Arc::clone(&self.inner) }) as Arc<dyn MethodHandler>,
+            ),
+            (
+                "get_camera_info".to_string(),
+                Arc::new(CameraServiceGetCameraInfoHandler { inner: Arc::clone(&self.inner) }) as Arc<dyn MethodHandler>,
+            ),
+            (
+                "set_pixel_format".to_string(),
+                Arc::new(CameraServiceSetPixelFormatHandler { inner: Arc::clone(&self.inner) }) as Arc<dyn MethodHandler>,
+            ),
+            (
+                "set_flash".to_string(),
+                Arc::new(CameraServiceSetFlashHandler { inner: Arc::clone(&self.inner) }) as Arc<dyn MethodHandler>,
+            ),
+        ]
+    }
+}
+
+struct CameraServiceOpenHandler<T> {
+    inner: Arc<T>,
+}
+
+#[async_trait]
+impl<T: CameraService> MethodHandler for CameraServiceOpenHandler<T> {
+    async fn handle(&self, ctx: &CallContext, request: Bytes) -> Result<Bytes> {
+        let request: CameraServiceOpenRequest = ctx.codec().decode(&request)?;
+        let response = self.inner.open(ctx, request.camera_id).await?;
+        Ok(Bytes::from(ctx.codec().encode(&response)?))
+    }
+}
+
+struct CameraServiceCloseHandler<T> {
+    inner: Arc<T>,
+}
+
+#[async_trait]
+impl<T: CameraService> MethodHandler for CameraServiceCloseHandler<T> {
+    async fn handle(&self, ctx: &CallContext, request: Bytes) -> Result<Bytes> {
+        let _: CameraServiceCloseRequest = ctx.codec().decode(&request)?;
+        let response = self.inner.close(ctx).await?;
+        Ok(Bytes::from(ctx.codec().encode(&response)?))
+    }
+}
+
+struct CameraServiceCaptureHandler<T> {
+    inner: Arc<T>,
+}
+
+#[async_trait]
+impl<T: CameraService> MethodHandler for CameraServiceCaptureHandler<T> {
+    async fn handle(&self, ctx: &CallContext, request: Bytes) -> Result<Bytes> {
+        let _: CameraServiceCaptureRequest = ctx.codec().decode(&request)?;
+        let response = self.inner.capture(ctx).await?;
+        Ok(Bytes::from(ctx.codec().encode(&response)?))
+    }
+}
+
+struct CameraServiceListCamerasHandler<T> {
+    inner: Arc<T>,
+}
+
+#[async_trait]
+impl<T: CameraService> MethodHandler for CameraServiceListCamerasHandler<T> {
+    async fn handle(&self, ctx: &CallContext, request: Bytes) -> Result<Bytes> {
+        let _: CameraServiceListCamerasRequest = ctx.codec().decode(&request)?;
+        let response = self.inner.list_cameras(ctx).await?;
+        Ok(Bytes::from(ctx.codec().encode(&response)?))
+    }
+}
+
+struct CameraServiceSetParameterHandler<T> {
+    inner: Arc<T>,
+}
+
+#[async_trait]
+impl<T: CameraService> MethodHandler for CameraServiceSetParameterHandler<T> {
+    async fn handle(&self, ctx: &CallContext, request: Bytes) -> Result<Bytes> {
+        let request: CameraServiceSetParameterRequest = ctx.codec().decode(&request)?;
+        let response = self.inner.set_parameter(ctx, request.key, request.value).await?;
+        Ok(Bytes::from(ctx.codec().encode(&response)?))
+    }
+}
+
+struct CameraServiceGetCameraInfoHandler<T> {
+    inner: Arc<T>,
+}
+
+#[async_trait]
+impl<T: CameraService> MethodHandler for CameraServiceGetCameraInfoHandler<T> {
+    async fn handle(&self, ctx: &CallContext, request: Bytes) -> Result<Bytes> {
+        let request: CameraServiceGetCameraInfoRequest = ctx.codec().decode(&request)?;
+        let response = self.inner.get_camera_info(ctx, request.camera_id).await?;
+        Ok(Bytes::from(ctx.codec().encode(&response)?))
+    }
+}
+
+struct CameraServiceSetPixelFormatHandler<T> {
+    inner: Arc<T>,
+}
+
+#[async_trait]
+impl<T: CameraService> MethodHandler for CameraServiceSetPixelFormatHandler<T> {
+    async fn handle(&self, ctx: &CallContext, request: Bytes) -> Result<Bytes> {
+        let request: CameraServiceSetPixelFormatRequest = ctx.codec().decode(&request)?;
+        let response = self.inner.set_pixel_format(ctx, request.camera_id, request.format).await?;
+        Ok(Bytes::from(ctx.codec().encode(&response)?))
+    }
+}
+
+struct CameraServiceSetFlashHandler<T> {
+    inner: Arc<T>,
+}
+
+#[async_trait]
+impl<T: CameraService> MethodHandler for CameraServiceSetFlashHandler<T> {
+    async fn handle(&self, ctx: &CallContext, request: Bytes) -> Result<Bytes> {
+        let request: CameraServiceSetFlashRequest = ctx.codec().decode(&request)?;
+        let response = self.inner.set_flash(ctx, request.enabled).await?;
+        Ok(Bytes::from(ctx.codec().encode(&response)?))
+    }
+}
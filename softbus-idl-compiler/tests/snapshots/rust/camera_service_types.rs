@@ -0,0 +1,29 @@
+// 由softbus-idl-compiler自动生成，请勿手动修改
+// 类型声明: camera_service
+//
+// 需与同一IDL生成的服务代码include!到同一模块中
+
+/// PixelFormat枚举
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum PixelFormat {
+    Rgb888 = 0,
+    Yuv420,
+    Nv21,
+    Jpeg,
+}
+
+/// Resolution结构体
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// CameraInfo结构体
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CameraInfo {
+    pub id: i32,
+    pub name: String,
+    pub resolution: Resolution,
+    pub formats: Vec<PixelFormat>,
+}
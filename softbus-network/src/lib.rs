@@ -6,6 +6,7 @@ pub mod adapter;
 pub mod ble;
 pub mod wifi;
 pub mod mdns;
+pub mod tcp;
 
 pub use adapter::NetworkAdapter;
 
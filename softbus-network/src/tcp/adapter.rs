@@ -0,0 +1,245 @@
+//! TCP适配器实现
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use async_trait::async_trait;
+use socket2::{SockRef, TcpKeepalive};
+use tokio::net::TcpStream;
+use tokio::sync::Notify;
+use crate::adapter::{NetworkAdapter, AdapterResult, AdapterError, Connection, Listener};
+use super::channel::TcpConnection;
+
+/// TCP配置
+#[derive(Debug, Clone)]
+pub struct TcpConfig {
+    /// 是否禁用Nagle算法
+    pub nodelay: bool,
+    /// TCP keepalive空闲时间，`None`表示不启用
+    pub keepalive: Option<Duration>,
+    /// 发送缓冲区大小，`None`表示使用系统默认值
+    pub send_buffer_size: Option<usize>,
+    /// 接收缓冲区大小，`None`表示使用系统默认值
+    pub recv_buffer_size: Option<usize>,
+    /// 单帧最大长度（字节）
+    pub max_frame_size: usize,
+    /// 连接超时时间
+    pub connect_timeout: Duration,
+}
+
+impl Default for TcpConfig {
+    fn default() -> Self {
+        Self {
+            nodelay: true,
+            keepalive: Some(Duration::from_secs(60)),
+            send_buffer_size: Some(64 * 1024),
+            recv_buffer_size: Some(64 * 1024),
+            max_frame_size: 16 * 1024 * 1024,
+            connect_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// TCP适配器
+pub struct TcpAdapter {
+    initialized: bool,
+    name: String,
+    config: TcpConfig,
+}
+
+impl TcpAdapter {
+    /// 创建新的TCP适配器
+    pub fn new() -> Self {
+        Self::with_config(TcpConfig::default())
+    }
+
+    /// 使用指定配置创建TCP适配器
+    pub fn with_config(config: TcpConfig) -> Self {
+        Self {
+            initialized: false,
+            name: "TCP".to_string(),
+            config,
+        }
+    }
+
+    /// 获取当前配置
+    pub fn config(&self) -> &TcpConfig {
+        &self.config
+    }
+}
+
+impl Default for TcpAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 将配置中的socket选项应用到TCP流
+fn apply_socket_options(stream: &TcpStream, config: &TcpConfig) -> AdapterResult<()> {
+    let socket = SockRef::from(stream);
+
+    socket.set_nodelay(config.nodelay)?;
+
+    if let Some(idle) = config.keepalive {
+        socket.set_tcp_keepalive(&TcpKeepalive::new().with_time(idle))?;
+    }
+
+    if let Some(size) = config.send_buffer_size {
+        socket.set_send_buffer_size(size)?;
+    }
+
+    if let Some(size) = config.recv_buffer_size {
+        socket.set_recv_buffer_size(size)?;
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl NetworkAdapter for TcpAdapter {
+    async fn initialize(&mut self) -> AdapterResult<()> {
+        tracing::info!("Initializing TCP adapter");
+        self.initialized = true;
+        Ok(())
+    }
+
+    async fn shutdown(&mut self) -> AdapterResult<()> {
+        tracing::info!("Shutting down TCP adapter");
+        self.initialized = false;
+        Ok(())
+    }
+
+    async fn connect(&self, address: &str) -> AdapterResult<Box<dyn Connection>> {
+        if !self.initialized {
+            return Err(AdapterError::NotInitialized);
+        }
+
+        tracing::info!("Connecting to TCP peer: {}", address);
+        let stream = tokio::time::timeout(self.config.connect_timeout, TcpStream::connect(address))
+            .await
+            .map_err(|_| AdapterError::ConnectionFailed(format!("Connect to {} timed out", address)))?
+            .map_err(|e| AdapterError::ConnectionFailed(format!("{}: {}", address, e)))?;
+
+        apply_socket_options(&stream, &self.config)?;
+
+        Ok(Box::new(TcpConnection::new(stream, self.config.max_frame_size)))
+    }
+
+    async fn listen(&self, address: &str) -> AdapterResult<Box<dyn Listener>> {
+        if !self.initialized {
+            return Err(AdapterError::NotInitialized);
+        }
+
+        let listener = tokio::net::TcpListener::bind(address).await?;
+        let local_address = listener.local_addr()?.to_string();
+        tracing::info!("Starting TCP listener on: {}", local_address);
+
+        Ok(Box::new(TcpListener {
+            inner: listener,
+            local_address,
+            config: self.config.clone(),
+            stopped: AtomicBool::new(false),
+            stop_notify: Notify::new(),
+        }))
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// TCP监听器
+pub struct TcpListener {
+    inner: tokio::net::TcpListener,
+    local_address: String,
+    config: TcpConfig,
+    stopped: AtomicBool,
+    stop_notify: Notify,
+}
+
+#[async_trait]
+impl Listener for TcpListener {
+    async fn accept(&self) -> AdapterResult<Box<dyn Connection>> {
+        // 先登记通知再检查标志，避免错过并发的stop
+        let stopped = self.stop_notify.notified();
+        if self.stopped.load(Ordering::Acquire) {
+            return Err(AdapterError::Other("Listener stopped".to_string()));
+        }
+
+        tokio::select! {
+            result = self.inner.accept() => {
+                let (stream, peer) = result?;
+                tracing::debug!("Accepted TCP connection from: {}", peer);
+                apply_socket_options(&stream, &self.config)?;
+                Ok(Box::new(TcpConnection::new(stream, self.config.max_frame_size)) as Box<dyn Connection>)
+            }
+            _ = stopped => Err(AdapterError::Other("Listener stopped".to_string())),
+        }
+    }
+
+    async fn stop(&self) -> AdapterResult<()> {
+        tracing::info!("Stopping TCP listener on: {}", self.local_address);
+        self.stopped.store(true, Ordering::Release);
+        self.stop_notify.notify_waiters();
+        Ok(())
+    }
+
+    fn local_address(&self) -> String {
+        self.local_address.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+
+    #[tokio::test]
+    async fn test_tcp_adapter_requires_initialization() {
+        let adapter = TcpAdapter::new();
+        assert!(matches!(
+            adapter.listen("127.0.0.1:0").await,
+            Err(AdapterError::NotInitialized)
+        ));
+        assert!(matches!(
+            adapter.connect("127.0.0.1:1").await,
+            Err(AdapterError::NotInitialized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tcp_adapter_loopback() {
+        let mut adapter = TcpAdapter::new();
+        adapter.initialize().await.unwrap();
+
+        let listener = adapter.listen("127.0.0.1:0").await.unwrap();
+        let address = listener.local_address();
+
+        let (client, server) = tokio::join!(adapter.connect(&address), listener.accept());
+        let client = client.unwrap();
+        let server = server.unwrap();
+        assert_eq!(client.peer_address(), Some(address));
+
+        client.send(Bytes::from_static(b"ping")).await.unwrap();
+        assert_eq!(server.receive().await.unwrap(), Bytes::from_static(b"ping"));
+
+        server.send(Bytes::from_static(b"pong")).await.unwrap();
+        assert_eq!(client.receive().await.unwrap(), Bytes::from_static(b"pong"));
+    }
+
+    #[tokio::test]
+    async fn test_tcp_listener_stop() {
+        let mut adapter = TcpAdapter::new();
+        adapter.initialize().await.unwrap();
+
+        let listener = adapter.listen("127.0.0.1:0").await.unwrap();
+        let (accepted, stopped) = tokio::join!(listener.accept(), listener.stop());
+
+        stopped.unwrap();
+        assert!(accepted.is_err());
+        assert!(listener.accept().await.is_err());
+    }
+}
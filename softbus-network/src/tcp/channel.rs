@@ -0,0 +1,160 @@
+//! TCP连接实现
+//!
+//! 每条消息使用4字节大端长度前缀分帧，保证每次`receive`恰好返回一条完整消息。
+
+use async_trait::async_trait;
+use bytes::{BufMut, Bytes, BytesMut};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use crate::adapter::{Connection, AdapterResult, AdapterError};
+
+/// 帧头长度（字节）
+pub const FRAME_HEADER_LEN: usize = 4;
+
+/// TCP连接
+///
+/// 读写两端分别加锁，一个任务阻塞在`receive`时其他任务仍可`send`。
+/// `receive`不是取消安全的：在`select!`中被取消可能丢失半帧数据，
+/// 需要超时的调用方应在独立任务中循环读取。
+pub struct TcpConnection {
+    reader: Mutex<OwnedReadHalf>,
+    writer: Mutex<OwnedWriteHalf>,
+    peer_address: Option<String>,
+    max_frame_size: usize,
+}
+
+impl TcpConnection {
+    /// 基于已建立的TCP流创建连接
+    pub fn new(stream: TcpStream, max_frame_size: usize) -> Self {
+        let peer_address = stream.peer_addr().ok().map(|addr| addr.to_string());
+        let (reader, writer) = stream.into_split();
+
+        Self {
+            reader: Mutex::new(reader),
+            writer: Mutex::new(writer),
+            peer_address,
+            max_frame_size,
+        }
+    }
+
+    /// 获取允许的最大帧长度
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+}
+
+#[async_trait]
+impl Connection for TcpConnection {
+    async fn send(&self, data: Bytes) -> AdapterResult<()> {
+        if data.len() > self.max_frame_size {
+            return Err(AdapterError::SendFailed(format!(
+                "Frame too large: {} > {}",
+                data.len(),
+                self.max_frame_size
+            )));
+        }
+
+        // 帧头和负载合并为一次写入，避免Nagle关闭时产生两个小包
+        let mut frame = BytesMut::with_capacity(FRAME_HEADER_LEN + data.len());
+        frame.put_u32(data.len() as u32);
+        frame.put_slice(&data);
+
+        let mut writer = self.writer.lock().await;
+        writer
+            .write_all(&frame)
+            .await
+            .map_err(|e| AdapterError::SendFailed(e.to_string()))?;
+        writer
+            .flush()
+            .await
+            .map_err(|e| AdapterError::SendFailed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn receive(&self) -> AdapterResult<Bytes> {
+        let mut reader = self.reader.lock().await;
+
+        let mut header = [0u8; FRAME_HEADER_LEN];
+        reader.read_exact(&mut header).await.map_err(|e| match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => {
+                AdapterError::ReceiveFailed("Connection closed by peer".to_string())
+            }
+            _ => AdapterError::ReceiveFailed(e.to_string()),
+        })?;
+
+        let len = u32::from_be_bytes(header) as usize;
+        if len > self.max_frame_size {
+            return Err(AdapterError::ReceiveFailed(format!(
+                "Frame too large: {} > {}",
+                len, self.max_frame_size
+            )));
+        }
+
+        let mut payload = vec![0u8; len];
+        reader
+            .read_exact(&mut payload)
+            .await
+            .map_err(|e| AdapterError::ReceiveFailed(e.to_string()))?;
+
+        Ok(Bytes::from(payload))
+    }
+
+    async fn close(&self) -> AdapterResult<()> {
+        let mut writer = self.writer.lock().await;
+        writer.shutdown().await?;
+        Ok(())
+    }
+
+    fn peer_address(&self) -> Option<String> {
+        self.peer_address.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn connected_pair(max_frame_size: usize) -> (TcpConnection, TcpConnection) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let (client, server) = tokio::join!(TcpStream::connect(addr), listener.accept());
+
+        (
+            TcpConnection::new(client.unwrap(), max_frame_size),
+            TcpConnection::new(server.unwrap().0, max_frame_size),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_tcp_connection_framing() {
+        let (client, server) = connected_pair(1024).await;
+
+        client.send(Bytes::from_static(b"hello")).await.unwrap();
+        client.send(Bytes::new()).await.unwrap();
+        client.send(Bytes::from_static(b"world")).await.unwrap();
+
+        assert_eq!(server.receive().await.unwrap(), Bytes::from_static(b"hello"));
+        assert_eq!(server.receive().await.unwrap(), Bytes::new());
+        assert_eq!(server.receive().await.unwrap(), Bytes::from_static(b"world"));
+    }
+
+    #[tokio::test]
+    async fn test_tcp_connection_rejects_oversized_frame() {
+        let (client, _server) = connected_pair(4).await;
+
+        let result = client.send(Bytes::from_static(b"too long")).await;
+        assert!(matches!(result, Err(AdapterError::SendFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_tcp_connection_close() {
+        let (client, server) = connected_pair(1024).await;
+
+        client.close().await.unwrap();
+        assert!(matches!(server.receive().await, Err(AdapterError::ReceiveFailed(_))));
+    }
+}
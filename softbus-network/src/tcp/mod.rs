@@ -0,0 +1,7 @@
+//! TCP传输模块
+
+pub mod adapter;
+pub mod channel;
+
+pub use adapter::{TcpAdapter, TcpConfig, TcpListener};
+pub use channel::TcpConnection;
@@ -0,0 +1,49 @@
+//! TCP模块测试
+
+use bytes::Bytes;
+use softbus_network::tcp::TcpAdapter;
+use softbus_network::NetworkAdapter;
+
+#[tokio::test]
+async fn test_tcp_adapter_lifecycle() {
+    let mut adapter = TcpAdapter::new();
+
+    // 初始化
+    adapter.initialize().await.unwrap();
+    assert!(adapter.is_initialized());
+    assert_eq!(adapter.name(), "TCP");
+
+    // 关闭
+    adapter.shutdown().await.unwrap();
+    assert!(!adapter.is_initialized());
+}
+
+#[tokio::test]
+async fn test_tcp_adapter_message_boundaries() {
+    let mut adapter = TcpAdapter::new();
+    adapter.initialize().await.unwrap();
+
+    let listener = adapter.listen("127.0.0.1:0").await.unwrap();
+    let address = listener.local_address();
+
+    let server = tokio::spawn(async move {
+        let conn = listener.accept().await.unwrap();
+        // 回显直到对端关闭
+        while let Ok(message) = conn.receive().await {
+            conn.send(message).await.unwrap();
+        }
+    });
+
+    let client = adapter.connect(&address).await.unwrap();
+    let large = Bytes::from(vec![0xAB; 256 * 1024]);
+    for message in [Bytes::from_static(b"a"), large.clone(), Bytes::from_static(b"bc")] {
+        client.send(message).await.unwrap();
+    }
+
+    assert_eq!(client.receive().await.unwrap(), Bytes::from_static(b"a"));
+    assert_eq!(client.receive().await.unwrap(), large);
+    assert_eq!(client.receive().await.unwrap(), Bytes::from_static(b"bc"));
+
+    client.close().await.unwrap();
+    server.await.unwrap();
+}